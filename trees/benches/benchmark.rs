@@ -1,13 +1,13 @@
 // for tree_size in (10,000, 40,000, 70,000, 100,000, 130,000) do:
-// Start by creating an empty tree.
-// Values with tree_size are inserted into the tree.
+// Start by building a tree of the values below tree_size with from_sorted_iter.
 // A search is conducted for the (tree_size/10) lowest values.
 // end
+// The B-tree and B+ tree have no sorted build, their search benchmarks insert the values instead.
+// The build benchmarks time building a whole tree of tree_size sorted values at once.
 
-use lib::{rb_tree::RedBlackTree, tree::Tree, avl_tree::AvlTree, node::Node};
+use lib::{rb_tree::RedBlackTree, tree::Tree, avl_tree::AvlTree, splay_tree::SplayTree, treap::Treap, scapegoat_tree::ScapegoatTree, weight_balanced_tree::WeightBalancedTree, b_tree::BTree, b_plus_tree::BPlusTree};
 
-use std::iter;
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::{criterion_group, criterion_main, Criterion};
 
 //---------- RB Tree ---------------
 fn bench_rb_tree_insert(c: &mut Criterion){
@@ -37,15 +37,10 @@ fn bench_rb_tree_search(c: &mut Criterion){
 
     let mut group = c.benchmark_group("bench_rb_tree_search");
 
-    //create an empty tree
-    let mut rb_tree = RedBlackTree::new();
-
     //for 10k elements
     for tree_size in [10000, 40000, 70000 , 100000, 130000].iter() {
-        //insert the values into the tree without benching
-        for i in 0..=*tree_size {
-            rb_tree.insert(i);
-        }
+        //build the tree from the sorted values without benching
+        let rb_tree = RedBlackTree::from_sorted_iter(0..*tree_size);
 
         //bench the search to the tree_size/10 lowest values
         group.bench_with_input(
@@ -55,7 +50,7 @@ fn bench_rb_tree_search(c: &mut Criterion){
                 b.iter(|| {
                     //search for the lowest values
                     for i in 0..tree_size/10 {
-                        rb_tree.bst_search(i);
+                        rb_tree.bst_search(&i);
                     }
                 })
             },
@@ -65,6 +60,26 @@ fn bench_rb_tree_search(c: &mut Criterion){
     group.finish();
 }
 
+fn bench_rb_tree_build(c: &mut Criterion){
+
+    let mut group = c.benchmark_group("bench_rb_tree_build");
+
+    for tree_size in [10000, 40000, 70000 , 100000, 130000].iter() {
+        group.bench_with_input(
+            criterion::BenchmarkId::new("RB_tree_build", tree_size),
+            tree_size,
+            |b, &tree_size| {
+                b.iter(|| {
+                    //build a whole tree from sorted values
+                    RedBlackTree::from_sorted_iter(0..tree_size)
+                })
+            },
+        );
+
+    }
+    group.finish();
+}
+
 //---------- AVL Tree ---------------
 fn bench_avl_tree_insert(c: &mut Criterion){
 
@@ -94,14 +109,9 @@ fn bench_avl_tree_search(c: &mut Criterion){
     
         let mut group = c.benchmark_group("bench_avl_tree_search");
     
-        //create an empty tree
-        let mut avl_tree = AvlTree::new();
-
         for tree_size in [10000, 40000, 70000 , 100000, 130000].iter() {
-            //insert the values into the tree without benching
-            for i in 0..*tree_size {
-                avl_tree.insert(i);
-            }
+            //build the tree from the sorted values without benching
+            let avl_tree = AvlTree::from_sorted_iter(0..*tree_size);
 
             //bench the search to the tree_size/10 lowest values
             group.bench_with_input(
@@ -111,7 +121,7 @@ fn bench_avl_tree_search(c: &mut Criterion){
                     b.iter(|| {
                         //search for the lowest values
                         for i in 0..tree_size/10 {
-                            avl_tree.bst_search(i);
+                            avl_tree.bst_search(&i);
                         }
                     })
                 },
@@ -121,5 +131,524 @@ fn bench_avl_tree_search(c: &mut Criterion){
         group.finish();
 }
 
-criterion_group!(benches, bench_rb_tree_insert, bench_rb_tree_search, bench_avl_tree_insert, bench_avl_tree_search);
+fn bench_avl_tree_build(c: &mut Criterion){
+
+    let mut group = c.benchmark_group("bench_avl_tree_build");
+
+    for tree_size in [10000, 40000, 70000 , 100000, 130000].iter() {
+        group.bench_with_input(
+            criterion::BenchmarkId::new("AVL_tree_build", tree_size),
+            tree_size,
+            |b, &tree_size| {
+                b.iter(|| {
+                    //build a whole tree from sorted values
+                    AvlTree::from_sorted_iter(0..tree_size)
+                })
+            },
+        );
+
+    }
+    group.finish();
+}
+
+//---------- Splay Tree ---------------
+fn bench_splay_tree_insert(c: &mut Criterion){
+
+    let mut group = c.benchmark_group("bench_splay_tree_insert");
+
+    //create an empty tree
+    let mut splay_tree = SplayTree::new();
+
+    for tree_size in [10000, 40000, 70000 , 100000, 130000].iter() {
+        group.bench_with_input(
+            criterion::BenchmarkId::new("Splay_tree_insert", tree_size),
+            tree_size,
+            |b, &tree_size| {
+                b.iter(|| {
+                    //insert values into the tree
+                    splay_tree.insert(tree_size);
+                })
+            },
+        );
+
+    }
+    group.finish();
+}
+
+fn bench_splay_tree_search(c: &mut Criterion){
+
+    let mut group = c.benchmark_group("bench_splay_tree_search");
+
+    for tree_size in [10000, 40000, 70000 , 100000, 130000].iter() {
+        //build the tree from the sorted values without benching
+        let mut splay_tree = SplayTree::from_sorted_iter(0..*tree_size);
+
+        //bench the search to the tree_size/10 lowest values, each one splays the key it finds
+        group.bench_with_input(
+            criterion::BenchmarkId::new("Splay_tree_search", tree_size),
+            tree_size,
+            |b, &tree_size| {
+                b.iter(|| {
+                    //search for the lowest values
+                    for i in 0..tree_size/10 {
+                        splay_tree.search(&i);
+                    }
+                })
+            },
+        );
+
+    }
+    group.finish();
+}
+
+fn bench_splay_tree_build(c: &mut Criterion){
+
+    let mut group = c.benchmark_group("bench_splay_tree_build");
+
+    for tree_size in [10000, 40000, 70000 , 100000, 130000].iter() {
+        group.bench_with_input(
+            criterion::BenchmarkId::new("Splay_tree_build", tree_size),
+            tree_size,
+            |b, &tree_size| {
+                b.iter(|| {
+                    //build a whole tree from sorted values
+                    SplayTree::from_sorted_iter(0..tree_size)
+                })
+            },
+        );
+
+    }
+    group.finish();
+}
+
+//---------- Treap ---------------
+fn bench_treap_insert(c: &mut Criterion){
+
+    let mut group = c.benchmark_group("bench_treap_insert");
+
+    //create an empty tree, its priorities come from the default seed
+    let mut treap = Treap::new();
+
+    for tree_size in [10000, 40000, 70000 , 100000, 130000].iter() {
+        group.bench_with_input(
+            criterion::BenchmarkId::new("Treap_insert", tree_size),
+            tree_size,
+            |b, &tree_size| {
+                b.iter(|| {
+                    //insert values into the tree
+                    treap.insert(tree_size);
+                })
+            },
+        );
+
+    }
+    group.finish();
+}
+
+fn bench_treap_search(c: &mut Criterion){
+
+    let mut group = c.benchmark_group("bench_treap_search");
+
+    for tree_size in [10000, 40000, 70000 , 100000, 130000].iter() {
+        //build the tree from the sorted values without benching
+        let treap = Treap::from_sorted_iter(0..*tree_size);
+
+        //bench the search to the tree_size/10 lowest values
+        group.bench_with_input(
+            criterion::BenchmarkId::new("Treap_search", tree_size),
+            tree_size,
+            |b, &tree_size| {
+                b.iter(|| {
+                    //search for the lowest values
+                    for i in 0..tree_size/10 {
+                        treap.bst_search(&i);
+                    }
+                })
+            },
+        );
+
+    }
+    group.finish();
+}
+
+fn bench_treap_build(c: &mut Criterion){
+
+    let mut group = c.benchmark_group("bench_treap_build");
+
+    for tree_size in [10000, 40000, 70000 , 100000, 130000].iter() {
+        group.bench_with_input(
+            criterion::BenchmarkId::new("Treap_build", tree_size),
+            tree_size,
+            |b, &tree_size| {
+                b.iter(|| {
+                    //build a whole tree from sorted values
+                    Treap::from_sorted_iter(0..tree_size)
+                })
+            },
+        );
+
+    }
+    group.finish();
+}
+
+//---------- Scapegoat Tree ---------------
+fn bench_scapegoat_tree_insert(c: &mut Criterion){
+
+    let mut group = c.benchmark_group("bench_scapegoat_tree_insert");
+
+    //create an empty tree of the default alpha
+    let mut scapegoat_tree = ScapegoatTree::new();
+
+    for tree_size in [10000, 40000, 70000 , 100000, 130000].iter() {
+        group.bench_with_input(
+            criterion::BenchmarkId::new("Scapegoat_tree_insert", tree_size),
+            tree_size,
+            |b, &tree_size| {
+                b.iter(|| {
+                    //insert values into the tree
+                    scapegoat_tree.insert(tree_size);
+                })
+            },
+        );
+
+    }
+    group.finish();
+}
+
+fn bench_scapegoat_tree_search(c: &mut Criterion){
+
+    let mut group = c.benchmark_group("bench_scapegoat_tree_search");
+
+    for tree_size in [10000, 40000, 70000 , 100000, 130000].iter() {
+        //build the tree from the sorted values without benching
+        let scapegoat_tree = ScapegoatTree::from_sorted_iter(0..*tree_size);
+
+        //bench the search to the tree_size/10 lowest values
+        group.bench_with_input(
+            criterion::BenchmarkId::new("Scapegoat_tree_search", tree_size),
+            tree_size,
+            |b, &tree_size| {
+                b.iter(|| {
+                    //search for the lowest values
+                    for i in 0..tree_size/10 {
+                        scapegoat_tree.bst_search(&i);
+                    }
+                })
+            },
+        );
+
+    }
+    group.finish();
+}
+
+fn bench_scapegoat_tree_build(c: &mut Criterion){
+
+    let mut group = c.benchmark_group("bench_scapegoat_tree_build");
+
+    for tree_size in [10000, 40000, 70000 , 100000, 130000].iter() {
+        group.bench_with_input(
+            criterion::BenchmarkId::new("Scapegoat_tree_build", tree_size),
+            tree_size,
+            |b, &tree_size| {
+                b.iter(|| {
+                    //build a whole tree from sorted values
+                    ScapegoatTree::from_sorted_iter(0..tree_size)
+                })
+            },
+        );
+
+    }
+    group.finish();
+}
+
+fn bench_scapegoat_tree_alpha(c: &mut Criterion){
+
+    let mut group = c.benchmark_group("bench_scapegoat_tree_alpha");
+
+    //ascending keys are the worst case for rebalancing, compare how often each alpha pays for it
+    for alpha in [0.55, 0.7, 0.85].iter() {
+        group.bench_with_input(
+            criterion::BenchmarkId::new("Scapegoat_tree_alpha", alpha),
+            alpha,
+            |b, &alpha| {
+                b.iter(|| {
+                    //insert ascending values into a fresh tree
+                    let mut scapegoat_tree = ScapegoatTree::with_alpha(alpha);
+                    for i in 0..10000 {
+                        scapegoat_tree.insert(i);
+                    }
+                    scapegoat_tree
+                })
+            },
+        );
+
+    }
+    group.finish();
+}
+
+//---------- Weight-Balanced Tree ---------------
+fn bench_weight_balanced_tree_insert(c: &mut Criterion){
+
+    let mut group = c.benchmark_group("bench_weight_balanced_tree_insert");
+
+    //create an empty tree of the default alpha
+    let mut weight_balanced_tree = WeightBalancedTree::new();
+
+    for tree_size in [10000, 40000, 70000 , 100000, 130000].iter() {
+        group.bench_with_input(
+            criterion::BenchmarkId::new("Weight_balanced_tree_insert", tree_size),
+            tree_size,
+            |b, &tree_size| {
+                b.iter(|| {
+                    //insert values into the tree
+                    weight_balanced_tree.insert(tree_size);
+                })
+            },
+        );
+
+    }
+    group.finish();
+}
+
+fn bench_weight_balanced_tree_search(c: &mut Criterion){
+
+    let mut group = c.benchmark_group("bench_weight_balanced_tree_search");
+
+    for tree_size in [10000, 40000, 70000 , 100000, 130000].iter() {
+        //build the tree from the sorted values without benching
+        let weight_balanced_tree = WeightBalancedTree::from_sorted_iter(0..*tree_size);
+
+        //bench the search to the tree_size/10 lowest values
+        group.bench_with_input(
+            criterion::BenchmarkId::new("Weight_balanced_tree_search", tree_size),
+            tree_size,
+            |b, &tree_size| {
+                b.iter(|| {
+                    //search for the lowest values
+                    for i in 0..tree_size/10 {
+                        weight_balanced_tree.bst_search(&i);
+                    }
+                })
+            },
+        );
+
+    }
+    group.finish();
+}
+
+fn bench_weight_balanced_tree_build(c: &mut Criterion){
+
+    let mut group = c.benchmark_group("bench_weight_balanced_tree_build");
+
+    for tree_size in [10000, 40000, 70000 , 100000, 130000].iter() {
+        group.bench_with_input(
+            criterion::BenchmarkId::new("Weight_balanced_tree_build", tree_size),
+            tree_size,
+            |b, &tree_size| {
+                b.iter(|| {
+                    //build a whole tree from sorted values
+                    WeightBalancedTree::from_sorted_iter(0..tree_size)
+                })
+            },
+        );
+
+    }
+    group.finish();
+}
+
+fn bench_weight_balanced_tree_alpha(c: &mut Criterion){
+
+    let mut group = c.benchmark_group("bench_weight_balanced_tree_alpha");
+
+    //ascending keys are the worst case for rebalancing, compare how often each alpha pays for it
+    for alpha in [0.2, 0.25, 0.29].iter() {
+        group.bench_with_input(
+            criterion::BenchmarkId::new("Weight_balanced_tree_alpha", alpha),
+            alpha,
+            |b, &alpha| {
+                b.iter(|| {
+                    //insert ascending values into a fresh tree
+                    let mut weight_balanced_tree = WeightBalancedTree::with_alpha(alpha);
+                    for i in 0..10000 {
+                        weight_balanced_tree.insert(i);
+                    }
+                    weight_balanced_tree
+                })
+            },
+        );
+
+    }
+    group.finish();
+}
+
+//---------- B-Tree ---------------
+fn bench_b_tree_insert(c: &mut Criterion){
+
+    let mut group = c.benchmark_group("bench_b_tree_insert");
+
+    //create an empty tree of the default order
+    let mut b_tree = BTree::new();
+
+    for tree_size in [10000, 40000, 70000 , 100000, 130000].iter() {
+        group.bench_with_input(
+            criterion::BenchmarkId::new("B_tree_insert", tree_size),
+            tree_size,
+            |b, &tree_size| {
+                b.iter(|| {
+                    //insert values into the tree
+                    b_tree.insert(tree_size);
+                })
+            },
+        );
+
+    }
+    group.finish();
+}
+
+fn bench_b_tree_search(c: &mut Criterion){
+
+    let mut group = c.benchmark_group("bench_b_tree_search");
+
+    for tree_size in [10000, 40000, 70000 , 100000, 130000].iter() {
+        //insert the sorted values without benching
+        let b_tree: BTree<i32> = (0..*tree_size).collect();
+
+        //bench the search to the tree_size/10 lowest values
+        group.bench_with_input(
+            criterion::BenchmarkId::new("B_tree_search", tree_size),
+            tree_size,
+            |b, &tree_size| {
+                b.iter(|| {
+                    //search for the lowest values
+                    for i in 0..tree_size/10 {
+                        b_tree.contains(&i);
+                    }
+                })
+            },
+        );
+
+    }
+    group.finish();
+}
+
+fn bench_b_tree_build(c: &mut Criterion){
+
+    let mut group = c.benchmark_group("bench_b_tree_build");
+
+    for tree_size in [10000, 40000, 70000 , 100000, 130000].iter() {
+        group.bench_with_input(
+            criterion::BenchmarkId::new("B_tree_build", tree_size),
+            tree_size,
+            |b, &tree_size| {
+                b.iter(|| {
+                    //insert the sorted values one by one, there is no bottom-up build
+                    (0..tree_size).collect::<BTree<i32>>()
+                })
+            },
+        );
+
+    }
+    group.finish();
+}
+
+//---------- B+ Tree ---------------
+fn bench_b_plus_tree_insert(c: &mut Criterion){
+
+    let mut group = c.benchmark_group("bench_b_plus_tree_insert");
+
+    //create an empty tree of the default order
+    let mut b_plus_tree = BPlusTree::new();
+
+    for tree_size in [10000, 40000, 70000 , 100000, 130000].iter() {
+        group.bench_with_input(
+            criterion::BenchmarkId::new("B_plus_tree_insert", tree_size),
+            tree_size,
+            |b, &tree_size| {
+                b.iter(|| {
+                    //insert values into the tree
+                    b_plus_tree.insert(tree_size);
+                })
+            },
+        );
+
+    }
+    group.finish();
+}
+
+fn bench_b_plus_tree_search(c: &mut Criterion){
+
+    let mut group = c.benchmark_group("bench_b_plus_tree_search");
+
+    for tree_size in [10000, 40000, 70000 , 100000, 130000].iter() {
+        //insert the sorted values without benching
+        let b_plus_tree: BPlusTree<i32> = (0..*tree_size).collect();
+
+        //bench the search to the tree_size/10 lowest values
+        group.bench_with_input(
+            criterion::BenchmarkId::new("B_plus_tree_search", tree_size),
+            tree_size,
+            |b, &tree_size| {
+                b.iter(|| {
+                    //search for the lowest values
+                    for i in 0..tree_size/10 {
+                        b_plus_tree.contains(&i);
+                    }
+                })
+            },
+        );
+
+    }
+    group.finish();
+}
+
+fn bench_b_plus_tree_build(c: &mut Criterion){
+
+    let mut group = c.benchmark_group("bench_b_plus_tree_build");
+
+    for tree_size in [10000, 40000, 70000 , 100000, 130000].iter() {
+        group.bench_with_input(
+            criterion::BenchmarkId::new("B_plus_tree_build", tree_size),
+            tree_size,
+            |b, &tree_size| {
+                b.iter(|| {
+                    //insert the sorted values one by one, there is no bottom-up build
+                    (0..tree_size).collect::<BPlusTree<i32>>()
+                })
+            },
+        );
+
+    }
+    group.finish();
+}
+
+fn bench_b_plus_tree_range(c: &mut Criterion){
+
+    let mut group = c.benchmark_group("bench_b_plus_tree_range");
+
+    for tree_size in [10000, 40000, 70000 , 100000, 130000].iter() {
+        //insert the sorted values without benching
+        let b_plus_tree: BPlusTree<i32> = (0..*tree_size).collect();
+
+        //bench a scan over the tree_size/10 lowest values, which follows the leaf links
+        group.bench_with_input(
+            criterion::BenchmarkId::new("B_plus_tree_range", tree_size),
+            tree_size,
+            |b, &tree_size| {
+                b.iter(|| {
+                    b_plus_tree.range(0..tree_size/10).count()
+                })
+            },
+        );
+
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_rb_tree_insert, bench_rb_tree_search, bench_rb_tree_build, bench_avl_tree_insert, bench_avl_tree_search, bench_avl_tree_build,
+    bench_splay_tree_insert, bench_splay_tree_search, bench_splay_tree_build,
+    bench_treap_insert, bench_treap_search, bench_treap_build,
+    bench_scapegoat_tree_insert, bench_scapegoat_tree_search, bench_scapegoat_tree_build, bench_scapegoat_tree_alpha,
+    bench_weight_balanced_tree_insert, bench_weight_balanced_tree_search, bench_weight_balanced_tree_build, bench_weight_balanced_tree_alpha,
+    bench_b_tree_insert, bench_b_tree_search, bench_b_tree_build,
+    bench_b_plus_tree_insert, bench_b_plus_tree_search, bench_b_plus_tree_build, bench_b_plus_tree_range);
 criterion_main!(benches);
\ No newline at end of file
@@ -0,0 +1,272 @@
+use std::cmp::min;
+use crate::tree::*;
+use crate::node::*;
+use crate::arena::Arena;
+use crate::cli::CLIPrintable;
+use crate::comparator::{Comparator, NaturalOrder};
+use crate::validate::ValidationError;
+
+type MaybeAaTree = Option<NodeId>;
+
+// the balancing data of an AA node is its level, leaves are on level 1
+pub type AaTreeNode<T, V = ()> = LinkedNode<T, V, usize>;
+
+// AA tree (Andersson). A red-black tree in disguise where only right children can be red, kept
+// as a level per node instead of a colour: a left child is always one level down and a right
+// child one level down or on the same level. All rebalancing is done by two operations, `skew`
+// and `split`, and only a leaf is ever unlinked, so deleting has no cases to tell apart.
+pub struct AaTree<T, V = (), C = NaturalOrder> {
+    root: MaybeAaTree,
+    nodes: Arena<AaTreeNode<T, V>>,
+    compare: C,
+}
+
+impl<T, V, C: Comparator<T>> Tree<T, V> for AaTree<T, V, C> {
+    type Node = AaTreeNode<T, V>;
+    type Compare = C;
+
+    fn with_comparator(compare: C) -> Self {
+        Self { root: None, nodes: Arena::new(), compare }
+    }
+
+    fn comparator(&self) -> &C {
+        &self.compare
+    }
+
+    fn get_root(&self) -> MaybeAaTree {
+        self.root
+    }
+
+    fn set_root(&mut self, node: MaybeAaTree) {
+        self.root = node;
+    }
+
+    fn nodes(&self) -> &Arena<AaTreeNode<T, V>> {
+        &self.nodes
+    }
+
+    fn nodes_mut(&mut self) -> &mut Arena<AaTreeNode<T, V>> {
+        &mut self.nodes
+    }
+
+    fn insert_fix(&mut self, node: NodeId) -> NodeId {
+        // a new node is a leaf
+        self.set_level(node, 1);
+        self.fix_above(node)
+    }
+
+    fn build_fix(&mut self, _node: NodeId, _depth: usize, _levels: usize) {
+        // `link_sorted` sets the levels as it links the nodes
+    }
+
+    fn link_sorted(&mut self, ids: &[NodeId], _depth: usize, _levels: usize) -> MaybeAaTree {
+        // a perfectly balanced tree can have left children on the same level, a 2-3 tree can't
+        self.link_2_3(ids, height_2_3(ids.len()))
+    }
+
+    fn join_nodes(&mut self, left: MaybeAaTree, node: NodeId, right: MaybeAaTree) -> NodeId {
+        let left_level = self.level_of(left);
+        let right_level = self.level_of(right);
+        if left_level == right_level {
+            self.set_children(node, left, right);
+            self.set_level(node, left_level + 1);
+            self.update_size(node);
+            return node;
+        }
+        let (side, tall, short, short_level) = if left_level > right_level {
+            (Side::Right, left.unwrap(), right, right_level)
+        } else {
+            (Side::Left, right.unwrap(), left, left_level)
+        };
+
+        // walk down the inner edge of the taller side to the first subtree on the same level as the
+        // shorter side, the node takes its place one level up over both of them
+        let mut parent = tall;
+        let mut child = self.get_child(parent, side);
+        while self.level_of(child) > short_level {
+            parent = child.unwrap();
+            child = self.get_child(parent, side);
+        }
+
+        match side {
+            Side::Right => self.set_children(node, child, short),
+            _ => self.set_children(node, short, child),
+        }
+        self.set_level(node, short_level + 1);
+        self.set_child(parent, side, Some(node));
+        self.set_parent(node, Some(side), Some(parent));
+        self.refresh_sizes(Some(node));
+
+        // the node may now be a horizontal link going the wrong way, same as a new leaf
+        self.fix_above(node)
+    }
+
+    fn remove_node(&mut self, node: NodeId) -> (T, V) {
+        // a node with a left child swaps entries with its predecessor and one with only a right
+        // child with that child, either way the node taken out is a leaf on level 1
+        let leaf = match (self.left(node), self.right(node)) {
+            (Some(left), _) => self.find_max(left),
+            (None, Some(right)) => right,
+            (None, None) => node,
+        };
+        if leaf != node {
+            self.swap_entry(node, leaf);
+        }
+
+        match (self.get_parent(leaf), self.get_is_child(leaf)) {
+            (Some(parent), Some(side)) => {
+                self.set_child(parent, side, None);
+                self.refresh_sizes(Some(parent));
+                let mut top = parent;
+                let mut current = Some(parent);
+                while let Some(n) = current {
+                    top = self.delete_fix(n);
+                    current = self.get_parent(top);
+                }
+                self.root = Some(top);
+            }
+            _ => self.root = None,
+        }
+        self.nodes.remove(leaf).into_entry()
+    }
+
+    fn structure_label(&self, node: NodeId) -> String where T: std::fmt::Display {
+        format!("{} (L{})", self.get_key(node), self.level(node))
+    }
+
+    fn dot_attributes(&self, node: NodeId) -> String where T: std::fmt::Display {
+        format!("label=\"{} (L{})\"", dot_escape(self.get_key(node)), self.level(node))
+    }
+
+    fn validate<'a>(&'a self) -> Result<(), ValidationError<&'a T>> where Self::Node: 'a {
+        self.validate_bst()?;
+        let mut stack: Vec<NodeId> = self.root.into_iter().collect();
+        while let Some(node) = stack.pop() {
+            let (key, level) = (self.get_key(node), self.level(node));
+            let (left, right) = (self.left(node), self.right(node));
+            if (left.is_none() || right.is_none()) && level != 1 {
+                return Err(ValidationError::MissingChild { key, level });
+            }
+            for (side, child) in [(Side::Left, left), (Side::Right, right)] {
+                let child = match child {
+                    None => continue,
+                    Some(c) => c,
+                };
+                let child_level = self.level(child);
+                if child_level + 1 != level && (side == Side::Left || child_level != level) {
+                    return Err(ValidationError::BadLevel { key: self.get_key(child), level: child_level, parent: key, parent_level: level });
+                }
+                stack.push(child);
+            }
+            if let Some(grandchild) = right.and_then(|r| self.right(r)) {
+                if self.level(grandchild) == level {
+                    return Err(ValidationError::HorizontalRun { key: self.get_key(grandchild), grandparent: key });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// a 3-node is its smaller key with the larger one as a right child on the same level
+impl<T, V, C: Comparator<T>> TwoThreeTree<T, V> for AaTree<T, V, C> {
+    fn set_2_3_height(&mut self, node: NodeId, height: usize) {
+        self.set_level(node, height);
+    }
+
+    fn link_3_node(&mut self, low: NodeId, high: NodeId, [left, between, right]: [MaybeAaTree; 3], height: usize) -> NodeId {
+        self.set_children(high, between, right);
+        self.set_level(high, height);
+        self.update_size(high);
+        self.set_children(low, left, Some(high));
+        low
+    }
+}
+
+impl<T, V, C: Comparator<T>> AaTree<T, V, C> {
+    fn level(&self, node: NodeId) -> usize {
+        *self.nodes[node].get_balance()
+    }
+
+    fn level_of(&self, node: MaybeAaTree) -> usize {
+        node.map_or(0, |n| self.level(n))
+    }
+
+    fn set_level(&mut self, node: NodeId, level: usize) {
+        self.nodes[node].set_balance(level);
+    }
+
+    // a left child on the same level is a horizontal link going the wrong way, rotate it to the right
+    fn skew(&mut self, node: NodeId) -> NodeId {
+        match self.left(node) {
+            Some(left) if self.level(left) == self.level(node) => {
+                self.rotate(Side::Right, node);
+                left
+            }
+            _ => node,
+        }
+    }
+
+    // two horizontal links in a row, the middle node goes up a level
+    fn split(&mut self, node: NodeId) -> NodeId {
+        let right = match self.right(node) {
+            Some(r) if self.level_of(self.right(r)) == self.level(node) => r,
+            _ => return node,
+        };
+        self.rotate(Side::Left, node);
+        self.set_level(right, self.level(right) + 1);
+        right
+    }
+
+    // skew and split every node above one that changed, returning the root
+    fn fix_above(&mut self, node: NodeId) -> NodeId {
+        let mut top = node;
+        while let Some(parent) = self.get_parent(top) {
+            let skewed = self.skew(parent);
+            top = self.split(skewed);
+        }
+        top
+    }
+
+    // restore the rules at a node that lost a key below it, returning the root of its subtree
+    fn delete_fix(&mut self, node: NodeId) -> NodeId {
+        // a node more than one level above a child comes down, taking a right child on its level with it
+        let level = min(self.level_of(self.left(node)), self.level_of(self.right(node))) + 1;
+        if level < self.level(node) {
+            self.set_level(node, level);
+            if let Some(right) = self.right(node).filter(|&r| self.level(r) > level) {
+                self.set_level(right, level);
+            }
+        }
+
+        // the level that came down can leave three horizontal links in a row, with some of them
+        // going left, which takes up to three skews and two splits to straighten out
+        let node = self.skew(node);
+        if let Some(right) = self.right(node) {
+            let right = self.skew(right);
+            if let Some(further) = self.right(right) {
+                self.skew(further);
+            }
+        }
+        let node = self.split(node);
+        if let Some(right) = self.right(node) {
+            self.split(right);
+        }
+        node
+    }
+
+}
+
+impl<T, V, C> CLIPrintable for AaTree<T, V, C> {
+    fn pretty_name() -> &'static str {
+        "AA Tree"
+    }
+}
+
+tree_impls!(AaTree, AaTreeNode);
+
+impl<T, V, C: Default> Default for AaTree<T, V, C> {
+    fn default() -> Self {
+        Self { root: None, nodes: Arena::new(), compare: C::default() }
+    }
+}
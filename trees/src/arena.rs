@@ -0,0 +1,90 @@
+use std::ops::{Index, IndexMut};
+use super::node::NodeId;
+
+// Vec-backed storage for the nodes of a tree.
+// Nodes refer to each other by their index into `nodes`. Removing a node empties its slot
+// and remembers the index in `free`, so the next insert reuses it instead of growing the Vec.
+#[derive(Clone, Debug)]
+pub struct Arena<N> {
+    nodes: Vec<Option<N>>,
+    free: Vec<NodeId>,
+}
+
+impl<N> Arena<N> {
+    pub fn new() -> Self {
+        Self { nodes: Vec::new(), free: Vec::new() }
+    }
+
+    // store a node and return the index it can be found at
+    pub fn insert(&mut self, node: N) -> NodeId {
+        match self.free.pop() {
+            Some(id) => {
+                self.nodes[id] = Some(node);
+                id
+            }
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    // take a node out of the arena, its index may be handed out again afterwards
+    pub fn remove(&mut self, id: NodeId) -> N {
+        let node = self.nodes[id].take().expect("node was already removed");
+        self.free.push(id);
+        node
+    }
+
+    pub fn get(&self, id: NodeId) -> Option<&N> {
+        self.nodes.get(id).and_then(|n| n.as_ref())
+    }
+
+    pub fn get_mut(&mut self, id: NodeId) -> Option<&mut N> {
+        self.nodes.get_mut(id).and_then(|n| n.as_mut())
+    }
+
+    // mutable access to two different nodes at once
+    pub fn pair_mut(&mut self, a: NodeId, b: NodeId) -> (&mut N, &mut N) {
+        assert_ne!(a, b, "cannot borrow the same node twice");
+        let (first, second) = if a < b { (a, b) } else { (b, a) };
+        let (low, high) = self.nodes.split_at_mut(second);
+        let x = low[first].as_mut().expect("node was removed");
+        let y = high[0].as_mut().expect("node was removed");
+        if a < b { (x, y) } else { (y, x) }
+    }
+
+    // number of live nodes
+    pub fn len(&self) -> usize {
+        self.nodes.len() - self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.free.clear();
+    }
+}
+
+impl<N> Default for Arena<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N> Index<NodeId> for Arena<N> {
+    type Output = N;
+
+    fn index(&self, id: NodeId) -> &N {
+        self.nodes[id].as_ref().expect("node was removed")
+    }
+}
+
+impl<N> IndexMut<NodeId> for Arena<N> {
+    fn index_mut(&mut self, id: NodeId) -> &mut N {
+        self.nodes[id].as_mut().expect("node was removed")
+    }
+}
@@ -1,75 +1,80 @@
-use std::cell::RefCell;
-use std::cmp::max;
-use std::rc::Rc;
+use std::cmp::{max, Ordering};
 use crate::tree::*;
 use crate::node::*;
+use crate::arena::Arena;
+use crate::validate::ValidationError;
 use crate::cli::CLIPrintable;
-use std::borrow::{Borrow, BorrowMut};
+use crate::comparator::{Comparator, NaturalOrder};
 
-type MaybeAvlTree<T> = Option<Rc<RefCell<AvlTreeNode<T>>>>;
+type MaybeAvlTree = Option<NodeId>;
 
 #[derive(Debug)]
-pub struct AvlTreeNode<T> {
+pub struct AvlTreeNode<T, V = ()> {
     pub key: T,
-    pub parent: MaybeAvlTree<T>,
+    value: V,
+    pub parent: MaybeAvlTree,
     pub height: usize,
-    pub left: MaybeAvlTree<T>,
-    pub right: MaybeAvlTree<T>,
+    pub size: usize,
+    pub left: MaybeAvlTree,
+    pub right: MaybeAvlTree,
     pub is_child: Option<Side>,
-    //pub balance_factor: i8,
 }
 
-pub struct AvlTree<T> {
-    root: MaybeAvlTree<T>,
+pub struct AvlTree<T, V = (), C = NaturalOrder> {
+    root: MaybeAvlTree,
+    nodes: Arena<AvlTreeNode<T, V>>,
+    compare: C,
 }
 
-impl<T: Ord> Traversible<T> for AvlTreeNode<T> {
-    fn left(&self) -> &Option<Rc<RefCell<Self>>> {
-        return &self.left;
+impl<T, V> Traversible<T> for AvlTreeNode<T, V> {
+    fn left(&self) -> Option<NodeId> {
+        self.left
     }
-    fn right(&self) -> &Option<Rc<RefCell<Self>>> {
-        return &self.right;
+    fn right(&self) -> Option<NodeId> {
+        self.right
     }
 }
 
-impl<T: Ord + Clone> Node<T> for AvlTreeNode<T>{
+impl<T, V> Node<T, V> for AvlTreeNode<T, V>{
 
-    fn new(key: T) -> Self {
+    fn new(key: T, value: V) -> Self {
         Self{
-            key: key,
+            key,
+            value,
             left: None,
             right: None,
             parent: None,
             height: 1,
+            size: 1,
             is_child: None,
-            //balance_factor: 0,
             }
     }
 
-    fn get_key(&self) -> T {
-        self.key.clone()
+    fn get_key(&self) -> &T {
+        &self.key
     }
 
-    fn set_key(&mut self, val: T) {
-        self.key = val;
+    fn get_key_mut(&mut self) -> &mut T {
+        &mut self.key
     }
 
-    fn greater(&self, val: T) -> bool {
-        self.key > val
+    fn get_value(&self) -> &V {
+        &self.value
     }
 
-    fn equal(&self, val: T) -> bool {
-        self.key == val
+    fn get_value_mut(&mut self) -> &mut V {
+        &mut self.value
     }
 
-    fn less(&self, val: T) -> bool {
-        self.key < val
+    fn into_entry(self) -> (T, V) {
+        (self.key, self.value)
     }
 
-    fn get_child(&self, side: Side) -> MaybeAvlTree<T> {
+
+    fn get_child(&self, side: Side) -> MaybeAvlTree {
         match side {
-            Side::Left => self.left.clone(),
-            Side::Right => self.right.clone(),
+            Side::Left => self.left,
+            Side::Right => self.right,
             Side::Neither => None
         }
     }
@@ -79,7 +84,7 @@ impl<T: Ord + Clone> Node<T> for AvlTreeNode<T>{
         match &self.is_child {
             None => false,
             Some(val) => {
-                if val == &side { true } else { false }
+                val == &side
             }
         }
     }
@@ -89,15 +94,15 @@ impl<T: Ord + Clone> Node<T> for AvlTreeNode<T>{
         &self.is_child
     }
 
-    fn take_child(&mut self, side: Side) -> MaybeAvlTree<T> {
+    fn take_child(&mut self, side: Side) -> MaybeAvlTree {
         match side {
             Side::Left => self.left.take(),
-            Side::Right => self.right.take(),           
+            Side::Right => self.right.take(),
             Side::Neither => None
         }
     }
 
-    fn set_child(&mut self, side: Side, child: MaybeAvlTree<T>) {
+    fn set_child(&mut self, side: Side, child: MaybeAvlTree) {
         match side {
             Side::Left => self.left = child,
             Side::Right => self.right = child,
@@ -107,54 +112,33 @@ impl<T: Ord + Clone> Node<T> for AvlTreeNode<T>{
 
     fn is_leaf(&self) -> bool {
         // check left and right pointers to determine if this node is a leaf node
-        if let None = self.left {
-            if let None = self.right {
-                return true;
-            }
-        }
-        return false;
+        self.left.is_none() && self.right.is_none()
     }
 
-    fn get_sibling(&self) -> MaybeAvlTree<T> {
-        if let Some(p) = self.get_parent() {
-            let parent = p.as_ref().borrow_mut();
-            if self.is_child(Side::Left) {
-                parent.get_child(Side::Right)
-            } else if self.is_child(Side::Right) {
-                parent.get_child(Side::Left)
-            } else { None }
-        }
-        else { None }
+    fn get_parent(&self) -> MaybeAvlTree {
+        self.parent
     }
 
-    fn get_uncle(&self) -> MaybeAvlTree<T> {
-        if let Some(p) = self.get_parent() {
-            p.as_ref().borrow().get_sibling()
-        } else { None }
-    } 
-
-    fn get_grandparent(&self) -> MaybeAvlTree<T> {
-        if let Some(p) = self.get_parent() {
-            p.as_ref().borrow().get_parent()
-        } else { None }
-    } 
+    fn get_parent_mut(&mut self) -> &mut MaybeAvlTree {
+        &mut self.parent
+    }
 
-    fn get_parent(&self) -> MaybeAvlTree<T> {
-        self.parent.clone()
+    fn get_size(&self) -> usize {
+        self.size
     }
 
-    fn get_parent_mut(&mut self) -> &mut MaybeAvlTree<T> {
-        self.parent.borrow_mut()
+    fn set_size(&mut self, size: usize) {
+        self.size = size;
     }
 
-    fn set_parent(&mut self, is_child: Option<Side>, parent: MaybeAvlTree<T>) {
+    fn set_parent(&mut self, is_child: Option<Side>, parent: MaybeAvlTree) {
         self.parent = parent;
         self.is_child = is_child;
-    }   
+    }
 }
 
 
-impl<T: Ord + std::fmt::Debug + std::fmt::Display +Copy>  AvlTreeNode<T> {
+impl<T, V> AvlTreeNode<T, V> {
 
     fn get_height(&self) -> usize {
         self.height
@@ -164,458 +148,289 @@ impl<T: Ord + std::fmt::Debug + std::fmt::Display +Copy>  AvlTreeNode<T> {
         self.height = height;
     }
 
-    fn update_height(&mut self) {
-        let left_height: usize;
-        let right_height: usize;
-
-        if let Some(left) = self.get_child(Side::Left){
-            left_height = left.as_ref().borrow().get_height();
-        }else{left_height = 0;}
+}
 
-        if let Some(right) = self.get_child(Side::Right){
-            right_height = right.as_ref().borrow().get_height();
-        }else{right_height = 0;}
-        
-        let calculated_height = 1 + max(left_height, right_height);
-        self.set_height(calculated_height);
-    }
+impl<T, V, C: Comparator<T>> Tree<T, V> for AvlTree<T, V, C> {
+    type Node = AvlTreeNode<T, V>;
+    type Compare = C;
 
-    // Helper function to get the balance factor of a node.
-    fn get_balance_factor(&mut self) -> i8{
-
-        //declare left_height and right_height
-        let mut left_height: i8 = 0;
-        let mut right_height: i8 = 0;
-
-        if let Some(left) = self.get_child(Side::Left){
-            left_height = (left.as_ref().borrow().get_height()) as i8;
-        }else{left_height = 0;}
-
-        if let Some(right) = self.get_child(Side::Right){
-            right_height = (right.as_ref().borrow().get_height()) as i8;
-        }else{right_height = 0;}
-    
-        let calculated_balance_factor = left_height - right_height;
-        //self.set_balance_factor(calculated_balance_factor);
-        return calculated_balance_factor
-    }
-
-    fn print_inorder_node(&self) {
-        // function called recursively to traverse nodes in order and print values
-        // if this is a leaf node, print its value
-        if self.is_leaf() {
-            println!("{:?}", self.get_key());
-            return;
-        }
-        // otherwise, first go left for lower values
-        if let Some(ptr) = &self.left {
-            ptr.as_ref().borrow().print_inorder_node();
-        }
-        // then print this node's value
-        println!("{:?}", self.get_key());
-        // then go right for higher values
-        if let Some(ptr) = &self.right {
-            ptr.as_ref().borrow().print_inorder_node();
-        }
+    fn with_comparator(compare: C) -> Self {
+        Self { root: None, nodes: Arena::new(), compare }
     }
 
-    fn print_structure_node(&self, depth: usize, from: Side) {
-        // first go left...
-        if let Some(ptr) = &self.left {
-            ptr.as_ref().borrow().print_structure_node(depth + 1, Side::Left);            
-        }
-        
-        // print this node with prefix
-        // couple characters that make tree look 'smooth'
-        let smooth = match from {
-            Side::Left => "┌───────┘",
-            Side::Neither => "",
-            Side::Right => "└───────┐"
-        };
-        // case for depth to make lines line up nice
-        let space = if depth == 0 { 0 } else { (depth - 1) * 8 };
-        // print line representing this node
-        println!("{: <1$}{2}{3}", "", space, smooth, self.get_key());
-        
-        // then go right...
-        if let Some(ptr) = &self.right {
-            ptr.as_ref().borrow().print_structure_node(depth + 1, Side::Right);
-        }
+    fn comparator(&self) -> &C {
+        &self.compare
     }
 
-    fn count_leaves_node(&self) -> usize {
-        if self.is_leaf() {
-            return 1;
-        }
-        let mut count: usize = 0;
-        // otherwise, first go left for lower values
-        if let Some(ptr) = &self.left {
-            count += ptr.as_ref().borrow().count_leaves_node();
-        }
-        // then go right for higher values
-        if let Some(ptr) = &self.right {
-            count += ptr.as_ref().borrow().count_leaves_node();
-        }
-        return count;
+    fn get_root(&self) -> MaybeAvlTree {
+        self.root
     }
 
-    fn get_height_node(&self, depth: usize) -> usize {
-        // recursive helper function for get_height of tree
-        if self.is_leaf() {
-            return depth;
-        }
-        // find max depth of either branch
-        let mut m: usize = usize::MIN;
-        // otherwise, first go left for lower values
-        if let Some(ptr) = &self.left {
-            m = std::cmp::max(m, ptr.as_ref().borrow().get_height_node(depth + 1));
-        }
-        // then go right for higher values
-        if let Some(ptr) = &self.right {
-            m = std::cmp::max(m, ptr.as_ref().borrow().get_height_node(depth + 1));
+    fn set_root(&mut self, node: MaybeAvlTree) {
+        self.root = node;
+        if let Some(root) = node {
+            self.update_height(root);
         }
-        return m;
-    }
-
-}
-
-impl<T: Ord + Copy + std::fmt::Debug + std::fmt::Display> Tree<T> for AvlTree<T> {
-    type Node = AvlTreeNode<T>;
-
-    fn new() -> Self {
-        Self {root: None}
     }
 
-    fn get_root(&self) -> &MaybeAvlTree<T> {
-        &self.root
+    fn nodes(&self) -> &Arena<AvlTreeNode<T, V>> {
+        &self.nodes
     }
 
-    fn set_root(&mut self, node: Option<Rc<RefCell<AvlTreeNode<T>>>>) {
-        self.root = node.clone();
-        match node{
-            None => {},
-            Some(ptr) => {
-                let mut n = ptr.as_ref().borrow_mut();
-                n.update_height();
-            }
-        }
-
+    fn nodes_mut(&mut self) -> &mut Arena<AvlTreeNode<T, V>> {
+        &mut self.nodes
     }
 
-    fn insert_fix(&mut self, node: Rc<RefCell<AvlTreeNode<T>>>) -> Rc<RefCell<AvlTreeNode<T>>> {
+    fn insert_fix(&mut self, node: NodeId) -> NodeId {
         //This function runs after a node is inserted, we're given the node that is inserted
         //We need to check if the tree is balanced and if not, fix it
 
         //First, we need to update the height of the node and all of its ancestors
-        self.refresh_height(node.clone());
-        
+        self.refresh_height(node);
+
         //if tree is rebalanced, we need to check if the tree is balanced again
         let mut is_balanced:bool = true;
 
-        let node_key = self.get_key(node.clone());
-
         //Next, we need to check if the tree is balanced
         //check balancing factor of the node and its acestors
-        let mut current_node = node.clone();
+        let mut current_node = node;
         loop{
 
-            let balance_factor = self.get_balance_factor(current_node.clone());
-            let root_key = self.get_key(current_node.clone());
-            
-            if balance_factor > 1 || balance_factor < -1 {
+            let balance_factor = self.get_balance_factor(current_node);
+
+            if !(-1..=1).contains(&balance_factor) {
                 is_balanced = false;
                 //Tree is unbalanced, we need to fix it
 
-                //debug
-                //println!("For node {} Balance factor: {}", root_key, balance_factor);
-                //println!("Tree is unbalanced, fixing it");
-
                 //check for which of the 4 cases the tree is unbalanced
 
                 //left heavy
                 if balance_factor > 1 {
                     //since balance factor is > 1 we know there is a left child
-                    //get left child and grandchild pointers
-                    
-                    //get the left child key
-                    let left_ptr = self.left(current_node.clone()).unwrap();
-                    let left_key = self.get_key(left_ptr.clone());
-                    drop(left_ptr);
-                    
+                    //compare the key of the inserted node with the left child key
+                    let left_child = self.left(current_node).unwrap();
+                    let order = self.compare(self.get_key(node), self.get_key(left_child));
 
                     //case 1: bf > 1 and key value of node is less than key value of left child
-                    if node_key < left_key {
-                        //println!("Case 1: Right rotation");
-                        self.rotate(Side::Right, current_node.clone());
+                    if order == Ordering::Less {
+                        self.rotate(Side::Right, current_node);
                     }
-                
+
                 //case 2: bf > 1 and key value of node is greater than key value of left child
-                    else if node_key > left_key {
-                        //println!("Case 2: Left-Right rotation");
-                        let left_child = self.left(current_node.clone()).unwrap();
-                        self.rotate(Side::Left, left_child.clone());
-                        self.rotate(Side::Right, current_node.clone());
-                        drop(left_child);
+                    else if order == Ordering::Greater {
+                        self.rotate(Side::Left, left_child);
+                        self.rotate(Side::Right, current_node);
                     }
                 }
                 //case 3: bf < -1 and key value of node is greater than key value of right child
                 else if balance_factor < -1 {
 
                     //since balance factor is < -1 we know there is a right child
-                    //get the right child key
-                    let right_ptr = self.right(current_node.clone()).unwrap();
-                    let right_key = self.get_key(right_ptr.clone());
-                    drop(right_ptr);
-
-                    if node_key > right_key{
-                        //println!("Case 3: Left rotation");
-                        self.rotate(Side::Left, current_node.clone());
+                    //compare the key of the inserted node with the right child key
+                    let right_child = self.right(current_node).unwrap();
+                    let order = self.compare(self.get_key(node), self.get_key(right_child));
+
+                    if order == Ordering::Greater {
+                        self.rotate(Side::Left, current_node);
                     }
 
                 //case 4: bf < -1 and key value of node is less than key value of right child
-                    else if node_key < right_key{
-                        //println!("Case 4: Right-Left rotation");
-                        let right_child = self.right(current_node.clone()).unwrap();
-                        self.rotate(Side::Right, right_child.clone());
-                        self.rotate(Side::Left, current_node.clone());
-                        drop(right_child);
+                    else if order == Ordering::Less {
+                        self.rotate(Side::Right, right_child);
+                        self.rotate(Side::Left, current_node);
                     }
                 }
                 //after fixing the tree, we need to update the height of the node and all of its ancestors
-                self.refresh_height(current_node.clone());
+                self.refresh_height(current_node);
             }
 
-            let n = current_node.as_ref().borrow();
-            if let Some(p) = n.get_parent() {
-                drop(n);
+            if let Some(p) = self.get_parent(current_node) {
                 current_node = p;
+            } else if is_balanced {
+                break;
             } else {
-                if is_balanced{
-                    break;
-                
-                }else{
-                    //if the tree is still unbalanced, we need to check again
-                    is_balanced = true;
-                    drop(n);
-                    drop(current_node);
-                    current_node = node.clone();
-                }
+                //if the tree is still unbalanced, we need to check again
+                is_balanced = true;
+                current_node = node;
             }
         }
 
-
-        //lets return root of the node 
-        drop(current_node);
-        let mut current_node = node.clone();
-        let temp_clone = current_node.clone();
-        let a = current_node.as_ref().borrow_mut();
-        let not_root = a.get_parent().is_some();
-        drop(a);
-        let root = if not_root { self.climb_to_root(temp_clone) } else { temp_clone };
-
-        root
+        //lets return root of the node
+        self.climb_to_root(node)
     }
 
-    fn rotate(&mut self, side: Side, node: Rc<RefCell<AvlTreeNode<T>>>) {
-        //here side means the direction of rotation
-
-        let node_key = self.get_key(node.clone());
+    fn build_fix(&mut self, node: NodeId, _depth: usize, _levels: usize) {
+        //the children are already built, so the height can be worked out from them
+        self.update_height(node);
+    }
 
-        //debug
-        //println!("Rotating node {}", node_key);
+    fn join_nodes(&mut self, left: MaybeAvlTree, node: NodeId, right: MaybeAvlTree) -> NodeId {
+        let left_height = self.height_of(left);
+        let right_height = self.height_of(right);
 
-        let mut n = node.as_ref().borrow_mut();
+        //if the two sides are close enough in height the node can simply go on top of them
+        if left_height.abs_diff(right_height) <= 1 {
+            self.set_children(node, left, right);
+            self.update_height(node);
+            self.update_size(node);
+            return node;
+        }
 
-        //before anything we save the parent of the node to update latter
-        let grandparent = n.get_parent();
-        //get the node key to use in setting parent later
-        
-
-        //first we check the side of rotation
-        if side == Side::Left {
-            //left rotation
-            //we need to rotate the node to the left
-            //first we need to get the right child of the node
-            let right_child_ptr = n.get_child(Side::Right);
-            let right_child = right_child_ptr.as_ref().unwrap().as_ref().borrow_mut();
-
-            //next we need to save the left child of the right child if there is any
-            if let Some(left_grandchild) = right_child.get_child(Side::Left){
-                //if there is a left grandchild, we need to set it as the right child of the node
-                n.set_child(Side::Right, Some(left_grandchild.clone()));
-
-                //we also need to set the node as the parent of the left grandchild
-                left_grandchild.as_ref().borrow_mut().set_parent(Some(Side::Right), Some(node.clone()));
-            }else{
-                //if there is no left grandchild, we need to set the right child of the node as None
-                n.set_child(Side::Right, None);
-            }
+        //otherwise we walk down the inner edge of the taller side until we reach a subtree
+        //about as tall as the shorter side, the node takes its place with both of them as children
+        let (side, tall, short, short_height) = if left_height > right_height {
+            (Side::Right, left.unwrap(), right, right_height)
+        } else {
+            (Side::Left, right.unwrap(), left, left_height)
+        };
+        let mut parent = tall;
+        let mut child = self.get_child(parent, side);
+        while self.height_of(child) > short_height + 1 {
+            parent = child.unwrap();
+            child = self.get_child(parent, side);
+        }
 
-            drop(right_child);
-            //next we need to set the right child as the parent of the node
-            n.set_parent(Some(Side::Left), Some(right_child_ptr.as_ref().unwrap().clone()));
-
-            //and set the node as the left child of the right child
-            let mut right_child = right_child_ptr.as_ref().unwrap().as_ref().borrow_mut();
-            right_child.set_child(Side::Left, Some(node.clone()));
-
-            //and update the parent of the right child
-            right_child.set_parent(None, grandparent.clone());
-
-            //update the grandparent's right child
-            if let Some(ptr) = grandparent{
-                let mut gp = ptr.as_ref().borrow_mut();
-                //check if the node is the left or right child of the grandparent
-                if gp.key > node_key {
-                    gp.set_child(Side::Left, Some(right_child_ptr.as_ref().unwrap().clone()));
-                }else{
-                    gp.set_child(Side::Right, Some(right_child_ptr.as_ref().unwrap().clone()));
-                }
-            }
+        match side {
+            Side::Right => self.set_children(node, child, short),
+            _ => self.set_children(node, short, child),
+        }
+        self.update_height(node);
+        self.update_size(node);
+        self.set_child(parent, side, Some(node));
+        self.set_parent(node, Some(side), Some(parent));
 
-            //finally we need to update the height of the node and the right child
-            if let Some(grand_child) = n.get_child(Side::Right){
-                grand_child.as_ref().borrow_mut().update_height();
-            }else{
-                n.update_height();
-            }
-        }else{
+        //the taller side grew along the path we took, fix it on the way back up
+        self.rebalance(parent)
+    }
 
-            //right rotation
-            //we need to rotate the node to the right
+    fn rotate(&mut self, side: Side, node: NodeId) {
+        //here side means the direction of rotation
 
-            //first we need to get the left child of the node
-            let left_child_ptr = n.get_child(Side::Left);
-            let left_child = left_child_ptr.as_ref().unwrap().as_ref().borrow_mut();
+        //before anything we save the parent of the node to update latter
+        let grandparent = self.get_parent(node);
+        //and which side of the grandparent the node hangs from, the new subtree root takes its place
+        let node_is_child = self.get_is_child(node);
 
-            //next we need to save the right child of the left child if there is any
-            if let Some(right_grandchild) = left_child.get_child(Side::Right){
-                //if there is a right grandchild, we need to set it as the left child of the node
-                n.set_child(Side::Left, Some(right_grandchild.clone()));
+        //the child on the opposite side of the rotation moves up into the node's place
+        //(the right child for a left rotation, the left child for a right rotation)
+        let child = self.get_child(node, !side).unwrap();
 
-                //we also need to set the node as the parent of the right grandchild
-                right_grandchild.as_ref().borrow_mut().set_parent(Some(Side::Left), Some(node.clone()));
-            }else{
-                //if there is no right grandchild, we need to set the left child of the node as None
-                n.set_child(Side::Left, None);
-            }
+        //next we need to save the inner grandchild if there is any,
+        //it becomes the node's child on the side the moved up child came from
+        let inner_grandchild = self.get_child(child, side);
+        self.set_child(node, !side, inner_grandchild);
+        if let Some(g) = inner_grandchild {
+            //we also need to set the node as the parent of the grandchild
+            self.set_parent(g, Some(!side), Some(node));
+        }
 
-            drop(left_child);
-            //next we need to set the left child as the parent of the node
-            n.set_parent(Some(Side::Right), Some(left_child_ptr.as_ref().unwrap().clone()));
-
-            //and set the node as the right child of the left child
-            let mut left_child = left_child_ptr.as_ref().unwrap().as_ref().borrow_mut();
-            left_child.set_child(Side::Right, Some(node.clone()));
-
-            //and update the parent of the left child
-            left_child.set_parent(None, grandparent.clone());
-
-            //update the grandparent's left child
-            if let Some(ptr) = grandparent{
-                let mut gp = ptr.as_ref().borrow_mut();
-                //check if the node is the left or right child of the grandparent
-                if gp.key > node_key {
-                    gp.set_child(Side::Left, Some(left_child_ptr.as_ref().unwrap().clone()));
-                }else{
-                    gp.set_child(Side::Right, Some(left_child_ptr.as_ref().unwrap().clone()));
-                }
-            }
+        //next we need to set the child as the parent of the node
+        //and set the node as the child of the child on the rotation side
+        self.set_parent(node, Some(side), Some(child));
+        self.set_child(child, side, Some(node));
 
-            //finally we need to update the height of the node and the left child
-            if let Some(grand_child) = n.get_child(Side::Left){
-                grand_child.as_ref().borrow_mut().update_height();
-            }else{
-                n.update_height();
-            }
+        //and update the parent of the child
+        self.set_parent(child, node_is_child, grandparent);
 
+        //update the grandparent's child on the side the node hung from
+        if let (Some(gp), Some(gp_side)) = (grandparent, node_is_child) {
+            self.set_child(gp, gp_side, Some(child));
         }
-    }
 
-    fn get_height(&self) -> usize {
-        // PART 1.4 get height of tree
-        if let Some(ptr) = &self.root {
-            let root: std::cell::Ref<'_, AvlTreeNode<T>> = ptr.as_ref().borrow();
-            return root.get_height_node(1);
-        }
-        else {
-            return 0;
-        }
+        //finally we need to update the height and size of the node and then of the child,
+        //which is now the parent of the node
+        self.update_height(node);
+        self.update_height(child);
+        self.update_size(node);
+        self.update_size(child);
     }
 
-    fn print_inorder(&self) {
-        // PART 1.5 print in-order traversal of tree
-        println!("-------- Tree In-Order -------");
-        if let Some(ptr) = &self.root {
-            let root = ptr.as_ref().borrow();
-            root.print_inorder_node();
-        }
-        else {
-            println!("Empty tree");
-        }
-        println!("------------------------------");
+    fn dot_attributes(&self, node: NodeId) -> String where T: std::fmt::Display {
+        let (left, right) = (self.height_of(self.left(node)), self.height_of(self.right(node)));
+        let balance = left as isize - right as isize;
+        format!("label=\"{}\\nh={} bf={balance}\"", dot_escape(self.get_key(node)), self.height_of(Some(node)))
     }
 
-    fn print_structure(&self) {
-        // PART 1.7 print tree showing structure and colours
-        println!("------- Tree Structure -------");
-        if let Some(ptr) = &self.root {
-            let root = ptr.as_ref().borrow();
-            root.print_structure_node(0, Side::Neither);
+    fn validate<'a>(&'a self) -> Result<(), ValidationError<&'a T>> where Self::Node: 'a {
+        self.validate_bst()?;
+        if let Some(root) = self.root {
+            self.validate_heights(root)?;
         }
-        else {
-            println!("Empty tree");
-        }       
-        println!("------------------------------");
+        Ok(())
     }
 
-    fn count_leaves(&self) -> usize {
-        // PART 1.3 count leaves in tree
-        if let Some(ptr) = &self.root {
-            let root = ptr.as_ref().borrow();
-            return root.count_leaves_node();
-        }
-        else {
-            return 0;
+
+    fn remove_node(&mut self, node: NodeId) -> (T, V) {
+        let mut node = node;
+
+        //if the node has two children, we swap the key and value of its in-order predecessor into it
+        //and remove the predecessor instead, which has at most one (left) child
+        if let (Some(left_child), Some(_)) = (self.left(node), self.right(node)) {
+            let predecessor = self.find_max(left_child);
+            self.swap_entry(node, predecessor);
+            node = predecessor;
         }
-    }
 
-    fn is_empty(&self) -> bool {
-        // PART 1.6 check if tree empty
-        if let None = &self.root {
-            return true;
-        } else {
-            return false;
+        //splice the node out of the tree by linking its only child (if any) to its parent
+        let parent = self.get_parent(node);
+        let node_is_child = self.get_is_child(node);
+        let child = match self.left(node) {
+            Some(left) => Some(left),
+            None => self.right(node),
+        };
+
+        match parent {
+            None => {
+                //the node was the root
+                if let Some(c) = child {
+                    self.set_parent(c, None, None);
+                }
+                self.root = child;
+            }
+            Some(p) => {
+                let side = node_is_child.unwrap();
+                self.set_child(p, side, child);
+                if let Some(c) = child {
+                    self.set_parent(c, Some(side), Some(p));
+                }
+
+                //walk up from the parent of the removed node, fixing heights, sizes and rebalancing
+                let new_root = self.rebalance(p);
+                self.set_root(Some(new_root));
+            }
         }
-    } 
 
-    
-    fn delete(&mut self, key: T) {
-        unimplemented!("Deletion is not implemented for AVL Tree");
+        //finally free the removed node and hand back its entry
+        self.nodes.remove(node).into_entry()
     }
 }
 
 
-impl<T> CLIPrintable for AvlTree<T> {
+impl<T, V, C> CLIPrintable for AvlTree<T, V, C> {
     fn pretty_name() -> &'static str {
         "AVL tree"
     }
 }
 
+tree_impls!(AvlTree, AvlTreeNode);
 
-impl<T> AvlTree<T>
+impl<T, V, C: Default> Default for AvlTree<T, V, C> {
+    fn default() -> Self {
+        Self { root: None, nodes: Arena::new(), compare: C::default() }
+    }
+}
+
+impl<T, V, C> AvlTree<T, V, C>
 where
-T: Ord + Copy + std::fmt::Debug + std::fmt::Display
+C: Comparator<T>
 {
 
-    pub fn refresh_height(&mut self, node: Rc<RefCell<AvlTreeNode<T>>>) {
-        let mut current_node = node.clone();
+    pub fn refresh_height(&mut self, node: NodeId) {
+        let mut current_node = node;
         //travel up the tree to update the height of the all acestors nodes
         loop {
-            let mut n = current_node.as_ref().borrow_mut();
-            n.update_height();
-            if let Some(p) = n.get_parent() {
-                drop(n);
+            self.update_height(current_node);
+            if let Some(p) = self.get_parent(current_node) {
                 current_node = p;
             } else {
                 break;
@@ -623,41 +438,97 @@ T: Ord + Copy + std::fmt::Debug + std::fmt::Display
         }
     }
 
-    pub fn print_inorder(&self) {
-        // PART 2.5 print in-order traversal of tree
-        println!("-------- Tree In-Order -------");
-        if let Some(ptr) = &self.root {
-            let root = ptr.as_ref().borrow();
-            root.print_inorder_node();
-        }
-        else {
-            println!("Empty tree");
+    fn rebalance(&mut self, node: NodeId) -> NodeId {
+        //This function runs after a node is removed or two subtrees are joined,
+        //we're given the lowest node whose subtree changed
+        //Unlike insertion, this can unbalance every ancestor, so we keep climbing to the root
+
+        let mut current_node = node;
+        loop {
+            //everything below is already fixed, so the node can be worked out from its children
+            self.update_height(current_node);
+            self.update_size(current_node);
+            let balance_factor = self.get_balance_factor(current_node);
+
+            //left heavy
+            if balance_factor > 1 {
+                //the balance factor of the left child tells us if a single rotation is enough
+                let left_child = self.left(current_node).unwrap();
+                if self.get_balance_factor(left_child) < 0 {
+                    //Left-Right case
+                    self.rotate(Side::Left, left_child);
+                }
+                //Left-Left case
+                self.rotate(Side::Right, current_node);
+                //the node moved down, continue from the new root of this subtree
+                current_node = self.get_parent(current_node).unwrap();
+            }
+            //right heavy
+            else if balance_factor < -1 {
+                let right_child = self.right(current_node).unwrap();
+                if self.get_balance_factor(right_child) > 0 {
+                    //Right-Left case
+                    self.rotate(Side::Right, right_child);
+                }
+                //Right-Right case
+                self.rotate(Side::Left, current_node);
+                current_node = self.get_parent(current_node).unwrap();
+            }
+
+            match self.get_parent(current_node) {
+                Some(p) => current_node = p,
+                None => break,
+            }
         }
-        println!("------------------------------");
+
+        //current_node has no parent, so it is the root of the tree
+        current_node
     }
 
-    pub fn print_structure(&self) {
-        // PART 2.7 print tree showing structure and colours
-       println!("------- Tree Structure -------");
-        if let Some(ptr) = &self.root {
-            let root = ptr.as_ref().borrow();
-            root.print_structure_node(0, Side::Neither);
+    // check the stored heights and balance below a node, returning the real height of its subtree
+    fn validate_heights(&self, node: NodeId) -> Result<usize, ValidationError<&T>> {
+        let key = self.get_key(node);
+        let left = match self.left(node) {
+            Some(l) => self.validate_heights(l)?,
+            None => 0,
+        };
+        let right = match self.right(node) {
+            Some(r) => self.validate_heights(r)?,
+            None => 0,
+        };
+
+        let actual = 1 + max(left, right);
+        let stored = self.node(node).get_height();
+        if stored != actual {
+            return Err(ValidationError::WrongHeight { key, stored, actual });
+        }
+        let balance = left as isize - right as isize;
+        if !(-1..=1).contains(&balance) {
+            return Err(ValidationError::Unbalanced { key, balance });
         }
-        else {
-            println!("Empty tree");
-        }       
-        println!("------------------------------");
+        Ok(actual)
     }
 
-    fn get_balance_factor(&self, node: Rc<RefCell<AvlTreeNode<T>>>) -> i8 {
-        node.as_ref().borrow_mut().get_balance_factor()
+    fn height_of(&self, node: MaybeAvlTree) -> usize {
+        match node {
+            Some(n) => self.node(n).get_height(),
+            None => 0,
+        }
     }
 
-    fn greater(&self, node: Rc<RefCell<AvlTreeNode<T>>>, k: T) -> bool {
-        node.as_ref().borrow().greater(k)
+    fn update_height(&mut self, node: NodeId) {
+        let left_height = self.height_of(self.left(node));
+        let right_height = self.height_of(self.right(node));
+
+        let calculated_height = 1 + max(left_height, right_height);
+        self.node_mut(node).set_height(calculated_height);
     }
-    fn less(&self, node: Rc<RefCell<AvlTreeNode<T>>>, k: T) -> bool {
-        node.as_ref().borrow().less(k)
+
+    // Helper function to get the balance factor of a node.
+    fn get_balance_factor(&self, node: NodeId) -> i8 {
+        let left_height = self.height_of(self.left(node)) as i8;
+        let right_height = self.height_of(self.right(node)) as i8;
+        left_height - right_height
     }
 
-}
\ No newline at end of file
+}
@@ -0,0 +1,503 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt::{Debug, Display};
+use std::iter::FusedIterator;
+use std::ops::{Bound, RangeBounds};
+use crate::arena::Arena;
+use crate::node::NodeId;
+use crate::b_tree::DEFAULT_ORDER;
+use crate::cli::{CLIPrintable, CLITree};
+use crate::comparator::{Comparator, NaturalOrder};
+use crate::multiway::{self, MultiwayNode};
+use crate::validate::ValidationError;
+
+struct BPlusTreeNode<T> {
+    keys: Vec<T>,
+    // empty for a leaf
+    children: Vec<NodeId>,
+    // the leaf after this one in key order, only leaves are linked
+    next: Option<NodeId>,
+}
+
+impl<T> MultiwayNode<T> for BPlusTreeNode<T> {
+    fn keys(&self) -> &[T] {
+        &self.keys
+    }
+
+    fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+
+    fn next_leaf(&self) -> Option<NodeId> {
+        self.next
+    }
+}
+
+// B+ tree of a given order: nodes fill, split and merge like those of a `BTree`, but every key
+// lives in a leaf and the inner nodes only hold copies of keys to steer searches with. The leaves
+// are linked from left to right, so a range scan finds its first key and then reads leaf after
+// leaf without going back up the tree. Copying keys up needs them to be `Clone`.
+pub struct BPlusTree<T, C = NaturalOrder> {
+    root: Option<NodeId>,
+    nodes: Arena<BPlusTreeNode<T>>,
+    order: usize,
+    len: usize,
+    compare: C,
+}
+
+// what inserting into a subtree did to its root
+enum Inserted<T> {
+    Present,
+    Fits,
+    // the root overflowed and was split, the key that separates the halves goes up with the right one
+    Split(T, NodeId),
+}
+
+impl<T> BPlusTree<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // an empty tree whose nodes have at most `order` children, `order` has to be at least 3
+    pub fn with_order(order: usize) -> Self where T: Ord {
+        Self::with_order_and_comparator(order, NaturalOrder)
+    }
+}
+
+impl<T, C: Comparator<T>> BPlusTree<T, C> {
+    // a tree of the default order that orders its keys with `compare`
+    pub fn with_comparator(compare: C) -> Self {
+        Self::with_order_and_comparator(DEFAULT_ORDER, compare)
+    }
+
+    pub fn with_order_and_comparator(order: usize, compare: C) -> Self {
+        assert!(order >= 3, "the order of a B+ tree has to be at least 3");
+        Self { root: None, nodes: Arena::new(), order, len: 0, compare }
+    }
+
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn contains<Q: ?Sized>(&self, key: &Q) -> bool where T: Borrow<Q>, C: Comparator<Q> {
+        self.get(key).is_some()
+    }
+
+    // the stored key that is equal to `key`
+    pub fn get<Q: ?Sized>(&self, key: &Q) -> Option<&T> where T: Borrow<Q>, C: Comparator<Q> {
+        let mut node = self.root?;
+        while !self.nodes[node].children.is_empty() {
+            node = self.nodes[node].children[self.child_index(node, key)];
+        }
+        let keys = &self.nodes[node].keys;
+        let i = keys.binary_search_by(|k| self.compare.compare(k.borrow(), key)).ok()?;
+        Some(&keys[i])
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        self.iter().next()
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        let mut node = self.root?;
+        while let Some(&child) = self.nodes[node].children.last() {
+            node = child;
+        }
+        self.nodes[node].keys.last()
+    }
+
+    // insert a key, returning false if it was already in the tree
+    pub fn insert(&mut self, key: T) -> bool where T: Clone {
+        let root = match self.root {
+            Some(r) => r,
+            None => {
+                let leaf = BPlusTreeNode { keys: vec![key], children: Vec::new(), next: None };
+                self.root = Some(self.nodes.insert(leaf));
+                self.len = 1;
+                return true;
+            }
+        };
+        match self.insert_into(root, key) {
+            Inserted::Present => return false,
+            Inserted::Fits => {}
+            // the root was split, so the tree grows a level at the top
+            Inserted::Split(separator, right) => {
+                let new_root = BPlusTreeNode { keys: vec![separator], children: vec![root, right], next: None };
+                self.root = Some(self.nodes.insert(new_root));
+            }
+        }
+        self.len += 1;
+        true
+    }
+
+    // remove a key from the tree, returning the stored key if it was there.
+    // copies of it may stay behind in inner nodes, they still separate the same leaves
+    pub fn delete<Q: ?Sized>(&mut self, key: &Q) -> Option<T> where T: Borrow<Q> + Clone, C: Comparator<Q> {
+        let root = self.root?;
+        let removed = self.remove_from(root, key)?;
+        self.len -= 1;
+        // a root left without keys gives way to its only child, the tree loses a level
+        if self.nodes[root].keys.is_empty() {
+            self.root = self.nodes.remove(root).children.first().copied();
+        }
+        Some(removed)
+    }
+
+    // in-order iterator over the keys, following the links between the leaves
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.range::<T, _>(..)
+    }
+
+    // in-order iterator over the keys that fall inside `range`, e.g. `tree.range(3..10)`
+    pub fn range<Q: ?Sized, R: RangeBounds<Q>>(&self, range: R) -> Iter<'_, T> where T: Borrow<Q>, C: Comparator<Q> {
+        // the iterator stops at the first key past the end
+        let end = match range.end_bound() {
+            Bound::Included(k) => self.position(Bound::Excluded(k)),
+            Bound::Excluded(k) => self.position(Bound::Included(k)),
+            Bound::Unbounded => None,
+        };
+        let mut front = self.position(range.start_bound());
+        // a start past the end gives nothing rather than running on to the last key
+        if let Some((leaf, i)) = front {
+            let key = self.nodes[leaf].keys[i].borrow();
+            let past = match range.end_bound() {
+                Bound::Included(k) => self.compare.compare(key, k) == Ordering::Greater,
+                Bound::Excluded(k) => self.compare.compare(key, k) != Ordering::Less,
+                Bound::Unbounded => false,
+            };
+            if past {
+                front = None;
+            }
+        }
+        Iter { nodes: &self.nodes, front, end }
+    }
+
+    // number of levels, every leaf is on the last one
+    pub fn get_height(&self) -> usize {
+        multiway::height(&self.nodes, self.root)
+    }
+
+    // number of nodes without children
+    pub fn count_leaves(&self) -> usize {
+        multiway::count_leaves(&self.nodes, self.root)
+    }
+
+    pub fn print_inorder(&self) where T: Debug {
+        multiway::print_inorder(self.iter());
+    }
+
+    // print the tree on its side, with the root on the left and smaller keys above it
+    pub fn print_structure(&self) where T: Display {
+        multiway::print_structure(&self.nodes, self.root);
+    }
+
+    // like `print_structure`, but drawn top-down with the root on the first line
+    pub fn print_top_down(&self) where T: Display {
+        multiway::print_top_down(&self.nodes, self.root);
+    }
+
+    // the tree drawn top-down, one line per level with its nodes from left to right
+    pub fn format_top_down(&self) -> String where T: Display {
+        multiway::format_levels(&self.nodes, self.root)
+    }
+
+    // the tree as a Graphviz DOT graph with a record per node and the leaf links dashed
+    pub fn to_dot(&self) -> String where T: Display {
+        multiway::to_dot(&self.nodes, self.root)
+    }
+
+    // check that the tree is a valid B+ tree of its order, returning the first broken rule found
+    pub fn validate(&self) -> Result<(), ValidationError<&T>> {
+        let count = multiway::validate(&self.nodes, self.root, self.order, &self.compare, true)?;
+        let root = match self.root {
+            Some(r) => r,
+            None => return Ok(()),
+        };
+        if count != self.len {
+            return Err(ValidationError::WrongSize { key: &self.nodes[root].keys[0], stored: self.len, actual: count });
+        }
+
+        // the links have to go through the leaves in the order the tree has them in
+        let mut leaves = Vec::new();
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            match self.nodes[node].children.is_empty() {
+                true => leaves.push(node),
+                false => stack.extend(self.nodes[node].children.iter().rev()),
+            }
+        }
+        let next = leaves.iter().skip(1).map(|&l| Some(l)).chain([None]);
+        for (&leaf, expected) in leaves.iter().zip(next) {
+            if self.nodes[leaf].next != expected {
+                return Err(ValidationError::LeafLink { key: &self.nodes[leaf].keys[0] });
+            }
+        }
+        Ok(())
+    }
+
+    // the child of an inner node the search for `key` goes on in, a key equal to a separator is on its right
+    fn child_index<Q: ?Sized>(&self, node: NodeId, key: &Q) -> usize where T: Borrow<Q>, C: Comparator<Q> {
+        self.nodes[node].keys.partition_point(|k| self.compare.compare(k.borrow(), key) != Ordering::Greater)
+    }
+
+    // the leaf and index of the first key inside a lower bound
+    fn position<Q: ?Sized>(&self, bound: Bound<&Q>) -> Option<(NodeId, usize)> where T: Borrow<Q>, C: Comparator<Q> {
+        let mut node = self.root?;
+        while !self.nodes[node].children.is_empty() {
+            let i = match bound {
+                Bound::Included(k) | Bound::Excluded(k) => self.child_index(node, k),
+                Bound::Unbounded => 0,
+            };
+            node = self.nodes[node].children[i];
+        }
+        let keys = &self.nodes[node].keys;
+        let i = match bound {
+            Bound::Included(k) => keys.partition_point(|x| self.compare.compare(x.borrow(), k) == Ordering::Less),
+            Bound::Excluded(k) => keys.partition_point(|x| self.compare.compare(x.borrow(), k) != Ordering::Greater),
+            Bound::Unbounded => 0,
+        };
+        // past the last key of the leaf, the first key of the next one is the answer
+        match i < keys.len() {
+            true => Some((node, i)),
+            false => self.nodes[node].next.map(|n| (n, 0)),
+        }
+    }
+
+    fn insert_into(&mut self, node: NodeId, key: T) -> Inserted<T> where T: Clone {
+        if self.nodes[node].children.is_empty() {
+            let keys = &self.nodes[node].keys;
+            let i = match keys.binary_search_by(|k| self.compare.compare(k, &key)) {
+                Ok(_) => return Inserted::Present,
+                Err(i) => i,
+            };
+            self.nodes[node].keys.insert(i, key);
+            if self.nodes[node].keys.len() < self.order {
+                return Inserted::Fits;
+            }
+
+            // a full leaf keeps its first half and the rest moves to a new leaf linked in after it,
+            // a copy of the new leaf's first key goes up to separate the two
+            let n = &mut self.nodes[node];
+            let keys = n.keys.split_off(n.keys.len() / 2);
+            let separator = keys[0].clone();
+            let right = BPlusTreeNode { keys, children: Vec::new(), next: n.next };
+            let right = self.nodes.insert(right);
+            self.nodes[node].next = Some(right);
+            return Inserted::Split(separator, right);
+        }
+
+        let i = self.child_index(node, &key);
+        let child = self.nodes[node].children[i];
+        match self.insert_into(child, key) {
+            Inserted::Split(separator, right) => {
+                let n = &mut self.nodes[node];
+                n.keys.insert(i, separator);
+                n.children.insert(i + 1, right);
+            }
+            done => return done,
+        }
+        if self.nodes[node].keys.len() < self.order {
+            return Inserted::Fits;
+        }
+
+        // a full inner node splits like a B-tree node, its middle separator moves up
+        let n = &mut self.nodes[node];
+        let mid = n.keys.len() / 2;
+        let keys = n.keys.split_off(mid + 1);
+        let separator = n.keys.pop().unwrap();
+        let children = n.children.split_off(mid + 1);
+        Inserted::Split(separator, self.nodes.insert(BPlusTreeNode { keys, children, next: None }))
+    }
+
+    fn remove_from<Q: ?Sized>(&mut self, node: NodeId, key: &Q) -> Option<T> where T: Borrow<Q> + Clone, C: Comparator<Q> {
+        if self.nodes[node].children.is_empty() {
+            let keys = &self.nodes[node].keys;
+            let i = keys.binary_search_by(|k| self.compare.compare(k.borrow(), key)).ok()?;
+            return Some(self.nodes[node].keys.remove(i));
+        }
+        let i = self.child_index(node, key);
+        let child = self.nodes[node].children[i];
+        let removed = self.remove_from(child, key)?;
+        self.refill(node, i);
+        Some(removed)
+    }
+
+    // bring the child at `i` back up to the fewest keys it may hold after it lost one, by moving
+    // a key over from a sibling or by merging it with a sibling
+    fn refill(&mut self, parent: NodeId, i: usize) where T: Clone {
+        let min = multiway::min_keys(self.order);
+        let child = self.nodes[parent].children[i];
+        if self.nodes[child].keys.len() >= min {
+            return;
+        }
+        let leaf = self.nodes[child].children.is_empty();
+        let left = i.checked_sub(1).map(|j| self.nodes[parent].children[j]);
+        let right = self.nodes[parent].children.get(i + 1).copied();
+
+        if let Some(l) = left.filter(|&l| self.nodes[l].keys.len() > min) {
+            let key = self.nodes[l].keys.pop().unwrap();
+            if leaf {
+                // the left leaf's last key moves over and separates the two from now on
+                self.nodes[parent].keys[i - 1] = key.clone();
+                self.nodes[child].keys.insert(0, key);
+            } else {
+                // the separator comes down in front of the child and the left sibling's last key takes its place
+                let grandchild = self.nodes[l].children.pop().unwrap();
+                let separator = std::mem::replace(&mut self.nodes[parent].keys[i - 1], key);
+                let c = &mut self.nodes[child];
+                c.keys.insert(0, separator);
+                c.children.insert(0, grandchild);
+            }
+        } else if let Some(r) = right.filter(|&r| self.nodes[r].keys.len() > min) {
+            let key = self.nodes[r].keys.remove(0);
+            if leaf {
+                self.nodes[child].keys.push(key);
+                self.nodes[parent].keys[i] = self.nodes[r].keys[0].clone();
+            } else {
+                let grandchild = self.nodes[r].children.remove(0);
+                let separator = std::mem::replace(&mut self.nodes[parent].keys[i], key);
+                let c = &mut self.nodes[child];
+                c.keys.push(separator);
+                c.children.push(grandchild);
+            }
+        } else if left.is_some() {
+            self.merge(parent, i - 1);
+        } else {
+            self.merge(parent, i);
+        }
+    }
+
+    // fold the child after `j` into the child at `j`. leaves drop the separator between them,
+    // it is only a copy, and inner nodes take it down between their keys
+    fn merge(&mut self, parent: NodeId, j: usize) {
+        let separator = self.nodes[parent].keys.remove(j);
+        let right = self.nodes[parent].children.remove(j + 1);
+        let left = self.nodes[parent].children[j];
+        let BPlusTreeNode { keys, children, next } = self.nodes.remove(right);
+        let l = &mut self.nodes[left];
+        if l.children.is_empty() {
+            l.next = next;
+        } else {
+            l.keys.push(separator);
+        }
+        l.keys.extend(keys);
+        l.children.extend(children);
+    }
+}
+
+impl<T, C> CLIPrintable for BPlusTree<T, C> {
+    fn pretty_name() -> &'static str {
+        "B+ Tree"
+    }
+}
+
+impl<T: Clone + Debug + Display, C: Comparator<T>> CLITree<T> for BPlusTree<T, C> {
+    // every child of an inner node is there, a B+ tree has no empty subtrees to draw
+    const NIL_LEAVES: bool = false;
+
+    fn insert(&mut self, key: T) -> bool {
+        self.insert(key)
+    }
+
+    fn delete(&mut self, key: &T) -> Option<T> {
+        self.delete(key)
+    }
+
+    fn search(&mut self, key: &T) -> bool {
+        self.contains(key)
+    }
+
+    fn count_leaves(&self) -> usize {
+        self.count_leaves()
+    }
+
+    fn get_height(&self) -> usize {
+        self.get_height()
+    }
+
+    fn print_inorder(&self) {
+        self.print_inorder()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.is_empty()
+    }
+
+    fn print_structure(&self) {
+        self.print_structure()
+    }
+
+    fn print_top_down(&self) {
+        self.print_top_down()
+    }
+
+    fn to_dot(&self, _show_nil: bool) -> String {
+        self.to_dot()
+    }
+}
+
+impl<T, C: Default> Default for BPlusTree<T, C> {
+    fn default() -> Self {
+        Self { root: None, nodes: Arena::new(), order: DEFAULT_ORDER, len: 0, compare: C::default() }
+    }
+}
+
+impl<T: Clone, C: Comparator<T> + Default> FromIterator<T> for BPlusTree<T, C> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut tree = Self::default();
+        tree.extend(iter);
+        tree
+    }
+}
+
+impl<T: Clone, C: Comparator<T>> Extend<T> for BPlusTree<T, C> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for key in iter {
+            self.insert(key);
+        }
+    }
+}
+
+impl<'a, T, C: Comparator<T>> IntoIterator for &'a BPlusTree<T, C> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+// in-order iterator over keys of a `BPlusTree`, reading the leaves one after the other
+pub struct Iter<'a, T> {
+    nodes: &'a Arena<BPlusTreeNode<T>>,
+    // the leaf and index of the next key, and of the first key not to hand out
+    front: Option<(NodeId, usize)>,
+    end: Option<(NodeId, usize)>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let (leaf, i) = self.front?;
+        if self.front == self.end {
+            return None;
+        }
+        let keys = &self.nodes[leaf].keys;
+        self.front = match i + 1 < keys.len() {
+            true => Some((leaf, i + 1)),
+            false => self.nodes[leaf].next.map(|n| (n, 0)),
+        };
+        Some(&keys[i])
+    }
+}
+
+impl<T> FusedIterator for Iter<'_, T> {}
@@ -0,0 +1,446 @@
+use std::borrow::Borrow;
+use std::fmt::{Debug, Display};
+use std::iter::FusedIterator;
+use crate::arena::Arena;
+use crate::node::NodeId;
+use crate::cli::{CLIPrintable, CLITree};
+use crate::comparator::{Comparator, NaturalOrder};
+use crate::multiway::{self, MultiwayNode};
+use crate::validate::ValidationError;
+
+// order of the trees built by `BTree::new`
+pub const DEFAULT_ORDER: usize = 16;
+
+struct BTreeNode<T> {
+    keys: Vec<T>,
+    // empty for a leaf
+    children: Vec<NodeId>,
+}
+
+impl<T> MultiwayNode<T> for BTreeNode<T> {
+    fn keys(&self) -> &[T] {
+        &self.keys
+    }
+
+    fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+}
+
+// B-tree (Bayer and McCreight) of a given order m: every node holds up to m - 1 keys next to each
+// other in a Vec and an inner node one child more than it has keys, every node but the root is at
+// least half full and all leaves are on the same level. A lookup binary searches O(log_m n) nodes
+// rather than following a pointer per level of a binary tree, which is much kinder to the cache.
+pub struct BTree<T, C = NaturalOrder> {
+    root: Option<NodeId>,
+    nodes: Arena<BTreeNode<T>>,
+    order: usize,
+    len: usize,
+    compare: C,
+}
+
+// what inserting into a subtree did to its root
+enum Inserted<T> {
+    Present,
+    Fits,
+    // the root overflowed and was split, the middle key goes up together with the new right half
+    Split(T, NodeId),
+}
+
+impl<T> BTree<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // an empty tree whose nodes have at most `order` children, `order` has to be at least 3
+    pub fn with_order(order: usize) -> Self where T: Ord {
+        Self::with_order_and_comparator(order, NaturalOrder)
+    }
+}
+
+impl<T, C: Comparator<T>> BTree<T, C> {
+    // a tree of the default order that orders its keys with `compare`
+    pub fn with_comparator(compare: C) -> Self {
+        Self::with_order_and_comparator(DEFAULT_ORDER, compare)
+    }
+
+    pub fn with_order_and_comparator(order: usize, compare: C) -> Self {
+        assert!(order >= 3, "the order of a B-tree has to be at least 3");
+        Self { root: None, nodes: Arena::new(), order, len: 0, compare }
+    }
+
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn contains<Q: ?Sized>(&self, key: &Q) -> bool where T: Borrow<Q>, C: Comparator<Q> {
+        self.get(key).is_some()
+    }
+
+    // the stored key that is equal to `key`
+    pub fn get<Q: ?Sized>(&self, key: &Q) -> Option<&T> where T: Borrow<Q>, C: Comparator<Q> {
+        let mut node = self.root?;
+        loop {
+            match self.search_node(node, key) {
+                Ok(i) => return Some(&self.nodes[node].keys[i]),
+                Err(i) => node = *self.nodes[node].children.get(i)?,
+            }
+        }
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        let mut node = self.root?;
+        while let Some(&child) = self.nodes[node].children.first() {
+            node = child;
+        }
+        self.nodes[node].keys.first()
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        let mut node = self.root?;
+        while let Some(&child) = self.nodes[node].children.last() {
+            node = child;
+        }
+        self.nodes[node].keys.last()
+    }
+
+    // insert a key, returning false if it was already in the tree
+    pub fn insert(&mut self, key: T) -> bool {
+        let root = match self.root {
+            Some(r) => r,
+            None => {
+                self.root = Some(self.nodes.insert(BTreeNode { keys: vec![key], children: Vec::new() }));
+                self.len = 1;
+                return true;
+            }
+        };
+        match self.insert_into(root, key) {
+            Inserted::Present => return false,
+            Inserted::Fits => {}
+            // the root was split, so the tree grows a level at the top
+            Inserted::Split(middle, right) => {
+                let new_root = BTreeNode { keys: vec![middle], children: vec![root, right] };
+                self.root = Some(self.nodes.insert(new_root));
+            }
+        }
+        self.len += 1;
+        true
+    }
+
+    // remove a key from the tree, returning the stored key if it was there
+    pub fn delete<Q: ?Sized>(&mut self, key: &Q) -> Option<T> where T: Borrow<Q>, C: Comparator<Q> {
+        let root = self.root?;
+        let removed = self.remove_from(root, key)?;
+        self.len -= 1;
+        // a root left without keys gives way to its only child, the tree loses a level
+        if self.nodes[root].keys.is_empty() {
+            self.root = self.nodes.remove(root).children.first().copied();
+        }
+        Some(removed)
+    }
+
+    // in-order iterator over the keys
+    pub fn iter(&self) -> Iter<'_, T> {
+        let mut iter = Iter { nodes: &self.nodes, stack: Vec::new(), remaining: self.len };
+        iter.descend(self.root);
+        iter
+    }
+
+    // number of levels, every leaf is on the last one
+    pub fn get_height(&self) -> usize {
+        multiway::height(&self.nodes, self.root)
+    }
+
+    // number of nodes without children
+    pub fn count_leaves(&self) -> usize {
+        multiway::count_leaves(&self.nodes, self.root)
+    }
+
+    pub fn print_inorder(&self) where T: Debug {
+        multiway::print_inorder(self.iter());
+    }
+
+    // print the tree on its side, with the root on the left and smaller keys above it
+    pub fn print_structure(&self) where T: Display {
+        multiway::print_structure(&self.nodes, self.root);
+    }
+
+    // like `print_structure`, but drawn top-down with the root on the first line
+    pub fn print_top_down(&self) where T: Display {
+        multiway::print_top_down(&self.nodes, self.root);
+    }
+
+    // the tree drawn top-down, one line per level with its nodes from left to right
+    pub fn format_top_down(&self) -> String where T: Display {
+        multiway::format_levels(&self.nodes, self.root)
+    }
+
+    // the tree as a Graphviz DOT graph with a record per node, e.g. for `dot -Tsvg tree.dot`
+    pub fn to_dot(&self) -> String where T: Display {
+        multiway::to_dot(&self.nodes, self.root)
+    }
+
+    // check that the tree is a valid B-tree of its order, returning the first broken rule found
+    pub fn validate(&self) -> Result<(), ValidationError<&T>> {
+        let count = multiway::validate(&self.nodes, self.root, self.order, &self.compare, false)?;
+        match self.root {
+            Some(root) if count != self.len => {
+                Err(ValidationError::WrongSize { key: &self.nodes[root].keys[0], stored: self.len, actual: count })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // where `key` is in a node, or the child to look for it in
+    fn search_node<Q: ?Sized>(&self, node: NodeId, key: &Q) -> Result<usize, usize> where T: Borrow<Q>, C: Comparator<Q> {
+        self.nodes[node].keys.binary_search_by(|k| self.compare.compare(k.borrow(), key))
+    }
+
+    fn insert_into(&mut self, node: NodeId, key: T) -> Inserted<T> {
+        let i = match self.search_node(node, &key) {
+            Ok(_) => return Inserted::Present,
+            Err(i) => i,
+        };
+        if self.nodes[node].children.is_empty() {
+            self.nodes[node].keys.insert(i, key);
+        } else {
+            let child = self.nodes[node].children[i];
+            match self.insert_into(child, key) {
+                Inserted::Split(middle, right) => {
+                    let n = &mut self.nodes[node];
+                    n.keys.insert(i, middle);
+                    n.children.insert(i + 1, right);
+                }
+                done => return done,
+            }
+        }
+        if self.nodes[node].keys.len() < self.order {
+            return Inserted::Fits;
+        }
+
+        // one key too many: the middle one moves up and the keys after it go to a new node
+        let n = &mut self.nodes[node];
+        let mid = n.keys.len() / 2;
+        let keys = n.keys.split_off(mid + 1);
+        let middle = n.keys.pop().unwrap();
+        let children = if n.children.is_empty() { Vec::new() } else { n.children.split_off(mid + 1) };
+        Inserted::Split(middle, self.nodes.insert(BTreeNode { keys, children }))
+    }
+
+    fn remove_from<Q: ?Sized>(&mut self, node: NodeId, key: &Q) -> Option<T> where T: Borrow<Q>, C: Comparator<Q> {
+        let leaf = self.nodes[node].children.is_empty();
+        match self.search_node(node, key) {
+            Ok(i) if leaf => Some(self.nodes[node].keys.remove(i)),
+            Ok(i) => {
+                // the largest key on the left takes the place of the removed one
+                let child = self.nodes[node].children[i];
+                let predecessor = self.pop_last(child);
+                let removed = std::mem::replace(&mut self.nodes[node].keys[i], predecessor);
+                self.refill(node, i);
+                Some(removed)
+            }
+            Err(_) if leaf => None,
+            Err(i) => {
+                let child = self.nodes[node].children[i];
+                let removed = self.remove_from(child, key)?;
+                self.refill(node, i);
+                Some(removed)
+            }
+        }
+    }
+
+    // take the largest key out of a subtree
+    fn pop_last(&mut self, node: NodeId) -> T {
+        match self.nodes[node].children.last() {
+            None => self.nodes[node].keys.pop().unwrap(),
+            Some(&child) => {
+                let last = self.pop_last(child);
+                let i = self.nodes[node].keys.len();
+                self.refill(node, i);
+                last
+            }
+        }
+    }
+
+    // bring the child at `i` back up to the fewest keys it may hold after it lost one, by moving
+    // a key over from a sibling through the parent or by merging it with a sibling
+    fn refill(&mut self, parent: NodeId, i: usize) {
+        let min = multiway::min_keys(self.order);
+        let child = self.nodes[parent].children[i];
+        if self.nodes[child].keys.len() >= min {
+            return;
+        }
+        let left = i.checked_sub(1).map(|j| self.nodes[parent].children[j]);
+        let right = self.nodes[parent].children.get(i + 1).copied();
+
+        if let Some(l) = left.filter(|&l| self.nodes[l].keys.len() > min) {
+            // the separator comes down in front of the child and the left sibling's last key takes its place
+            let key = self.nodes[l].keys.pop().unwrap();
+            let grandchild = self.nodes[l].children.pop();
+            let separator = std::mem::replace(&mut self.nodes[parent].keys[i - 1], key);
+            let c = &mut self.nodes[child];
+            c.keys.insert(0, separator);
+            if let Some(g) = grandchild {
+                c.children.insert(0, g);
+            }
+        } else if let Some(r) = right.filter(|&r| self.nodes[r].keys.len() > min) {
+            let key = self.nodes[r].keys.remove(0);
+            let grandchild = (!self.nodes[r].children.is_empty()).then(|| self.nodes[r].children.remove(0));
+            let separator = std::mem::replace(&mut self.nodes[parent].keys[i], key);
+            let c = &mut self.nodes[child];
+            c.keys.push(separator);
+            c.children.extend(grandchild);
+        } else if left.is_some() {
+            self.merge(parent, i - 1);
+        } else {
+            self.merge(parent, i);
+        }
+    }
+
+    // fold the child after `j` and the separator between them into the child at `j`
+    fn merge(&mut self, parent: NodeId, j: usize) {
+        let separator = self.nodes[parent].keys.remove(j);
+        let right = self.nodes[parent].children.remove(j + 1);
+        let left = self.nodes[parent].children[j];
+        let BTreeNode { keys, children } = self.nodes.remove(right);
+        let l = &mut self.nodes[left];
+        l.keys.push(separator);
+        l.keys.extend(keys);
+        l.children.extend(children);
+    }
+}
+
+impl<T, C> CLIPrintable for BTree<T, C> {
+    fn pretty_name() -> &'static str {
+        "B-Tree"
+    }
+}
+
+impl<T: Debug + Display, C: Comparator<T>> CLITree<T> for BTree<T, C> {
+    // every child of an inner node is there, a B-tree has no empty subtrees to draw
+    const NIL_LEAVES: bool = false;
+
+    fn insert(&mut self, key: T) -> bool {
+        self.insert(key)
+    }
+
+    fn delete(&mut self, key: &T) -> Option<T> {
+        self.delete(key)
+    }
+
+    fn search(&mut self, key: &T) -> bool {
+        self.contains(key)
+    }
+
+    fn count_leaves(&self) -> usize {
+        self.count_leaves()
+    }
+
+    fn get_height(&self) -> usize {
+        self.get_height()
+    }
+
+    fn print_inorder(&self) {
+        self.print_inorder()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.is_empty()
+    }
+
+    fn print_structure(&self) {
+        self.print_structure()
+    }
+
+    fn print_top_down(&self) {
+        self.print_top_down()
+    }
+
+    fn to_dot(&self, _show_nil: bool) -> String {
+        self.to_dot()
+    }
+}
+
+impl<T, C: Default> Default for BTree<T, C> {
+    fn default() -> Self {
+        Self { root: None, nodes: Arena::new(), order: DEFAULT_ORDER, len: 0, compare: C::default() }
+    }
+}
+
+impl<T, C: Comparator<T> + Default> FromIterator<T> for BTree<T, C> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut tree = Self::default();
+        tree.extend(iter);
+        tree
+    }
+}
+
+impl<T, C: Comparator<T>> Extend<T> for BTree<T, C> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for key in iter {
+            self.insert(key);
+        }
+    }
+}
+
+impl<'a, T, C: Comparator<T>> IntoIterator for &'a BTree<T, C> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+// in-order iterator over the keys of a `BTree`
+pub struct Iter<'a, T> {
+    nodes: &'a Arena<BTreeNode<T>>,
+    // the nodes on the way down to the next key, each with the index of its next key
+    stack: Vec<(NodeId, usize)>,
+    remaining: usize,
+}
+
+impl<T> Iter<'_, T> {
+    // go down the first children from `node`, whose first keys all come before the rest
+    fn descend(&mut self, mut node: Option<NodeId>) {
+        while let Some(n) = node {
+            self.stack.push((n, 0));
+            node = self.nodes[n].children.first().copied();
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let nodes = self.nodes;
+        loop {
+            let (node, i) = *self.stack.last()?;
+            if i < nodes[node].keys.len() {
+                // the keys of the child after this key come next
+                self.stack.last_mut().unwrap().1 += 1;
+                self.descend(nodes[node].children.get(i + 1).copied());
+                self.remaining -= 1;
+                return Some(&nodes[node].keys[i]);
+            }
+            self.stack.pop();
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
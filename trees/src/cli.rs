@@ -1,7 +1,16 @@
+use std::fmt::{Debug, Display};
 use std::io::stdin;
 use crate::rb_tree::*;
 use crate::tree::*;
 use crate::avl_tree::*;
+use crate::splay_tree::*;
+use crate::treap::*;
+use crate::llrb_tree::*;
+use crate::aa_tree::*;
+use crate::scapegoat_tree::{self, *};
+use crate::weight_balanced_tree::{self, *};
+use crate::b_tree::*;
+use crate::b_plus_tree::*;
 
 // type names for some basic node types
 pub trait CLIPrintable {
@@ -26,6 +35,74 @@ impl CLIPrintable for char {
     }
 }
 
+// what the tree menu does with a tree. the B-trees implement it themselves and
+// every `Tree` of bare keys can do all of it when wrapped in a `Binary`
+pub trait CLITree<T> {
+    // whether `to_dot` can draw empty subtrees as NIL leaves
+    const NIL_LEAVES: bool = true;
+
+    fn insert(&mut self, key: T) -> bool;
+    fn delete(&mut self, key: &T) -> Option<T>;
+    fn search(&mut self, key: &T) -> bool;
+    fn count_leaves(&self) -> usize;
+    fn get_height(&self) -> usize;
+    fn print_inorder(&self);
+    fn is_empty(&self) -> bool;
+    fn print_structure(&self);
+    fn print_top_down(&self);
+    fn to_dot(&self, show_nil: bool) -> String;
+}
+
+// a binary search tree handed to the tree menu
+pub struct Binary<R>(pub R);
+
+impl<R: CLIPrintable> CLIPrintable for Binary<R> {
+    fn pretty_name() -> &'static str {
+        R::pretty_name()
+    }
+}
+
+impl<T: Debug + Display, R: Tree<T>> CLITree<T> for Binary<R> {
+    fn insert(&mut self, key: T) -> bool {
+        self.0.insert(key)
+    }
+
+    fn delete(&mut self, key: &T) -> Option<T> {
+        self.0.delete(key)
+    }
+
+    fn search(&mut self, key: &T) -> bool {
+        self.0.search(key)
+    }
+
+    fn count_leaves(&self) -> usize {
+        self.0.count_leaves()
+    }
+
+    fn get_height(&self) -> usize {
+        self.0.get_height()
+    }
+
+    fn print_inorder(&self) {
+        self.0.print_inorder()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn print_structure(&self) {
+        self.0.print_structure()
+    }
+
+    fn print_top_down(&self) {
+        self.0.print_top_down()
+    }
+
+    fn to_dot(&self, show_nil: bool) -> String {
+        self.0.to_dot(show_nil)
+    }
+}
 
 fn get_menu_choice(menu: &str, n: u32, ret: &mut u32) {
     // obtaining an integer selection from CLI menu
@@ -33,17 +110,16 @@ fn get_menu_choice(menu: &str, n: u32, ret: &mut u32) {
         println!("{}\n", menu);
         let mut s: String = "".to_string();
 
-        match stdin().read_line(&mut s) {
-            Err(_) => println!("Something went wrong reading input, please try again."),
-            Ok(_) => {}
-        };
+        if stdin().read_line(&mut s).is_err() {
+            println!("Something went wrong reading input, please try again.");
+        }
     
         match s.trim().parse::<u32>() {
             Err(_) => println!("Please enter a valid integer."),
             Ok(p) => {
                 if 1 <= p && p <= n {
                     *ret = p;
-                    println!("");
+                    println!();
                     break;
                 }
             }
@@ -58,22 +134,62 @@ fn get_gen_type<T: std::str::FromStr + CLIPrintable>(ret: &mut T) {
         println!("Please enter a node value of type {name}.");
         let mut s: String = "".to_string();
 
-        match stdin().read_line(&mut s) {
-            Err(_) => println!("Something went wrong reading input, please try again."),
-            Ok(_) => {}
-        };
+        if stdin().read_line(&mut s).is_err() {
+            println!("Something went wrong reading input, please try again.");
+        }
     
         match s.trim().parse::<T>() {
             Err(_) => println!("That input isn't valid, please try again."),
             Ok(p) => {
                 *ret = p;
-                println!("");
+                println!();
                 break;
             }
         }
     }    
 }
 
+fn get_line(prompt: &str) -> String {
+    // read a non-empty line of text from the console
+    loop {
+        println!("{prompt}");
+        let mut s: String = "".to_string();
+
+        if stdin().read_line(&mut s).is_err() {
+            println!("Something went wrong reading input, please try again.");
+            continue;
+        }
+
+        let s = s.trim();
+        if !s.is_empty() {
+            println!();
+            return s.to_string();
+        }
+    }
+}
+
+fn get_order() -> usize {
+    // read the order of a B-tree, the most children one of its nodes may have
+    loop {
+        let s = get_line("Please enter the order of the tree, the most children a node may have (at least 3).");
+        match s.parse::<usize>() {
+            Ok(order) if order >= 3 => return order,
+            _ => println!("Please enter an integer of at least 3."),
+        }
+    }
+}
+
+fn get_alpha(range: &str, valid: fn(f64) -> bool) -> f64 {
+    // read the balance factor of a scapegoat or weight-balanced tree
+    loop {
+        let s = get_line(&format!("Please enter the alpha of the tree ({range})."));
+        match s.parse::<f64>() {
+            Ok(alpha) if valid(alpha) => return alpha,
+            _ => println!("Please enter a number {range}."),
+        }
+    }
+}
+
 fn get_continue() {
     // force user to press enter so menu isn't printed immediately after output
     loop {
@@ -83,20 +199,18 @@ fn get_continue() {
         match stdin().read_line(&mut s) {
             Err(_) => println!("Something went wrong reading input, please try again."),
             Ok(_) => {
-                println!("");
+                println!();
                 break;
             }
         };
     }
 }
 
-fn tree_loop<T, R>() where
+fn tree_loop<T, R>(mut tree: R) where
     T: std::cmp::Ord + Copy + std::fmt::Debug + std::fmt::Display
         + std::default::Default + std::str::FromStr + CLIPrintable,
-    R: Tree<T> + CLIPrintable
+    R: CLITree<T> + CLIPrintable,
 {
-    let mut tree: R = R::new();
-
     println!("> A new {} with {} keys has been created.", R::pretty_name(), T::pretty_name());
 
     loop {
@@ -105,52 +219,91 @@ fn tree_loop<T, R>() where
         get_menu_choice("Please select an operation by entering the corresponding integer:
 1. Insert Node
 2. Delete Node
-3. Count Leaves
-4. Get Height
-5. Print In-Order Traversal
-6. Check if Empty
-7. Print Tree Structure
-8. Go Back (deletes tree)", 8, &mut c);
+3. Search for Node
+4. Count Leaves
+5. Get Height
+6. Print In-Order Traversal
+7. Check if Empty
+8. Print Tree Structure
+9. Export Tree Structure to a Graphviz DOT File
+10. Go Back (deletes tree)", 10, &mut c);
 
         match c {
             1u32 => {
                 // insert
                 let mut node_val: T = T::default();
                 get_gen_type(&mut node_val);
-                tree.insert(node_val);
-                println!("> The node {node_val} was inserted, if it did not already exist in the tree.\n");
+                if tree.insert(node_val) {
+                    println!("> The node {node_val} was inserted.\n");
+                } else {
+                    println!("> The node {node_val} is already in the tree, nothing was inserted.\n");
+                }
             }   
             2u32 => {
                 // delete
                 let mut node_val: T = T::default();
                 get_gen_type(&mut node_val);
-                tree.delete(node_val);
-                println!("> The node {node_val} was deleted, if it existed in the tree.\n");
+                match tree.delete(&node_val) {
+                    Some(key) => println!("> The node {key} was deleted.\n"),
+                    None => println!("> The node {node_val} is not in the tree, nothing was deleted.\n"),
+                }
             }
             3u32 => {
+                // search
+                let mut node_val: T = T::default();
+                get_gen_type(&mut node_val);
+                if tree.search(&node_val) {
+                    println!("> The node {node_val} is in the tree.\n");
+                } else {
+                    println!("> The node {node_val} is not in the tree.\n");
+                }
+            }
+            4u32 => {
                 // count leaves
                 let count = tree.count_leaves();
                 println!("> Leaf count: {count}\n");
             }
-            4u32 => {
+            5u32 => {
                 // hright
                 let height = tree.get_height();
                 println!("> Height: {height}\n\n");
             }
-            5u32 => {
+            6u32 => {
                 tree.print_inorder();
             }
-            6u32 => {
+            7u32 => {
                 if tree.is_empty() {
                     println!("> Tree is empty.\n");
                 } else {
                     println!("> Tree is not empty.\n");
                 }
             }
-            7u32 => {
-                tree.print_structure();
-            }
             8u32 => {
+                let mut style = 0;
+                get_menu_choice("Please select how to draw the tree:
+1. Sideways (root on the left)
+2. Top-Down (root at the top)", 2, &mut style);
+                if style == 1 {
+                    tree.print_structure();
+                } else {
+                    tree.print_top_down();
+                }
+            }
+            9u32 => {
+                // write the tree out as DOT
+                let path = get_line("Please enter the file to write the DOT graph to.");
+                let mut nil = 2;
+                if R::NIL_LEAVES {
+                    get_menu_choice("Should the NIL leaves be drawn?
+1. Yes
+2. No", 2, &mut nil);
+                }
+                match std::fs::write(&path, tree.to_dot(nil == 1)) {
+                    Ok(()) => println!("> The tree was written to {path}, render it with e.g. `dot -Tsvg {path} -o tree.svg`.\n"),
+                    Err(e) => println!("> Could not write to {path}: {e}\n"),
+                }
+            }
+            10u32 => {
                 println!("> Returning to main menu (and deleting this tree)...\n");
                 return;
             }
@@ -170,9 +323,17 @@ pub fn run_cli() {
         get_menu_choice("Please select a type of tree by entering the corresponding integer:
 1. Red-Black Tree
 2. AVL Tree
-3. (Exit Program)", 3, &mut c);
+3. Splay Tree
+4. Treap
+5. Left-Leaning Red-Black Tree
+6. AA Tree
+7. Scapegoat Tree
+8. Weight-Balanced Tree
+9. B-Tree
+10. B+ Tree
+11. (Exit Program)", 11, &mut c);
 
-        if c == 3u32 {
+        if c == 11u32 {
             break;
         }
 
@@ -186,17 +347,85 @@ pub fn run_cli() {
         match c {
             1u32 => {
                 match t {
-                    1u32 => tree_loop::<i8, RedBlackTree<i8>>(),
-                    2u32 => tree_loop::<i32, RedBlackTree<i32>>(),
-                    3u32 => tree_loop::<char, RedBlackTree<char>>(),
+                    1u32 => tree_loop::<i8, _>(Binary(RedBlackTree::new())),
+                    2u32 => tree_loop::<i32, _>(Binary(RedBlackTree::new())),
+                    3u32 => tree_loop::<char, _>(Binary(RedBlackTree::new())),
                     _ => println!("Something went wrong, please try again.\n")
                 }
             },
             2u32 => {
                 match t {
-                    1u32 => tree_loop::<i8, AvlTree<i8>>(),
-                    2u32 => tree_loop::<i32, AvlTree<i32>>(),
-                    3u32 => tree_loop::<char, AvlTree<char>>(),
+                    1u32 => tree_loop::<i8, _>(Binary(AvlTree::new())),
+                    2u32 => tree_loop::<i32, _>(Binary(AvlTree::new())),
+                    3u32 => tree_loop::<char, _>(Binary(AvlTree::new())),
+                    _ => println!("Something went wrong, please try again.\n")
+                }
+            },
+            3u32 => {
+                match t {
+                    1u32 => tree_loop::<i8, _>(Binary(SplayTree::new())),
+                    2u32 => tree_loop::<i32, _>(Binary(SplayTree::new())),
+                    3u32 => tree_loop::<char, _>(Binary(SplayTree::new())),
+                    _ => println!("Something went wrong, please try again.\n")
+                }
+            },
+            4u32 => {
+                match t {
+                    1u32 => tree_loop::<i8, _>(Binary(Treap::new())),
+                    2u32 => tree_loop::<i32, _>(Binary(Treap::new())),
+                    3u32 => tree_loop::<char, _>(Binary(Treap::new())),
+                    _ => println!("Something went wrong, please try again.\n")
+                }
+            },
+            5u32 => {
+                match t {
+                    1u32 => tree_loop::<i8, _>(Binary(LeftLeaningRedBlackTree::new())),
+                    2u32 => tree_loop::<i32, _>(Binary(LeftLeaningRedBlackTree::new())),
+                    3u32 => tree_loop::<char, _>(Binary(LeftLeaningRedBlackTree::new())),
+                    _ => println!("Something went wrong, please try again.\n")
+                }
+            },
+            6u32 => {
+                match t {
+                    1u32 => tree_loop::<i8, _>(Binary(AaTree::new())),
+                    2u32 => tree_loop::<i32, _>(Binary(AaTree::new())),
+                    3u32 => tree_loop::<char, _>(Binary(AaTree::new())),
+                    _ => println!("Something went wrong, please try again.\n")
+                }
+            },
+            7u32 => {
+                let alpha = get_alpha("above 0.5 and less than 1", scapegoat_tree::is_valid_alpha);
+                match t {
+                    1u32 => tree_loop::<i8, _>(Binary(ScapegoatTree::with_alpha(alpha))),
+                    2u32 => tree_loop::<i32, _>(Binary(ScapegoatTree::with_alpha(alpha))),
+                    3u32 => tree_loop::<char, _>(Binary(ScapegoatTree::with_alpha(alpha))),
+                    _ => println!("Something went wrong, please try again.\n")
+                }
+            },
+            8u32 => {
+                let alpha = get_alpha("above 2/11 and at most 1 - 1/sqrt(2), about 0.18 to 0.29", weight_balanced_tree::is_valid_alpha);
+                match t {
+                    1u32 => tree_loop::<i8, _>(Binary(WeightBalancedTree::with_alpha(alpha))),
+                    2u32 => tree_loop::<i32, _>(Binary(WeightBalancedTree::with_alpha(alpha))),
+                    3u32 => tree_loop::<char, _>(Binary(WeightBalancedTree::with_alpha(alpha))),
+                    _ => println!("Something went wrong, please try again.\n")
+                }
+            },
+            9u32 => {
+                let order = get_order();
+                match t {
+                    1u32 => tree_loop::<i8, _>(BTree::with_order(order)),
+                    2u32 => tree_loop::<i32, _>(BTree::with_order(order)),
+                    3u32 => tree_loop::<char, _>(BTree::with_order(order)),
+                    _ => println!("Something went wrong, please try again.\n")
+                }
+            },
+            10u32 => {
+                let order = get_order();
+                match t {
+                    1u32 => tree_loop::<i8, _>(BPlusTree::with_order(order)),
+                    2u32 => tree_loop::<i32, _>(BPlusTree::with_order(order)),
+                    3u32 => tree_loop::<char, _>(BPlusTree::with_order(order)),
                     _ => println!("Something went wrong, please try again.\n")
                 }
             },
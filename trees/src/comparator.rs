@@ -0,0 +1,24 @@
+use std::cmp::Ordering;
+
+// Decides the order of the keys in a tree. Every insert, search and delete goes through
+// the tree's comparator, so keys don't have to be `Ord` themselves.
+pub trait Comparator<T: ?Sized> {
+    fn compare(&self, a: &T, b: &T) -> Ordering;
+}
+
+// the natural order of keys that are `Ord`, the default for every tree
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NaturalOrder;
+
+impl<T: Ord + ?Sized> Comparator<T> for NaturalOrder {
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        a.cmp(b)
+    }
+}
+
+// any closure comparing two keys is a comparator, including boxed ones like `Box<dyn Fn(&T, &T) -> Ordering>`
+impl<T: ?Sized, F: Fn(&T, &T) -> Ordering> Comparator<T> for F {
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        self(a, b)
+    }
+}
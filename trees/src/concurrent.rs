@@ -0,0 +1,109 @@
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use super::tree::*;
+use super::comparator::Comparator;
+use super::rb_tree::RedBlackTree;
+use super::avl_tree::AvlTree;
+
+// A tree that can be shared between threads, e.g. behind an `Arc`.
+// Lookups take a read lock so they run side by side, updates take the write lock for
+// the whole rebalance. The nodes live in one arena, so the lock covers the tree as a whole.
+pub struct ConcurrentTree<T, V = (), B = RedBlackTree<T, V>> {
+    tree: RwLock<B>,
+    marker: PhantomData<(T, V)>,
+}
+
+pub type ConcurrentRedBlackTree<T, V = ()> = ConcurrentTree<T, V, RedBlackTree<T, V>>;
+pub type ConcurrentAvlTree<T, V = ()> = ConcurrentTree<T, V, AvlTree<T, V>>;
+
+impl<T, V, B> ConcurrentTree<T, V, B>
+where
+    B: Tree<T, V>,
+{
+    pub fn new() -> Self where B::Compare: Default {
+        Self::from_tree(B::new())
+    }
+
+    // an empty tree ordering its keys with `compare`
+    pub fn with_comparator(compare: B::Compare) -> Self {
+        Self::from_tree(B::with_comparator(compare))
+    }
+
+    // share a tree that has already been built
+    pub fn from_tree(tree: B) -> Self {
+        Self { tree: RwLock::new(tree), marker: PhantomData }
+    }
+
+    pub fn into_inner(self) -> B {
+        self.tree.into_inner().expect("a thread panicked while updating the tree")
+    }
+
+    // hold the read lock, for running several queries against the same version of the tree
+    pub fn read(&self) -> RwLockReadGuard<'_, B> {
+        // a panic in the middle of a rebalance may have left the tree broken, so don't go on
+        self.tree.read().expect("a thread panicked while updating the tree")
+    }
+
+    // hold the write lock, for several updates that have to happen together
+    pub fn write(&self) -> RwLockWriteGuard<'_, B> {
+        self.tree.write().expect("a thread panicked while updating the tree")
+    }
+
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    pub fn contains<Q: ?Sized>(&self, key: &Q) -> bool where T: Borrow<Q>, B::Compare: Comparator<Q> {
+        self.read().bst_search(key)
+    }
+
+    // a copy of the value stored with a key, the lock is released before it is returned
+    pub fn get<Q: ?Sized>(&self, key: &Q) -> Option<V> where T: Borrow<Q>, B::Compare: Comparator<Q>, V: Clone {
+        let tree = self.read();
+        let node = tree.bst_find(tree.get_root(), key)?;
+        Some(tree.get_value(node).clone())
+    }
+
+    // insert a key with a default value, returning false if it was already in the tree
+    pub fn insert(&self, key: T) -> bool where V: Default {
+        self.write().insert(key)
+    }
+
+    // insert a key with its value, returning the value it replaced if the key was already present
+    pub fn insert_entry(&self, key: T, value: V) -> Option<V> {
+        self.write().insert_entry(key, value)
+    }
+
+    // remove a key, returning the stored key if it was there
+    pub fn delete<Q: ?Sized>(&self, key: &Q) -> Option<T> where T: Borrow<Q>, B::Compare: Comparator<Q> {
+        self.write().delete(key)
+    }
+
+    pub fn remove_entry<Q: ?Sized>(&self, key: &Q) -> Option<(T, V)> where T: Borrow<Q>, B::Compare: Comparator<Q> {
+        self.write().remove_entry(key)
+    }
+}
+
+impl<T, V, B> Default for ConcurrentTree<T, V, B>
+where
+    B: Tree<T, V>,
+    B::Compare: Default,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, V, B> From<B> for ConcurrentTree<T, V, B>
+where
+    B: Tree<T, V>,
+{
+    fn from(tree: B) -> Self {
+        Self::from_tree(tree)
+    }
+}
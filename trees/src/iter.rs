@@ -0,0 +1,162 @@
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use super::arena::*;
+use super::node::*;
+
+// In-order iterator over the keys of a tree.
+// It keeps a node at each end and walks parent/child links towards the other end,
+// so it can be consumed from the front and the back at the same time.
+pub struct Iter<'a, T, V, N> {
+    nodes: &'a Arena<N>,
+    front: Option<NodeId>,
+    back: Option<NodeId>,
+    marker: PhantomData<(T, V)>,
+}
+
+// Like `Iter`, but yields each key together with its value.
+pub struct Entries<'a, T, V, N> {
+    iter: Iter<'a, T, V, N>,
+}
+
+// Owning version of `Iter`, takes the nodes over from the tree it was made from.
+// The in-order sequence of nodes is worked out up front, so each node can be taken
+// out of the arena as its key is handed out without breaking the links still to be followed.
+pub struct IntoIter<T, V, N> {
+    nodes: Arena<N>,
+    order: std::vec::IntoIter<NodeId>,
+    marker: PhantomData<(T, V)>,
+}
+
+impl<'a, T, V, N: Node<T, V>> Iter<'a, T, V, N> {
+    // iterate the keys from `front` to `back` inclusive, an empty iterator if either is missing
+    pub(crate) fn new(nodes: &'a Arena<N>, front: Option<NodeId>, back: Option<NodeId>) -> Self {
+        match (front, back) {
+            (Some(f), Some(b)) => Self { nodes, front: Some(f), back: Some(b), marker: PhantomData },
+            _ => Self { nodes, front: None, back: None, marker: PhantomData },
+        }
+    }
+
+    // the node at one end, moving that end one step towards the other
+    fn next_id(&mut self, side: Side) -> Option<NodeId> {
+        step(self.nodes, &mut self.front, &mut self.back, side)
+    }
+}
+
+impl<'a, T: 'a, V, N: Node<T, V>> Iterator for Iter<'a, T, V, N> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let node = self.next_id(Side::Right)?;
+        Some(self.nodes[node].get_key())
+    }
+}
+
+impl<'a, T: 'a, V, N: Node<T, V>> DoubleEndedIterator for Iter<'a, T, V, N> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        let node = self.next_id(Side::Left)?;
+        Some(self.nodes[node].get_key())
+    }
+}
+
+impl<'a, T: 'a, V, N: Node<T, V>> FusedIterator for Iter<'a, T, V, N> {}
+
+impl<'a, T, V, N: Node<T, V>> Entries<'a, T, V, N> {
+    pub(crate) fn new(iter: Iter<'a, T, V, N>) -> Self {
+        Self { iter }
+    }
+}
+
+impl<'a, T: 'a, V: 'a, N: Node<T, V>> Iterator for Entries<'a, T, V, N> {
+    type Item = (&'a T, &'a V);
+
+    fn next(&mut self) -> Option<(&'a T, &'a V)> {
+        let node = &self.iter.nodes[self.iter.next_id(Side::Right)?];
+        Some((node.get_key(), node.get_value()))
+    }
+}
+
+impl<'a, T: 'a, V: 'a, N: Node<T, V>> DoubleEndedIterator for Entries<'a, T, V, N> {
+    fn next_back(&mut self) -> Option<(&'a T, &'a V)> {
+        let node = &self.iter.nodes[self.iter.next_id(Side::Left)?];
+        Some((node.get_key(), node.get_value()))
+    }
+}
+
+impl<'a, T: 'a, V: 'a, N: Node<T, V>> FusedIterator for Entries<'a, T, V, N> {}
+
+impl<T, V, N: Node<T, V>> IntoIter<T, V, N> {
+    pub(crate) fn new(nodes: Arena<N>, root: Option<NodeId>) -> Self {
+        let mut front = root.map(|r| last_node(&nodes, r, Side::Left));
+        let mut back = root.map(|r| last_node(&nodes, r, Side::Right));
+        let mut order = Vec::with_capacity(nodes.len());
+        while let Some(node) = step(&nodes, &mut front, &mut back, Side::Right) {
+            order.push(node);
+        }
+        Self { nodes, order: order.into_iter(), marker: PhantomData }
+    }
+}
+
+impl<T, V, N: Node<T, V>> Iterator for IntoIter<T, V, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let node = self.order.next()?;
+        Some(self.nodes.remove(node).into_entry().0)
+    }
+}
+
+impl<T, V, N: Node<T, V>> DoubleEndedIterator for IntoIter<T, V, N> {
+    fn next_back(&mut self) -> Option<T> {
+        let node = self.order.next_back()?;
+        Some(self.nodes.remove(node).into_entry().0)
+    }
+}
+
+impl<T, V, N: Node<T, V>> FusedIterator for IntoIter<T, V, N> {}
+
+// take the node at one end (front for Right, back for Left) and move that end one step towards the other
+fn step<T, V, N: Node<T, V>>(nodes: &Arena<N>, front: &mut Option<NodeId>, back: &mut Option<NodeId>, side: Side) -> Option<NodeId> {
+    let node = match side {
+        Side::Right => (*front)?,
+        _ => (*back)?,
+    };
+
+    if *front == *back {
+        // both ends met, nothing left after this key
+        *front = None;
+        *back = None;
+    } else if side == Side::Right {
+        *front = next_node(nodes, node, Side::Right);
+    } else {
+        *back = next_node(nodes, node, Side::Left);
+    }
+    Some(node)
+}
+
+// follow children on one side as far as possible, i.e. the minimum (Left) or maximum (Right) of a subtree
+pub(crate) fn last_node<T, V, N: Node<T, V>>(nodes: &Arena<N>, node: NodeId, side: Side) -> NodeId {
+    let mut current = node;
+    while let Some(c) = nodes[current].get_child(side) {
+        current = c;
+    }
+    current
+}
+
+// the in-order successor (Right) or predecessor (Left) of a node
+pub(crate) fn next_node<T, V, N: Node<T, V>>(nodes: &Arena<N>, node: NodeId, side: Side) -> Option<NodeId> {
+    // if there is a subtree on that side, the next node is at its far opposite end
+    if let Some(c) = nodes[node].get_child(side) {
+        return Some(last_node(nodes, c, !side));
+    }
+
+    // otherwise climb until we come up from the opposite side
+    let mut current = node;
+    loop {
+        let n = &nodes[current];
+        match n.get_parent() {
+            None => return None,
+            Some(p) if n.is_child(side) => current = p,
+            Some(p) => return Some(p),
+        }
+    }
+}
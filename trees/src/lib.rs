@@ -1,5 +1,26 @@
 pub mod tree;
+pub mod arena;
+pub mod comparator;
 pub mod node;
+pub mod iter;
+pub mod validate;
 pub mod rb_tree;
 pub mod avl_tree;
-mod cli;
+pub mod splay_tree;
+pub mod treap;
+pub mod llrb_tree;
+pub mod aa_tree;
+pub mod scapegoat_tree;
+pub mod weight_balanced_tree;
+mod multiway;
+pub mod b_tree;
+pub mod b_plus_tree;
+pub mod persistent;
+pub mod persistent_rb_tree;
+pub mod persistent_avl_tree;
+pub mod tree_map;
+pub mod multiset;
+pub mod concurrent;
+#[cfg(feature = "serde")]
+pub mod serialize;
+pub mod cli;
@@ -0,0 +1,295 @@
+use std::cmp::Ordering;
+use crate::tree::*;
+use crate::node::*;
+use crate::arena::Arena;
+use crate::cli::CLIPrintable;
+use crate::comparator::{Comparator, NaturalOrder};
+use crate::rb_tree::NodeColor;
+use crate::validate::ValidationError;
+
+type MaybeLlrbTree = Option<NodeId>;
+
+// the balancing data of a left-leaning red-black node is its colour
+pub type LlrbTreeNode<T, V = ()> = LinkedNode<T, V, NodeColor>;
+
+// Left-leaning red-black tree (Sedgewick). A red-black tree where a red node is always a left
+// child, so it has exactly one shape for each 2-3 tree and every fix after an insert or a delete
+// is the same three local steps on the way back up. Deleting needs no nil sentinels: the way
+// down borrows a red link at each step so that the node finally taken out is red.
+pub struct LeftLeaningRedBlackTree<T, V = (), C = NaturalOrder> {
+    root: MaybeLlrbTree,
+    nodes: Arena<LlrbTreeNode<T, V>>,
+    compare: C,
+}
+
+impl<T, V, C: Comparator<T>> Tree<T, V> for LeftLeaningRedBlackTree<T, V, C> {
+    type Node = LlrbTreeNode<T, V>;
+    type Compare = C;
+
+    fn with_comparator(compare: C) -> Self {
+        Self { root: None, nodes: Arena::new(), compare }
+    }
+
+    fn comparator(&self) -> &C {
+        &self.compare
+    }
+
+    fn get_root(&self) -> MaybeLlrbTree {
+        self.root
+    }
+
+    fn set_root(&mut self, node: MaybeLlrbTree) {
+        self.root = node;
+        if let Some(n) = node {
+            self.set_color(n, NodeColor::Black);
+        }
+    }
+
+    fn nodes(&self) -> &Arena<LlrbTreeNode<T, V>> {
+        &self.nodes
+    }
+
+    fn nodes_mut(&mut self) -> &mut Arena<LlrbTreeNode<T, V>> {
+        &mut self.nodes
+    }
+
+    fn insert_fix(&mut self, node: NodeId) -> NodeId {
+        // the new node is red, each node above it is put right on the way back up
+        let mut top = node;
+        while let Some(parent) = self.get_parent(top) {
+            top = self.balance(parent);
+        }
+        top
+    }
+
+    fn build_fix(&mut self, _node: NodeId, _depth: usize, _levels: usize) {
+        // `link_sorted` colours the nodes as it links them
+    }
+
+    fn link_sorted(&mut self, ids: &[NodeId], _depth: usize, _levels: usize) -> MaybeLlrbTree {
+        // a perfectly balanced tree can have red nodes on the right, a 2-3 tree can't
+        self.link_2_3(ids, height_2_3(ids.len()))
+    }
+
+    fn join_nodes(&mut self, left: MaybeLlrbTree, node: NodeId, right: MaybeLlrbTree) -> NodeId {
+        // same as the red-black join, the fix after it leans the reds left again
+        self.join_colored(left, node, right)
+    }
+
+    fn remove_node(&mut self, node: NodeId) -> (T, V) {
+        let root = self.root.unwrap();
+        // a red root gives the way down a red link to start from
+        if !self.is_red(self.left(root)) && !self.is_red(self.right(root)) {
+            self.set_color(root, NodeColor::Red);
+        }
+        let (root, removed) = self.delete_below(root, node);
+        self.set_root(root);
+        self.nodes.remove(removed).into_entry()
+    }
+
+    fn structure_label(&self, node: NodeId) -> String where T: std::fmt::Display {
+        format!("{} ({})", self.get_key(node), self.get_color(node).letter())
+    }
+
+    fn dot_attributes(&self, node: NodeId) -> String where T: std::fmt::Display {
+        let fill = self.get_color(node).name();
+        format!("label=\"{}\", style=filled, fillcolor={fill}, fontcolor=white", dot_escape(self.get_key(node)))
+    }
+
+    fn validate<'a>(&'a self) -> Result<(), ValidationError<&'a T>> where Self::Node: 'a {
+        self.validate_bst()?;
+        if let Some(root) = self.root {
+            if self.is_red(Some(root)) {
+                return Err(ValidationError::RedRoot { key: self.get_key(root) });
+            }
+            self.validate_colors(root)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T, V, C: Comparator<T>> RedBlack<T, V> for LeftLeaningRedBlackTree<T, V, C> {
+    fn get_color(&self, node: NodeId) -> NodeColor {
+        *self.nodes[node].get_balance()
+    }
+
+    fn set_color(&mut self, node: NodeId, color: NodeColor) {
+        self.nodes[node].set_balance(color);
+    }
+}
+
+// a 3-node is a black node with its smaller key as a red left child
+impl<T, V, C: Comparator<T>> TwoThreeTree<T, V> for LeftLeaningRedBlackTree<T, V, C> {
+    fn set_2_3_height(&mut self, node: NodeId, _height: usize) {
+        self.set_color(node, NodeColor::Black);
+    }
+
+    fn link_3_node(&mut self, low: NodeId, high: NodeId, [left, between, right]: [MaybeLlrbTree; 3], _height: usize) -> NodeId {
+        self.set_children(low, left, between);
+        self.set_color(low, NodeColor::Red);
+        self.update_size(low);
+        self.set_children(high, Some(low), right);
+        high
+    }
+}
+
+impl<T, V, C: Comparator<T>> LeftLeaningRedBlackTree<T, V, C> {
+    // rotate towards `side`, the child coming up takes the node's colour and the node turns red
+    fn rotate_colored(&mut self, side: Side, node: NodeId) -> NodeId {
+        let child = self.get_child(node, !side).unwrap();
+        self.rotate(side, node);
+        self.set_color(child, self.get_color(node));
+        self.set_color(node, NodeColor::Red);
+        child
+    }
+
+    // a node and its two children all change colour, splitting or forming a 4-node
+    fn flip_colors(&mut self, node: NodeId) {
+        for n in [Some(node), self.left(node), self.right(node)].into_iter().flatten() {
+            let flipped = match self.get_color(n) {
+                NodeColor::Red => NodeColor::Black,
+                NodeColor::Black => NodeColor::Red,
+            };
+            self.set_color(n, flipped);
+        }
+    }
+
+    // restore the rules at a node whose children are valid, returning the root of its subtree
+    fn balance(&mut self, node: NodeId) -> NodeId {
+        self.update_size(node);
+        let mut node = node;
+        if self.is_red(self.right(node)) && !self.is_red(self.left(node)) {
+            node = self.rotate_colored(Side::Left, node);
+        }
+        if self.is_red(self.left(node)) && self.is_red(self.left(node).and_then(|l| self.left(l))) {
+            node = self.rotate_colored(Side::Right, node);
+        }
+        if self.is_red(self.left(node)) && self.is_red(self.right(node)) {
+            self.flip_colors(node);
+        }
+        node
+    }
+
+    // the node is red and both its left child and that child's left child are black,
+    // make one of them red by borrowing from the right
+    fn move_red_left(&mut self, node: NodeId) -> NodeId {
+        self.flip_colors(node);
+        let right = self.right(node).unwrap();
+        if self.is_red(self.left(right)) {
+            self.rotate_colored(Side::Right, right);
+            let node = self.rotate_colored(Side::Left, node);
+            self.flip_colors(node);
+            return node;
+        }
+        node
+    }
+
+    // the same on the right side, borrowing from the left
+    fn move_red_right(&mut self, node: NodeId) -> NodeId {
+        self.flip_colors(node);
+        if self.is_red(self.left(node).and_then(|l| self.left(l))) {
+            let node = self.rotate_colored(Side::Right, node);
+            self.flip_colors(node);
+            return node;
+        }
+        node
+    }
+
+    // link the result of deleting below a node back in as its child
+    fn relink(&mut self, node: NodeId, side: Side, child: MaybeLlrbTree) {
+        self.set_child(node, side, child);
+        if let Some(c) = child {
+            self.set_parent(c, Some(side), Some(node));
+        }
+    }
+
+    // take the smallest node out of a subtree, returning the new root of the subtree and the node
+    fn delete_min(&mut self, node: NodeId) -> (MaybeLlrbTree, NodeId) {
+        let left = match self.left(node) {
+            None => return (None, node),
+            Some(l) => l,
+        };
+        let mut node = node;
+        if !self.is_red(Some(left)) && !self.is_red(self.left(left)) {
+            node = self.move_red_left(node);
+        }
+        let (child, removed) = self.delete_min(self.left(node).unwrap());
+        self.relink(node, Side::Left, child);
+        (Some(self.balance(node)), removed)
+    }
+
+    // take the entry of `target` out of the subtree at `node`, returning the new root of the subtree
+    // and the node to free, which holds the entry by then
+    fn delete_below(&mut self, node: NodeId, target: NodeId) -> (MaybeLlrbTree, NodeId) {
+        let mut node = node;
+        let removed;
+        if node != target && self.compare(self.get_key(target), self.get_key(node)) == Ordering::Less {
+            let left = self.left(node).unwrap();
+            if !self.is_red(Some(left)) && !self.is_red(self.left(left)) {
+                node = self.move_red_left(node);
+            }
+            let (child, taken) = self.delete_below(self.left(node).unwrap(), target);
+            self.relink(node, Side::Left, child);
+            removed = taken;
+        } else {
+            if self.is_red(self.left(node)) {
+                node = self.rotate_colored(Side::Right, node);
+            }
+            if node == target && self.right(node).is_none() {
+                return (None, node);
+            }
+            let right = self.right(node).unwrap();
+            if !self.is_red(Some(right)) && !self.is_red(self.left(right)) {
+                node = self.move_red_right(node);
+            }
+            let right = self.right(node).unwrap();
+            let (child, taken) = if node == target {
+                // the smallest key on the right moves up in its place and that node is taken out instead
+                self.swap_entry(node, self.find_min(right));
+                self.delete_min(right)
+            } else {
+                self.delete_below(right, target)
+            };
+            self.relink(node, Side::Right, child);
+            removed = taken;
+        }
+        (Some(self.balance(node)), removed)
+    }
+
+    // black height of a subtree, checking that no red node is a right child or has a red child
+    fn validate_colors(&self, node: NodeId) -> Result<usize, ValidationError<&T>> {
+        let key = self.get_key(node);
+        let mut heights = [0, 0];
+        for (i, side) in [Side::Left, Side::Right].into_iter().enumerate() {
+            if let Some(child) = self.get_child(node, side) {
+                if self.is_red(Some(child)) {
+                    if self.is_red(Some(node)) {
+                        return Err(ValidationError::RedRed { key: self.get_key(child), parent: key });
+                    }
+                    if side == Side::Right {
+                        return Err(ValidationError::RightRed { key: self.get_key(child), parent: key });
+                    }
+                }
+                heights[i] = self.validate_colors(child)?;
+            }
+        }
+        if heights[0] != heights[1] {
+            return Err(ValidationError::BlackHeight { key, left: heights[0], right: heights[1] });
+        }
+        Ok(heights[0] + usize::from(!self.is_red(Some(node))))
+    }
+}
+
+impl<T, V, C> CLIPrintable for LeftLeaningRedBlackTree<T, V, C> {
+    fn pretty_name() -> &'static str {
+        "Left-Leaning Red-Black Tree"
+    }
+}
+
+tree_impls!(LeftLeaningRedBlackTree, LlrbTreeNode);
+
+impl<T, V, C: Default> Default for LeftLeaningRedBlackTree<T, V, C> {
+    fn default() -> Self {
+        Self { root: None, nodes: Arena::new(), compare: C::default() }
+    }
+}
@@ -0,0 +1,156 @@
+use std::borrow::Borrow;
+use std::iter::repeat_n;
+use std::marker::PhantomData;
+use super::tree::*;
+use super::comparator::Comparator;
+use super::rb_tree::RedBlackTree;
+use super::avl_tree::AvlTree;
+
+// A sorted multiset, keys can be inserted more than once.
+// Each distinct key is stored once in the backing tree with its number of occurrences as the value,
+// so keys that compare equal share one node and the first one inserted is the one that is kept.
+pub struct TreeMultiSet<T, B = RedBlackTree<T, usize>> {
+    tree: B,
+    // total number of occurrences, the tree only counts distinct keys
+    len: usize,
+    marker: PhantomData<T>,
+}
+
+pub type RedBlackMultiSet<T> = TreeMultiSet<T, RedBlackTree<T, usize>>;
+pub type AvlMultiSet<T> = TreeMultiSet<T, AvlTree<T, usize>>;
+
+impl<T, B> TreeMultiSet<T, B>
+where
+    B: Tree<T, usize>,
+{
+    pub fn new() -> Self where B::Compare: Default {
+        Self { tree: B::new(), len: 0, marker: PhantomData }
+    }
+
+    // an empty multiset ordering its keys with `compare`
+    pub fn with_comparator(compare: B::Compare) -> Self {
+        Self { tree: B::with_comparator(compare), len: 0, marker: PhantomData }
+    }
+
+    // number of keys, counting every occurrence
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    // number of keys, counting each distinct key once
+    pub fn distinct_len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // the underlying tree of distinct keys and their counts
+    pub fn tree(&self) -> &B {
+        &self.tree
+    }
+
+    pub fn contains<Q: ?Sized>(&self, key: &Q) -> bool where T: Borrow<Q>, B::Compare: Comparator<Q> {
+        self.tree.bst_search(key)
+    }
+
+    // how many times the key occurs, 0 if it doesn't
+    pub fn count<Q: ?Sized>(&self, key: &Q) -> usize where T: Borrow<Q>, B::Compare: Comparator<Q> {
+        match self.tree.bst_find(self.tree.get_root(), key) {
+            Some(node) => *self.tree.get_value(node),
+            None => 0,
+        }
+    }
+
+    // add one occurrence of the key, returning how many times it occurs now
+    pub fn insert(&mut self, key: T) -> usize {
+        self.insert_many(key, 1)
+    }
+
+    // add `count` occurrences of the key at once, returning how many times it occurs now
+    pub fn insert_many(&mut self, key: T, count: usize) -> usize {
+        if count == 0 {
+            return self.count(&key);
+        }
+        self.len += count;
+        match self.tree.bst_find(self.tree.get_root(), &key) {
+            Some(node) => {
+                let occurrences = self.tree.get_value_mut(node);
+                *occurrences += count;
+                *occurrences
+            }
+            None => {
+                self.tree.insert_new(key, count);
+                count
+            }
+        }
+    }
+
+    // remove a single occurrence of the key, returning false if it wasn't there.
+    // the key leaves the tree along with its last occurrence
+    pub fn remove_one<Q: ?Sized>(&mut self, key: &Q) -> bool where T: Borrow<Q>, B::Compare: Comparator<Q> {
+        let node = match self.tree.bst_find(self.tree.get_root(), key) {
+            Some(node) => node,
+            None => return false,
+        };
+        let occurrences = self.tree.get_value_mut(node);
+        if *occurrences > 1 {
+            *occurrences -= 1;
+        } else {
+            self.tree.remove_node(node);
+        }
+        self.len -= 1;
+        true
+    }
+
+    // remove every occurrence of the key, returning how many there were
+    pub fn remove_all<Q: ?Sized>(&mut self, key: &Q) -> usize where T: Borrow<Q>, B::Compare: Comparator<Q> {
+        let count = self.tree.remove_entry(key).map_or(0, |(_, count)| count);
+        self.len -= count;
+        count
+    }
+
+    // in-order iterator over the keys, each repeated as many times as it occurs
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> + '_ {
+        self.tree.entries().flat_map(|(key, &count)| repeat_n(key, count))
+    }
+
+    // in-order iterator over the distinct keys and how many times each occurs
+    pub fn counts(&self) -> impl DoubleEndedIterator<Item = (&T, usize)> + '_ {
+        self.tree.entries().map(|(key, &count)| (key, count))
+    }
+}
+
+impl<T, B> Default for TreeMultiSet<T, B>
+where
+    B: Tree<T, usize>,
+    B::Compare: Default,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, B> FromIterator<T> for TreeMultiSet<T, B>
+where
+    B: Tree<T, usize>,
+    B::Compare: Default,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<T, B> Extend<T> for TreeMultiSet<T, B>
+where
+    B: Tree<T, usize>,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for key in iter {
+            self.insert(key);
+        }
+    }
+}
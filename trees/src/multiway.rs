@@ -0,0 +1,257 @@
+use std::cmp::Ordering;
+use std::fmt::{Debug, Display, Write};
+use crate::arena::Arena;
+use crate::comparator::Comparator;
+use crate::node::{NodeId, Side};
+use crate::tree::dot_escape;
+use crate::validate::ValidationError;
+
+// The parts the B-tree and the B+ tree share. Both keep their nodes in an arena, every node
+// holding a sorted run of keys and, unless it is a leaf, one more child than it has keys.
+pub(crate) trait MultiwayNode<T> {
+    fn keys(&self) -> &[T];
+    fn children(&self) -> &[NodeId];
+
+    // the leaf after this one, for trees that link their leaves
+    fn next_leaf(&self) -> Option<NodeId> {
+        None
+    }
+
+    fn is_leaf(&self) -> bool {
+        self.children().is_empty()
+    }
+}
+
+// the fewest keys a node other than the root may hold, low enough that splitting a full node
+// gives two nodes with at least this many and high enough that two short nodes fit in one
+pub(crate) fn min_keys(order: usize) -> usize {
+    order.div_ceil(2) - 1
+}
+
+// number of levels, every leaf is on the last one
+pub(crate) fn height<T, N: MultiwayNode<T>>(nodes: &Arena<N>, root: Option<NodeId>) -> usize {
+    let mut height = 0;
+    let mut current = root;
+    while let Some(node) = current {
+        height += 1;
+        current = nodes[node].children().first().copied();
+    }
+    height
+}
+
+pub(crate) fn count_leaves<T, N: MultiwayNode<T>>(nodes: &Arena<N>, root: Option<NodeId>) -> usize {
+    let mut count = 0;
+    let mut stack: Vec<NodeId> = root.into_iter().collect();
+    while let Some(node) = stack.pop() {
+        if nodes[node].is_leaf() {
+            count += 1;
+        }
+        stack.extend(nodes[node].children());
+    }
+    count
+}
+
+// the keys of a node as they are printed, e.g. `[1 2 3]`
+fn label<T: Display>(keys: &[T]) -> String {
+    let keys: Vec<String> = keys.iter().map(|k| k.to_string()).collect();
+    format!("[{}]", keys.join(" "))
+}
+
+// the tree on its side like `Tree::print_structure`, with the root on the left,
+// the first half of a node's children above it and the rest below
+pub(crate) fn format_sideways<T: Display, N: MultiwayNode<T>>(nodes: &Arena<N>, root: Option<NodeId>) -> String {
+    let mut out = String::new();
+    if let Some(r) = root {
+        sideways(nodes, r, 0, &mut out);
+    }
+    out
+}
+
+fn sideways<T: Display, N: MultiwayNode<T>>(nodes: &Arena<N>, node: NodeId, depth: usize, out: &mut String) {
+    let children = nodes[node].children();
+    let half = children.len().div_ceil(2);
+    for &child in &children[..half] {
+        sideways(nodes, child, depth + 1, out);
+    }
+    writeln!(out, "{: <1$}{2}", "", depth * 8, label(nodes[node].keys())).unwrap();
+    for &child in &children[half..] {
+        sideways(nodes, child, depth + 1, out);
+    }
+}
+
+// the tree drawn top-down, one centred line per level with its nodes from left to right
+pub(crate) fn format_levels<T: Display, N: MultiwayNode<T>>(nodes: &Arena<N>, root: Option<NodeId>) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut level: Vec<NodeId> = root.into_iter().collect();
+    while !level.is_empty() {
+        let labels: Vec<String> = level.iter().map(|&n| label(nodes[n].keys())).collect();
+        lines.push(labels.join("  "));
+        level = level.iter().flat_map(|&n| nodes[n].children().iter().copied()).collect();
+    }
+
+    let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    let centred: Vec<String> = lines.iter()
+        .map(|l| format!("{: <1$}{2}", "", (width - l.chars().count()) / 2, l))
+        .collect();
+    centred.join("\n")
+}
+
+// a key as it can go inside a DOT record label, where braces, bars and angle brackets mean something
+fn record_escape<T: Display>(key: &T) -> String {
+    let mut escaped = String::new();
+    for c in dot_escape(key).chars() {
+        if "{}|<>".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// the tree as a Graphviz DOT graph, each node a record of its keys with the edge to a child
+// leaving from the gap between the keys that bound it. links between leaves are drawn dashed
+pub(crate) fn to_dot<T: Display, N: MultiwayNode<T>>(nodes: &Arena<N>, root: Option<NodeId>) -> String {
+    let mut dot = String::from("digraph Tree {\n    node [shape=record, fontname=\"Helvetica\"];\n");
+    let mut stack: Vec<NodeId> = root.into_iter().collect();
+    while let Some(node) = stack.pop() {
+        let (keys, children) = (nodes[node].keys(), nodes[node].children());
+        let mut fields: Vec<String> = Vec::new();
+        for (i, key) in keys.iter().enumerate() {
+            if !children.is_empty() {
+                fields.push(format!("<c{i}>"));
+            }
+            fields.push(record_escape(key));
+        }
+        if !children.is_empty() {
+            fields.push(format!("<c{}>", keys.len()));
+        }
+        writeln!(dot, "    n{node} [label=\"{}\"];", fields.join("|")).unwrap();
+
+        for (i, child) in children.iter().enumerate() {
+            writeln!(dot, "    n{node}:c{i} -> n{child};").unwrap();
+        }
+        if let Some(next) = nodes[node].next_leaf() {
+            writeln!(dot, "    n{node} -> n{next} [style=dashed, constraint=false];").unwrap();
+        }
+        // the first child goes on top of the stack so the tree is written out left to right
+        stack.extend(children.iter().rev());
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+// print the keys in order like `Tree::print_inorder`. the trees hand in their own iterators,
+// since the inner keys of a B+ tree are only copies of keys in its leaves
+pub(crate) fn print_inorder<'a, T: Debug + 'a>(keys: impl IntoIterator<Item = &'a T>) {
+    println!("-------- Tree In-Order -------");
+    let mut keys = keys.into_iter().peekable();
+    if keys.peek().is_none() {
+        println!("Empty tree");
+    }
+    for key in keys {
+        println!("{key:?}");
+    }
+    println!("------------------------------");
+}
+
+// print the tree on its side like `Tree::print_structure`
+pub(crate) fn print_structure<T: Display, N: MultiwayNode<T>>(nodes: &Arena<N>, root: Option<NodeId>) {
+    println!("------- Tree Structure -------");
+    match root {
+        Some(_) => print!("{}", format_sideways(nodes, root)),
+        None => println!("Empty tree"),
+    }
+    println!("------------------------------");
+}
+
+// print the tree top-down like `Tree::print_top_down`
+pub(crate) fn print_top_down<T: Display, N: MultiwayNode<T>>(nodes: &Arena<N>, root: Option<NodeId>) {
+    println!("------- Tree Structure -------");
+    match root {
+        Some(_) => println!("{}", format_levels(nodes, root)),
+        None => println!("Empty tree"),
+    }
+    println!("------------------------------");
+}
+
+// Checks the rules every multiway tree follows and returns how many keys it holds. With
+// `separators` the inner nodes only hold copies of keys that are in the leaves (a B+ tree),
+// a key may then equal the separator to its left and only the leaves are counted.
+pub(crate) fn validate<'a, T, N, C>(nodes: &'a Arena<N>, root: Option<NodeId>, order: usize, compare: &C, separators: bool) -> Result<usize, ValidationError<&'a T>>
+where
+    N: MultiwayNode<T>,
+    C: Comparator<T>,
+{
+    let mut check = Check { nodes, order, compare, separators, leaf_depth: None, reachable: 0, marker: std::marker::PhantomData };
+    let count = match root {
+        Some(r) => check.node(r, None, None, 0)?,
+        None => 0,
+    };
+    if check.reachable != nodes.len() {
+        return Err(ValidationError::Unreachable { reachable: check.reachable, stored: nodes.len() });
+    }
+    Ok(count)
+}
+
+struct Check<'a, 'c, T, N, C> {
+    nodes: &'a Arena<N>,
+    order: usize,
+    compare: &'c C,
+    separators: bool,
+    leaf_depth: Option<usize>,
+    reachable: usize,
+    marker: std::marker::PhantomData<T>,
+}
+
+impl<'a, T, N: MultiwayNode<T>, C: Comparator<T>> Check<'a, '_, T, N, C> {
+    // check a subtree whose keys have to lie between `low` and `high`
+    fn node(&mut self, node: NodeId, low: Option<&'a T>, high: Option<&'a T>, depth: usize) -> Result<usize, ValidationError<&'a T>> {
+        self.reachable += 1;
+        let nodes = self.nodes;
+        let (keys, children) = (nodes[node].keys(), nodes[node].children());
+
+        let min = if depth == 0 { 1 } else { min_keys(self.order) };
+        if keys.len() < min {
+            return Err(ValidationError::Underfull { key: keys.first(), keys: keys.len(), min });
+        }
+        if keys.len() >= self.order {
+            return Err(ValidationError::Overfull { key: &keys[0], keys: keys.len(), max: self.order - 1 });
+        }
+
+        for (i, key) in keys.iter().enumerate() {
+            if i > 0 && self.compare.compare(&keys[i - 1], key) != Ordering::Less {
+                return Err(ValidationError::OutOfOrder { key, ancestor: &keys[i - 1], side: Side::Right });
+            }
+            if let Some(low) = low {
+                let order = self.compare.compare(key, low);
+                if order == Ordering::Less || (order == Ordering::Equal && !self.separators) {
+                    return Err(ValidationError::OutOfOrder { key, ancestor: low, side: Side::Right });
+                }
+            }
+            if let Some(high) = high {
+                if self.compare.compare(key, high) != Ordering::Less {
+                    return Err(ValidationError::OutOfOrder { key, ancestor: high, side: Side::Left });
+                }
+            }
+        }
+
+        if children.is_empty() {
+            let expected = *self.leaf_depth.get_or_insert(depth);
+            if depth != expected {
+                return Err(ValidationError::LeafDepth { key: &keys[0], depth, expected });
+            }
+            return Ok(keys.len());
+        }
+        if children.len() != keys.len() + 1 {
+            return Err(ValidationError::ChildCount { key: &keys[0], keys: keys.len(), children: children.len() });
+        }
+
+        let mut count = if self.separators { 0 } else { keys.len() };
+        for (i, &child) in children.iter().enumerate() {
+            let low = if i == 0 { low } else { Some(&keys[i - 1]) };
+            let high = if i == keys.len() { high } else { Some(&keys[i]) };
+            count += self.node(child, low, high, depth + 1)?;
+        }
+        Ok(count)
+    }
+}
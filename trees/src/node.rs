@@ -1,8 +1,4 @@
 use std::ops::Not;
-use std::borrow::{Borrow, BorrowMut};
-use std::cell::RefCell;
-use std::rc::Rc;
-use std::cmp::max;
 
 #[derive(Clone, Debug, PartialEq, Copy)]
 pub enum Side {
@@ -22,35 +18,154 @@ impl Not for Side {
     }
 }
 
+// nodes live in their tree's `Arena` and refer to each other by index,
+// parent links included, so there are no reference cycles keeping nodes alive
+pub type NodeId = usize;
+
 pub trait Traversible<T> {
-    // fn left_mut(&mut self) -> &mut Option<Rc<RefCell<Self>>>;
-    // fn right_mut(&mut self) -> &mut Option<Rc<RefCell<Self>>>;
-    fn left(&self) -> &Option<Rc<RefCell<Self>>>;
-    fn right(&self) -> &Option<Rc<RefCell<Self>>>;
+    fn left(&self) -> Option<NodeId>;
+    fn right(&self) -> Option<NodeId>;
 }
-pub trait Node<T: Clone>: Traversible<T> {
-    fn new(key: T) -> Self;
-    fn get_key(&self) -> T; 
-    fn set_key(&mut self, val: T);
+pub trait Node<T, V = ()>: Traversible<T> {
+    fn new(key: T, value: V) -> Self;
+    // keys are borrowed from the node, so they don't have to be `Copy` or even `Clone`
+    fn get_key(&self) -> &T;
+    fn get_key_mut(&mut self) -> &mut T;
 
-    fn greater(&self, val: T) -> bool;
-    fn equal(&self, val: T) -> bool;
-    fn less(&self, val: T) -> bool;
+    fn get_value(&self) -> &V;
+    fn get_value_mut(&mut self) -> &mut V;
+    // take the key and value out of a node that has been removed from its tree
+    fn into_entry(self) -> (T, V);
 
-    fn get_child(&self, side: Side) -> Option<Rc<RefCell<Self>>>;
+    fn get_child(&self, side: Side) -> Option<NodeId>;
     fn get_is_child(&self) -> &Option<Side>;
     fn is_child(&self, side: Side) -> bool;
-    fn take_child(&mut self, side: Side) -> Option<Rc<RefCell<Self>>>;
-    fn set_child(&mut self, side: Side, child: Option<Rc<RefCell<Self>>>);
+    fn take_child(&mut self, side: Side) -> Option<NodeId>;
+    fn set_child(&mut self, side: Side, child: Option<NodeId>);
 
-    fn set_parent(&mut self, is_child: Option<Side>, parent: Option<Rc<RefCell<Self>>>);
-    fn get_parent(&self) -> Option<Rc<RefCell<Self>>>;
-    fn get_parent_mut(&mut self) -> &mut Option<Rc<RefCell<Self>>>;
+    fn set_parent(&mut self, is_child: Option<Side>, parent: Option<NodeId>);
+    fn get_parent(&self) -> Option<NodeId>;
+    fn get_parent_mut(&mut self) -> &mut Option<NodeId>;
 
-    fn get_sibling(&self) -> Option<Rc<RefCell<Self>>>;
-    fn get_uncle(&self) -> Option<Rc<RefCell<Self>>>;
-    fn get_grandparent(&self) -> Option<Rc<RefCell<Self>>>;
-    
     fn is_leaf(&self) -> bool;
 
-}
\ No newline at end of file
+    // number of keys in the subtree rooted at this node
+    fn get_size(&self) -> usize;
+    fn set_size(&mut self, size: usize);
+
+}
+// A plain binary node for trees that keep at most a small piece of balancing data per node,
+// e.g. a treap's priority or an AA tree's level. Trees that need nothing use `()`.
+#[derive(Debug)]
+pub struct LinkedNode<T, V = (), B = ()> {
+    key: T,
+    value: V,
+    balance: B,
+    parent: Option<NodeId>,
+    is_child: Option<Side>,
+    left: Option<NodeId>,
+    right: Option<NodeId>,
+    size: usize,
+}
+
+impl<T, V, B> LinkedNode<T, V, B> {
+    pub fn get_balance(&self) -> &B {
+        &self.balance
+    }
+
+    pub fn set_balance(&mut self, balance: B) {
+        self.balance = balance;
+    }
+}
+
+impl<T, V, B> Traversible<T> for LinkedNode<T, V, B> {
+    fn left(&self) -> Option<NodeId> {
+        self.left
+    }
+    fn right(&self) -> Option<NodeId> {
+        self.right
+    }
+}
+
+impl<T, V, B: Default> Node<T, V> for LinkedNode<T, V, B> {
+    fn new(key: T, value: V) -> Self {
+        Self { key, value, balance: B::default(), parent: None, is_child: None, left: None, right: None, size: 1 }
+    }
+
+    fn get_key(&self) -> &T {
+        &self.key
+    }
+
+    fn get_key_mut(&mut self) -> &mut T {
+        &mut self.key
+    }
+
+    fn get_value(&self) -> &V {
+        &self.value
+    }
+
+    fn get_value_mut(&mut self) -> &mut V {
+        &mut self.value
+    }
+
+    fn into_entry(self) -> (T, V) {
+        (self.key, self.value)
+    }
+
+    fn get_child(&self, side: Side) -> Option<NodeId> {
+        match side {
+            Side::Left => self.left,
+            Side::Right => self.right,
+            Side::Neither => None,
+        }
+    }
+
+    fn get_is_child(&self) -> &Option<Side> {
+        &self.is_child
+    }
+
+    fn is_child(&self, side: Side) -> bool {
+        self.is_child == Some(side)
+    }
+
+    fn take_child(&mut self, side: Side) -> Option<NodeId> {
+        match side {
+            Side::Left => self.left.take(),
+            Side::Right => self.right.take(),
+            Side::Neither => None,
+        }
+    }
+
+    fn set_child(&mut self, side: Side, child: Option<NodeId>) {
+        match side {
+            Side::Left => self.left = child,
+            Side::Right => self.right = child,
+            Side::Neither => {}
+        }
+    }
+
+    fn set_parent(&mut self, is_child: Option<Side>, parent: Option<NodeId>) {
+        self.parent = parent;
+        self.is_child = is_child;
+    }
+
+    fn get_parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    fn get_parent_mut(&mut self) -> &mut Option<NodeId> {
+        &mut self.parent
+    }
+
+    fn is_leaf(&self) -> bool {
+        self.left.is_none() && self.right.is_none()
+    }
+
+    fn get_size(&self) -> usize {
+        self.size
+    }
+
+    fn set_size(&mut self, size: usize) {
+        self.size = size;
+    }
+}
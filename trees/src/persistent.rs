@@ -0,0 +1,149 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::rc::Rc;
+use super::validate::ValidationError;
+
+// Nodes of the persistent trees. They are never changed once built, an update builds
+// new nodes along the path it takes and shares every other subtree with the old version.
+pub trait PersistentNode<T, V> {
+    fn key(&self) -> &T;
+    fn value(&self) -> &V;
+    fn left(&self) -> Option<&Rc<Self>>;
+    fn right(&self) -> Option<&Rc<Self>>;
+    // number of keys in the subtree rooted at this node
+    fn size(&self) -> usize;
+}
+
+// A tree whose updates leave the old version untouched.
+// `insert` and `delete` return the new version and cost O(log n), `clone` only copies the root pointer.
+// Keys and values are cloned into the nodes an update rebuilds, lookups borrow them like `BTreeMap`.
+pub trait PersistentTree<T: Ord + Clone, V: Clone = ()>: Clone {
+    type Node: PersistentNode<T, V>;
+
+    fn new() -> Self;
+    fn root(&self) -> Option<&Rc<Self::Node>>;
+
+    // a new version with the key set to the value, replacing the value if the key was there already
+    fn insert_entry(&self, key: T, value: V) -> Self;
+
+    // a new version without the key, or just a copy of this one if the key wasn't there
+    fn delete<Q: Ord + ?Sized>(&self, key: &Q) -> Self where T: Borrow<Q>;
+
+    // check the tree's invariants, returning the first broken rule found
+    fn validate(&self) -> Result<(), ValidationError<&T>>;
+
+    fn insert(&self, key: T) -> Self where V: Default {
+        self.insert_entry(key, V::default())
+    }
+
+    fn get<'a, Q: Ord + ?Sized>(&'a self, key: &Q) -> Option<&'a V> where T: Borrow<Q>, Self::Node: 'a {
+        let mut node = self.root();
+        while let Some(n) = node {
+            match key.cmp(n.key().borrow()) {
+                Ordering::Less => node = n.left(),
+                Ordering::Greater => node = n.right(),
+                Ordering::Equal => return Some(n.value()),
+            }
+        }
+        None
+    }
+
+    fn contains<Q: Ord + ?Sized>(&self, key: &Q) -> bool where T: Borrow<Q> {
+        self.get(key).is_some()
+    }
+
+    fn len(&self) -> usize {
+        self.root().map_or(0, |r| r.size())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.root().is_none()
+    }
+
+    fn first<'a>(&'a self) -> Option<&'a T> where Self::Node: 'a {
+        let mut node = self.root()?;
+        while let Some(left) = node.left() {
+            node = left;
+        }
+        Some(node.key())
+    }
+
+    fn last<'a>(&'a self) -> Option<&'a T> where Self::Node: 'a {
+        let mut node = self.root()?;
+        while let Some(right) = node.right() {
+            node = right;
+        }
+        Some(node.key())
+    }
+
+    // in-order iterator over the keys of this version
+    fn iter(&self) -> PersistentIter<'_, T, V, Self::Node> {
+        PersistentIter::new(self.root())
+    }
+
+    // true if both versions are the very same tree, without looking at any keys
+    fn ptr_eq(&self, other: &Self) -> bool {
+        match (self.root(), other.root()) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+// In-order iterator over a persistent tree, keeping the nodes still to visit on a stack.
+pub struct PersistentIter<'a, T, V, N> {
+    stack: Vec<&'a Rc<N>>,
+    marker: std::marker::PhantomData<(T, V)>,
+}
+
+impl<'a, T, V, N: PersistentNode<T, V>> PersistentIter<'a, T, V, N> {
+    fn new(root: Option<&'a Rc<N>>) -> Self {
+        let mut iter = Self { stack: Vec::new(), marker: std::marker::PhantomData };
+        iter.push_left(root);
+        iter
+    }
+
+    // stack the node and everything down its left edge
+    fn push_left(&mut self, node: Option<&'a Rc<N>>) {
+        let mut current = node;
+        while let Some(n) = current {
+            self.stack.push(n);
+            current = n.left();
+        }
+    }
+}
+
+impl<'a, T: 'a, V, N: PersistentNode<T, V>> Iterator for PersistentIter<'a, T, V, N> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let node = self.stack.pop()?;
+        self.push_left(node.right());
+        Some(node.key())
+    }
+}
+
+// check that keys are in order and sizes add up below a node, returning the size of the subtree
+pub(crate) fn validate_order<'a, T, V, N>(node: Option<&'a Rc<N>>, low: Option<&'a T>, high: Option<&'a T>) -> Result<usize, ValidationError<&'a T>>
+where
+    T: Ord,
+    N: PersistentNode<T, V>,
+{
+    let n = match node {
+        None => return Ok(0),
+        Some(n) => n,
+    };
+    let key = n.key();
+    if let Some(low) = low.filter(|l| key <= *l) {
+        return Err(ValidationError::OutOfOrder { key, ancestor: low, side: super::node::Side::Right });
+    }
+    if let Some(high) = high.filter(|h| key >= *h) {
+        return Err(ValidationError::OutOfOrder { key, ancestor: high, side: super::node::Side::Left });
+    }
+    let size = 1 + validate_order(n.left(), low, Some(key))? + validate_order(n.right(), Some(key), high)?;
+    if n.size() != size {
+        return Err(ValidationError::WrongSize { key, stored: n.size(), actual: size });
+    }
+    Ok(size)
+}
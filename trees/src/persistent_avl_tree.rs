@@ -0,0 +1,199 @@
+use std::cmp::{max, Ordering};
+use std::borrow::Borrow;
+use std::rc::Rc;
+use super::persistent::*;
+use super::validate::ValidationError;
+
+type Link<T, V> = Option<Rc<PersistentAvlNode<T, V>>>;
+
+#[derive(Debug)]
+pub struct PersistentAvlNode<T, V = ()> {
+    key: T,
+    value: V,
+    left: Link<T, V>,
+    right: Link<T, V>,
+    height: usize,
+    size: usize,
+}
+
+// AVL tree with path copying, every node on the path of an update is rebuilt
+// and rotations build new nodes instead of relinking the old ones.
+pub struct PersistentAvlTree<T, V = ()> {
+    root: Link<T, V>,
+}
+
+impl<T, V> PersistentNode<T, V> for PersistentAvlNode<T, V> {
+    fn key(&self) -> &T {
+        &self.key
+    }
+
+    fn value(&self) -> &V {
+        &self.value
+    }
+
+    fn left(&self) -> Option<&Rc<Self>> {
+        self.left.as_ref()
+    }
+
+    fn right(&self) -> Option<&Rc<Self>> {
+        self.right.as_ref()
+    }
+
+    fn size(&self) -> usize {
+        self.size
+    }
+}
+
+impl<T: Ord + Clone, V: Clone> PersistentTree<T, V> for PersistentAvlTree<T, V> {
+    type Node = PersistentAvlNode<T, V>;
+
+    fn new() -> Self {
+        Self { root: None }
+    }
+
+    fn root(&self) -> Option<&Rc<Self::Node>> {
+        self.root.as_ref()
+    }
+
+    fn insert_entry(&self, key: T, value: V) -> Self {
+        Self { root: ins(&self.root, key, value) }
+    }
+
+    fn delete<Q: Ord + ?Sized>(&self, key: &Q) -> Self where T: Borrow<Q> {
+        // nothing to copy if the key isn't there
+        if !self.contains(key) {
+            return self.clone();
+        }
+        Self { root: del(&self.root, key) }
+    }
+
+    fn validate(&self) -> Result<(), ValidationError<&T>> {
+        validate_order::<T, V, _>(self.root(), None, None)?;
+        validate_heights(&self.root)?;
+        Ok(())
+    }
+}
+
+impl<T, V> Clone for PersistentAvlTree<T, V> {
+    fn clone(&self) -> Self {
+        Self { root: self.root.clone() }
+    }
+}
+
+impl<T> PersistentAvlTree<T> {
+    // a tree of bare keys, so `PersistentAvlTree::new()` works without naming a value type
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<T, V> Default for PersistentAvlTree<T, V> {
+    fn default() -> Self {
+        Self { root: None }
+    }
+}
+
+fn height<T, V>(link: &Link<T, V>) -> usize {
+    link.as_ref().map_or(0, |n| n.height)
+}
+
+// build a node, working out its height and size from its children
+fn make<T, V>(left: Link<T, V>, key: T, value: V, right: Link<T, V>) -> Link<T, V> {
+    let height = 1 + max(height(&left), height(&right));
+    let size = 1 + left.as_ref().map_or(0, |l| l.size) + right.as_ref().map_or(0, |r| r.size);
+    Some(Rc::new(PersistentAvlNode { key, value, left, right, height, size }))
+}
+
+// build a node whose subtrees may differ in height by two, rotating to bring them back within one
+fn balance<T: Clone, V: Clone>(left: Link<T, V>, key: T, value: V, right: Link<T, V>) -> Link<T, V> {
+    let (left_height, right_height) = (height(&left), height(&right));
+
+    if left_height > right_height + 1 {
+        let l = left.as_ref().unwrap();
+        if height(&l.left) >= height(&l.right) {
+            // single right rotation
+            let new_right = make(l.right.clone(), key, value, right);
+            return make(l.left.clone(), l.key.clone(), l.value.clone(), new_right);
+        }
+        // left-right case, the inner grandchild comes up
+        let lr = l.right.as_ref().unwrap();
+        let new_left = make(l.left.clone(), l.key.clone(), l.value.clone(), lr.left.clone());
+        let new_right = make(lr.right.clone(), key, value, right);
+        return make(new_left, lr.key.clone(), lr.value.clone(), new_right);
+    }
+
+    if right_height > left_height + 1 {
+        let r = right.as_ref().unwrap();
+        if height(&r.right) >= height(&r.left) {
+            // single left rotation
+            let new_left = make(left, key, value, r.left.clone());
+            return make(new_left, r.key.clone(), r.value.clone(), r.right.clone());
+        }
+        // right-left case
+        let rl = r.left.as_ref().unwrap();
+        let new_left = make(left, key, value, rl.left.clone());
+        let new_right = make(rl.right.clone(), r.key.clone(), r.value.clone(), r.right.clone());
+        return make(new_left, rl.key.clone(), rl.value.clone(), new_right);
+    }
+
+    make(left, key, value, right)
+}
+
+fn ins<T: Ord + Clone, V: Clone>(link: &Link<T, V>, key: T, value: V) -> Link<T, V> {
+    let n = match link {
+        None => return make(None, key, value, None),
+        Some(n) => n,
+    };
+    match key.cmp(&n.key) {
+        Ordering::Less => balance(ins(&n.left, key, value), n.key.clone(), n.value.clone(), n.right.clone()),
+        Ordering::Greater => balance(n.left.clone(), n.key.clone(), n.value.clone(), ins(&n.right, key, value)),
+        Ordering::Equal => make(n.left.clone(), key, value, n.right.clone()),
+    }
+}
+
+fn del<T: Borrow<Q> + Clone, V: Clone, Q: Ord + ?Sized>(link: &Link<T, V>, key: &Q) -> Link<T, V> {
+    let n = link.as_ref()?;
+    match key.cmp(n.key.borrow()) {
+        Ordering::Less => balance(del(&n.left, key), n.key.clone(), n.value.clone(), n.right.clone()),
+        Ordering::Greater => balance(n.left.clone(), n.key.clone(), n.value.clone(), del(&n.right, key)),
+        Ordering::Equal => match (&n.left, &n.right) {
+            (None, right) => right.clone(),
+            (left, None) => left.clone(),
+            (left, Some(right)) => {
+                // the smallest key on the right takes the place of the deleted one
+                let (rest, min_key, min_value) = remove_min(right);
+                balance(left.clone(), min_key, min_value, rest)
+            }
+        },
+    }
+}
+
+// take the smallest entry out of a subtree, returning what is left and the entry
+fn remove_min<T: Clone, V: Clone>(node: &Rc<PersistentAvlNode<T, V>>) -> (Link<T, V>, T, V) {
+    match &node.left {
+        None => (node.right.clone(), node.key.clone(), node.value.clone()),
+        Some(left) => {
+            let (rest, key, value) = remove_min(left);
+            (balance(rest, node.key.clone(), node.value.clone(), node.right.clone()), key, value)
+        }
+    }
+}
+
+// check the stored heights and balance below a node, returning the real height of its subtree
+fn validate_heights<T, V>(link: &Link<T, V>) -> Result<usize, ValidationError<&T>> {
+    let n = match link {
+        None => return Ok(0),
+        Some(n) => n,
+    };
+    let left = validate_heights(&n.left)?;
+    let right = validate_heights(&n.right)?;
+    let actual = 1 + max(left, right);
+    if n.height != actual {
+        return Err(ValidationError::WrongHeight { key: &n.key, stored: n.height, actual });
+    }
+    let balance = left as isize - right as isize;
+    if !(-1..=1).contains(&balance) {
+        return Err(ValidationError::Unbalanced { key: &n.key, balance });
+    }
+    Ok(actual)
+}
@@ -0,0 +1,307 @@
+use std::cmp::Ordering;
+use std::borrow::Borrow;
+use std::rc::Rc;
+use super::persistent::*;
+use super::validate::ValidationError;
+
+#[derive(Clone, Debug, PartialEq, Copy)]
+enum NodeColor {
+    Red,
+    Black,
+}
+
+type Link<T, V> = Option<Rc<PersistentRedBlackNode<T, V>>>;
+
+#[derive(Debug)]
+pub struct PersistentRedBlackNode<T, V = ()> {
+    color: NodeColor,
+    key: T,
+    value: V,
+    left: Link<T, V>,
+    right: Link<T, V>,
+    size: usize,
+}
+
+// Red-black tree with path copying, following Okasaki's insert and Kahrs' delete.
+// Nodes are rebuilt on the way back up instead of being recoloured and rotated in place.
+pub struct PersistentRedBlackTree<T, V = ()> {
+    root: Link<T, V>,
+}
+
+impl<T, V> PersistentNode<T, V> for PersistentRedBlackNode<T, V> {
+    fn key(&self) -> &T {
+        &self.key
+    }
+
+    fn value(&self) -> &V {
+        &self.value
+    }
+
+    fn left(&self) -> Option<&Rc<Self>> {
+        self.left.as_ref()
+    }
+
+    fn right(&self) -> Option<&Rc<Self>> {
+        self.right.as_ref()
+    }
+
+    fn size(&self) -> usize {
+        self.size
+    }
+}
+
+impl<T: Ord + Clone, V: Clone> PersistentTree<T, V> for PersistentRedBlackTree<T, V> {
+    type Node = PersistentRedBlackNode<T, V>;
+
+    fn new() -> Self {
+        Self { root: None }
+    }
+
+    fn root(&self) -> Option<&Rc<Self::Node>> {
+        self.root.as_ref()
+    }
+
+    fn insert_entry(&self, key: T, value: V) -> Self {
+        Self { root: recolor(ins(&self.root, key, value), NodeColor::Black) }
+    }
+
+    fn delete<Q: Ord + ?Sized>(&self, key: &Q) -> Self where T: Borrow<Q> {
+        // the delete below expects the key to be there, otherwise it may recolour nodes on its way
+        if !self.contains(key) {
+            return self.clone();
+        }
+        Self { root: recolor(del(&self.root, key), NodeColor::Black) }
+    }
+
+    fn validate(&self) -> Result<(), ValidationError<&T>> {
+        if let Some(root) = self.root.as_ref().filter(|r| r.color == NodeColor::Red) {
+            return Err(ValidationError::RedRoot { key: &root.key });
+        }
+        validate_order::<T, V, _>(self.root(), None, None)?;
+        validate_colors(&self.root)?;
+        Ok(())
+    }
+}
+
+impl<T, V> Clone for PersistentRedBlackTree<T, V> {
+    fn clone(&self) -> Self {
+        Self { root: self.root.clone() }
+    }
+}
+
+impl<T> PersistentRedBlackTree<T> {
+    // a tree of bare keys, so `PersistentRedBlackTree::new()` works without naming a value type
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<T, V> Default for PersistentRedBlackTree<T, V> {
+    fn default() -> Self {
+        Self { root: None }
+    }
+}
+
+// build a node, working out its size from its children
+fn make<T, V>(color: NodeColor, left: Link<T, V>, key: T, value: V, right: Link<T, V>) -> Link<T, V> {
+    let size = 1 + left.as_ref().map_or(0, |l| l.size) + right.as_ref().map_or(0, |r| r.size);
+    Some(Rc::new(PersistentRedBlackNode { color, key, value, left, right, size }))
+}
+
+fn is_red<T, V>(link: &Link<T, V>) -> bool {
+    matches!(link, Some(n) if n.color == NodeColor::Red)
+}
+
+// an actual black node, nil leaves don't count
+fn is_black_node<T, V>(link: &Link<T, V>) -> bool {
+    matches!(link, Some(n) if n.color == NodeColor::Black)
+}
+
+// copy of the node with a different colour
+fn recolor<T: Clone, V: Clone>(link: Link<T, V>, color: NodeColor) -> Link<T, V> {
+    match link {
+        Some(n) if n.color != color => make(color, n.left.clone(), n.key.clone(), n.value.clone(), n.right.clone()),
+        other => other,
+    }
+}
+
+// make a black node red, lowering the black height of its subtree by one
+fn sub1<T: Clone, V: Clone>(link: Link<T, V>) -> Link<T, V> {
+    assert!(is_black_node(&link), "red-black invariant broken while deleting");
+    recolor(link, NodeColor::Red)
+}
+
+// build a black node, fixing a red node with a red child right below it
+fn balance<T: Clone, V: Clone>(left: Link<T, V>, key: T, value: V, right: Link<T, V>) -> Link<T, V> {
+    use NodeColor::*;
+
+    // both children red, push the red up a level
+    if is_red(&left) && is_red(&right) {
+        return make(Red, recolor(left, Black), key, value, recolor(right, Black));
+    }
+    if let Some(l) = left.as_ref().filter(|l| l.color == Red) {
+        if let Some(ll) = l.left.as_ref().filter(|n| n.color == Red) {
+            let new_left = make(Black, ll.left.clone(), ll.key.clone(), ll.value.clone(), ll.right.clone());
+            let new_right = make(Black, l.right.clone(), key, value, right);
+            return make(Red, new_left, l.key.clone(), l.value.clone(), new_right);
+        }
+        if let Some(lr) = l.right.as_ref().filter(|n| n.color == Red) {
+            let new_left = make(Black, l.left.clone(), l.key.clone(), l.value.clone(), lr.left.clone());
+            let new_right = make(Black, lr.right.clone(), key, value, right);
+            return make(Red, new_left, lr.key.clone(), lr.value.clone(), new_right);
+        }
+    }
+    if let Some(r) = right.as_ref().filter(|r| r.color == Red) {
+        if let Some(rr) = r.right.as_ref().filter(|n| n.color == Red) {
+            let new_left = make(Black, left, key, value, r.left.clone());
+            let new_right = make(Black, rr.left.clone(), rr.key.clone(), rr.value.clone(), rr.right.clone());
+            return make(Red, new_left, r.key.clone(), r.value.clone(), new_right);
+        }
+        if let Some(rl) = r.left.as_ref().filter(|n| n.color == Red) {
+            let new_left = make(Black, left, key, value, rl.left.clone());
+            let new_right = make(Black, rl.right.clone(), r.key.clone(), r.value.clone(), r.right.clone());
+            return make(Red, new_left, rl.key.clone(), rl.value.clone(), new_right);
+        }
+    }
+    make(Black, left, key, value, right)
+}
+
+fn ins<T: Ord + Clone, V: Clone>(link: &Link<T, V>, key: T, value: V) -> Link<T, V> {
+    let n = match link {
+        None => return make(NodeColor::Red, None, key, value, None),
+        Some(n) => n,
+    };
+    match key.cmp(&n.key) {
+        Ordering::Less => {
+            let left = ins(&n.left, key, value);
+            match n.color {
+                NodeColor::Black => balance(left, n.key.clone(), n.value.clone(), n.right.clone()),
+                NodeColor::Red => make(NodeColor::Red, left, n.key.clone(), n.value.clone(), n.right.clone()),
+            }
+        }
+        Ordering::Greater => {
+            let right = ins(&n.right, key, value);
+            match n.color {
+                NodeColor::Black => balance(n.left.clone(), n.key.clone(), n.value.clone(), right),
+                NodeColor::Red => make(NodeColor::Red, n.left.clone(), n.key.clone(), n.value.clone(), right),
+            }
+        }
+        Ordering::Equal => make(n.color, n.left.clone(), key, value, n.right.clone()),
+    }
+}
+
+// delete a key that is in the subtree, the result may have lost one from its black height
+fn del<T: Borrow<Q> + Clone, V: Clone, Q: Ord + ?Sized>(link: &Link<T, V>, key: &Q) -> Link<T, V> {
+    let n = link.as_ref()?;
+    match key.cmp(n.key.borrow()) {
+        Ordering::Less => {
+            if is_black_node(&n.left) {
+                bal_left(del(&n.left, key), n.key.clone(), n.value.clone(), n.right.clone())
+            } else {
+                make(NodeColor::Red, del(&n.left, key), n.key.clone(), n.value.clone(), n.right.clone())
+            }
+        }
+        Ordering::Greater => {
+            if is_black_node(&n.right) {
+                bal_right(n.left.clone(), n.key.clone(), n.value.clone(), del(&n.right, key))
+            } else {
+                make(NodeColor::Red, n.left.clone(), n.key.clone(), n.value.clone(), del(&n.right, key))
+            }
+        }
+        Ordering::Equal => append(&n.left, &n.right),
+    }
+}
+
+// rebuild a node whose left subtree is one short on black height
+fn bal_left<T: Clone, V: Clone>(left: Link<T, V>, key: T, value: V, right: Link<T, V>) -> Link<T, V> {
+    use NodeColor::*;
+
+    if is_red(&left) {
+        return make(Red, recolor(left, Black), key, value, right);
+    }
+    let r = right.as_ref().expect("red-black invariant broken while deleting");
+    if r.color == Black {
+        return balance(left, key, value, recolor(right, Red));
+    }
+    let rl = r.left.as_ref().filter(|n| n.color == Black).expect("red-black invariant broken while deleting");
+    let new_left = make(Black, left, key, value, rl.left.clone());
+    let new_right = balance(rl.right.clone(), r.key.clone(), r.value.clone(), sub1(r.right.clone()));
+    make(Red, new_left, rl.key.clone(), rl.value.clone(), new_right)
+}
+
+// rebuild a node whose right subtree is one short on black height
+fn bal_right<T: Clone, V: Clone>(left: Link<T, V>, key: T, value: V, right: Link<T, V>) -> Link<T, V> {
+    use NodeColor::*;
+
+    if is_red(&right) {
+        return make(Red, left, key, value, recolor(right, Black));
+    }
+    let l = left.as_ref().expect("red-black invariant broken while deleting");
+    if l.color == Black {
+        return balance(recolor(left, Red), key, value, right);
+    }
+    let lr = l.right.as_ref().filter(|n| n.color == Black).expect("red-black invariant broken while deleting");
+    let new_left = balance(sub1(l.left.clone()), l.key.clone(), l.value.clone(), lr.left.clone());
+    let new_right = make(Black, lr.right.clone(), key, value, right);
+    make(Red, new_left, lr.key.clone(), lr.value.clone(), new_right)
+}
+
+// join the two subtrees of a deleted node, every key of `left` is less than every key of `right`
+fn append<T: Clone, V: Clone>(left: &Link<T, V>, right: &Link<T, V>) -> Link<T, V> {
+    use NodeColor::*;
+
+    let (l, r) = match (left, right) {
+        (None, _) => return right.clone(),
+        (_, None) => return left.clone(),
+        (Some(l), Some(r)) => (l, r),
+    };
+    match (l.color, r.color) {
+        (Red, Red) => {
+            let middle = append(&l.right, &r.left);
+            match middle.as_ref().filter(|m| m.color == Red) {
+                Some(m) => make(
+                    Red,
+                    make(Red, l.left.clone(), l.key.clone(), l.value.clone(), m.left.clone()),
+                    m.key.clone(),
+                    m.value.clone(),
+                    make(Red, m.right.clone(), r.key.clone(), r.value.clone(), r.right.clone()),
+                ),
+                None => make(Red, l.left.clone(), l.key.clone(), l.value.clone(), make(Red, middle, r.key.clone(), r.value.clone(), r.right.clone())),
+            }
+        }
+        (Black, Black) => {
+            let middle = append(&l.right, &r.left);
+            match middle.as_ref().filter(|m| m.color == Red) {
+                Some(m) => make(
+                    Red,
+                    make(Black, l.left.clone(), l.key.clone(), l.value.clone(), m.left.clone()),
+                    m.key.clone(),
+                    m.value.clone(),
+                    make(Black, m.right.clone(), r.key.clone(), r.value.clone(), r.right.clone()),
+                ),
+                None => bal_left(l.left.clone(), l.key.clone(), l.value.clone(), make(Black, middle, r.key.clone(), r.value.clone(), r.right.clone())),
+            }
+        }
+        (Black, Red) => make(Red, append(left, &r.left), r.key.clone(), r.value.clone(), r.right.clone()),
+        (Red, Black) => make(Red, l.left.clone(), l.key.clone(), l.value.clone(), append(&l.right, right)),
+    }
+}
+
+// check the red-black rules below a node, returning its black height
+fn validate_colors<T, V>(link: &Link<T, V>) -> Result<usize, ValidationError<&T>> {
+    let n = match link {
+        None => return Ok(0),
+        Some(n) => n,
+    };
+    for child in [&n.left, &n.right] {
+        if n.color == NodeColor::Red && is_red(child) {
+            return Err(ValidationError::RedRed { key: &child.as_ref().unwrap().key, parent: &n.key });
+        }
+    }
+    let left = validate_colors(&n.left)?;
+    let right = validate_colors(&n.right)?;
+    if left != right {
+        return Err(ValidationError::BlackHeight { key: &n.key, left, right });
+    }
+    Ok(left + if n.color == NodeColor::Black { 1 } else { 0 })
+}
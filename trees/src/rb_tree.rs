@@ -1,79 +1,106 @@
 use super::tree::*;
 use super::node::*;
+use super::arena::Arena;
+use super::validate::ValidationError;
 use crate::cli::CLIPrintable;
-use std::borrow::{Borrow, BorrowMut};
-use std::cell::RefCell;
-use std::rc::Rc;
+use crate::comparator::{Comparator, NaturalOrder};
 
-#[derive(Clone, Debug, PartialEq, Copy)]
-enum NodeColor {
+// the colour of a node in the red-black trees, new nodes start out red
+#[derive(Clone, Debug, Default, PartialEq, Copy)]
+pub enum NodeColor {
+    #[default]
     Red,
     Black,
 }
 
-type MaybeRedBlackTree<T> = Option<Rc<RefCell<RedBlackTreeNode<T>>>>;
+impl NodeColor {
+    // the letter shown next to a key when printing the structure
+    pub(crate) fn letter(self) -> char {
+        match self {
+            NodeColor::Red => 'R',
+            NodeColor::Black => 'B',
+        }
+    }
+
+    // how the colour is written out, also the fill of a node in a DOT graph
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            NodeColor::Red => "red",
+            NodeColor::Black => "black",
+        }
+    }
+}
+
+type MaybeRedBlackTree = Option<NodeId>;
 
 #[derive(Debug)]
-pub struct RedBlackTreeNode<T> {
+pub struct RedBlackTreeNode<T, V = ()> {
     color: NodeColor,
     pub key: Option<T>,
-    pub parent: MaybeRedBlackTree<T>,
+    value: Option<V>,
+    pub parent: MaybeRedBlackTree,
     is_child: Option<Side>,
-    left: MaybeRedBlackTree<T>,
-    right: MaybeRedBlackTree<T>,
+    left: MaybeRedBlackTree,
+    right: MaybeRedBlackTree,
+    size: usize,
 }
 
-pub struct RedBlackTree<T> {
-    root: MaybeRedBlackTree<T>,
+pub struct RedBlackTree<T, V = (), C = NaturalOrder> {
+    root: MaybeRedBlackTree,
+    nodes: Arena<RedBlackTreeNode<T, V>>,
+    compare: C,
 }
 
-impl<T: Ord> Traversible<T> for RedBlackTreeNode<T> {
+impl<T, V> Traversible<T> for RedBlackTreeNode<T, V> {
 
-    fn left(&self) -> &Option<Rc<RefCell<Self>>> {
-        return &self.left;
+    fn left(&self) -> Option<NodeId> {
+        self.left
     }
-    fn right(&self) -> &Option<Rc<RefCell<Self>>> {
-        return &self.right;
+    fn right(&self) -> Option<NodeId> {
+        self.right
     }
 }
 
-impl<T: Ord + Clone> Node<T> for RedBlackTreeNode<T> {
-    fn new(key: T) -> Self {
+impl<T, V> Node<T, V> for RedBlackTreeNode<T, V> {
+    fn new(key: T, value: V) -> Self {
         Self {
             color: NodeColor::Red,
             key: Some(key),
+            value: Some(value),
             parent: None,
             is_child: None, // left or right child of its parent
             left: None,
             right: None,
+            size: 1,
         }
     }
 
-    fn get_key(&self) -> T {
-        self.key.clone().unwrap()
+    fn get_key(&self) -> &T {
+        self.key.as_ref().unwrap()
     }
 
-    fn set_key(&mut self, val: T) {
-        self.key = Some(val);
+    fn get_key_mut(&mut self) -> &mut T {
+        self.key.as_mut().unwrap()
     }
-    
-    fn greater(&self, val: T) -> bool {
-        self.get_key() > val
+
+    fn get_value(&self) -> &V {
+        self.value.as_ref().unwrap()
     }
 
-    fn equal(&self, val: T) -> bool {
-        self.get_key() == val
+    fn get_value_mut(&mut self) -> &mut V {
+        self.value.as_mut().unwrap()
     }
 
-    fn less(&self, val: T) -> bool {
-        self.get_key() < val
+    fn into_entry(self) -> (T, V) {
+        (self.key.unwrap(), self.value.unwrap())
     }
 
+
     // return the child from the given side
-    fn get_child(&self, side: Side) -> MaybeRedBlackTree<T> {
+    fn get_child(&self, side: Side) -> MaybeRedBlackTree {
         match side {
-            Side::Left => self.left.clone(),
-            Side::Right => self.right.clone(),
+            Side::Left => self.left,
+            Side::Right => self.right,
             Side::Neither => None
         }
     }
@@ -83,7 +110,7 @@ impl<T: Ord + Clone> Node<T> for RedBlackTreeNode<T> {
         match &self.is_child {
             None => false,
             Some(val) => {
-                if val == &side { true } else { false }
+                val == &side
             }
         }
     }
@@ -93,7 +120,7 @@ impl<T: Ord + Clone> Node<T> for RedBlackTreeNode<T> {
         &self.is_child
     }
 
-    fn take_child(&mut self, side: Side) -> MaybeRedBlackTree<T> {
+    fn take_child(&mut self, side: Side) -> MaybeRedBlackTree {
         match side {
             Side::Left => self.left.take(),
             Side::Right => self.right.take(),
@@ -102,7 +129,7 @@ impl<T: Ord + Clone> Node<T> for RedBlackTreeNode<T> {
     }
 
     // attach a child node to its parent
-    fn set_child(&mut self, side: Side, child: MaybeRedBlackTree<T>) {
+    fn set_child(&mut self, side: Side, child: MaybeRedBlackTree) {
         match side {
             Side::Left => self.left = child,
             Side::Right => self.right = child,
@@ -112,70 +139,48 @@ impl<T: Ord + Clone> Node<T> for RedBlackTreeNode<T> {
 
     fn is_leaf(&self) -> bool {
         // check left and right pointers to determine if this node is a leaf node
-        if let None = self.left {
-            if let None = self.right {
-                return true;
-            }
-        }
-        return false;
+        self.left.is_none() && self.right.is_none()
     }
 
-    fn get_sibling(&self) -> MaybeRedBlackTree<T> {
-        if let Some(p) = self.get_parent() {
-            let parent = p.as_ref().borrow_mut();
-            if self.is_child(Side::Left) {
-                parent.get_child(Side::Right)
-            } else if self.is_child(Side::Right) {
-                parent.get_child(Side::Left)
-            } else { None }
-        }
-        else { None }
+    fn get_parent(&self) -> MaybeRedBlackTree {
+        self.parent
     }
 
-    fn get_uncle(&self) -> MaybeRedBlackTree<T> {
-        if let Some(p) = self.get_parent() {
-            p.as_ref().borrow().get_sibling()
-        } else { None }
-    } 
-
-    fn get_grandparent(&self) -> MaybeRedBlackTree<T> {
-        if let Some(p) = self.get_parent() {
-            p.as_ref().borrow().get_parent()
-        } else { None }
-    } 
+    fn get_parent_mut(&mut self) -> &mut MaybeRedBlackTree {
+        &mut self.parent
+    }
 
-    fn get_parent(&self) -> MaybeRedBlackTree<T> {
-        self.parent.clone()
+    // attach a parent node to its child
+    fn get_size(&self) -> usize {
+        self.size
     }
 
-    fn get_parent_mut(&mut self) -> &mut MaybeRedBlackTree<T> {
-        self.parent.borrow_mut()
+    fn set_size(&mut self, size: usize) {
+        self.size = size;
     }
 
-    // attach a parent node to its child
-    fn set_parent(&mut self, is_child: Option<Side>, parent: MaybeRedBlackTree<T>) {
+    fn set_parent(&mut self, is_child: Option<Side>, parent: MaybeRedBlackTree) {
         self.parent = parent;
         self.is_child = is_child;
-    }   
+    }
 
 }
 
-impl<T: Ord + std::fmt::Debug + std::fmt::Display + Clone> RedBlackTreeNode<T> {
+impl<T, V> RedBlackTreeNode<T, V> {
     fn nil() -> Self {
         Self {
             color: NodeColor::Black,
             key: None,
+            value: None,
             parent: None,
             is_child: None, // left or right child of its parent
             left: None,
             right: None,
+            // nil nodes don't hold a key, so they don't count towards subtree sizes
+            size: 0,
         }
     }
 
-    
-    fn is_red(&self) -> bool {
-        self.color == NodeColor::Red
-    }
 
     fn is_nil(&self) -> bool {
         self.key.is_none()
@@ -185,526 +190,425 @@ impl<T: Ord + std::fmt::Debug + std::fmt::Display + Clone> RedBlackTreeNode<T> {
         self.color = color
     }
 
-    fn get_color(&self) -> NodeColor {
+    pub(crate) fn get_color(&self) -> NodeColor {
         self.color
     }
+}
 
-    fn swap_color(&mut self, node: Rc<RefCell<RedBlackTreeNode<T>>>) {
-        let mut n = node.as_ref().borrow_mut();
-        let temp = n.get_color();
-        n.set_color(self.get_color());
-        self.set_color(temp); 
-    }
+impl<T, V, C: Comparator<T>> Tree<T, V> for RedBlackTree<T, V, C> {
+    type Node = RedBlackTreeNode<T, V>;
+    type Compare = C;
 
-    fn print_inorder_node(&self) {
-        // function called recursively to traverse nodes in order and print values
-        // if this is a leaf node, print its value
-        if self.is_leaf() {
-            println!("{:?}", self.get_key());
-            return;
-        }
-        // otherwise, first go left for lower values
-        if let Some(ptr) = &self.left {
-            ptr.as_ref().borrow().print_inorder_node();
-        }
-        // then print this node's value
-        println!("{:?}", self.get_key());
-        // then go right for higher values
-        if let Some(ptr) = &self.right {
-            ptr.as_ref().borrow().print_inorder_node();
-        }
+    fn with_comparator(compare: C) -> Self {
+        Self { root: None, nodes: Arena::new(), compare }
     }
 
-    fn print_structure_node(&self, depth: usize, from: Side) {
-        // first go left...
-        if let Some(ptr) = &self.left {
-            ptr.as_ref().borrow().print_structure_node(depth + 1, Side::Left);            
-        }
-        
-        // print this node with prefix
-        // get R/B as char 
-        let colour = if self.is_red() { 'R' } else { 'B' };
-        // couple characters that make tree look 'smooth'
-        let smooth = match from {
-            Side::Left => "┌───────┘",
-            Side::Neither => "",
-            Side::Right => "└───────┐"
-        };
-        // case for depth to make lines line up nice
-        let space = if depth == 0 { 0 } else { (depth - 1) * 8 };
-        // print line representing this node
-        println!("{: <1$}{2}{3} ({4})", "", space, smooth, self.get_key(), colour);
-        
-        // then go right...
-        if let Some(ptr) = &self.right {
-            ptr.as_ref().borrow().print_structure_node(depth + 1, Side::Right);
-        }
+    fn comparator(&self) -> &C {
+        &self.compare
     }
 
-
-    fn count_leaves_node(&self) -> usize {
-        if self.is_leaf() {
-            return 1;
-        }
-        let mut count: usize = 0;
-        // otherwise, first go left for lower values
-        if let Some(ptr) = &self.left {
-            count += ptr.as_ref().borrow().count_leaves_node();
-        }
-        // then go right for higher values
-        if let Some(ptr) = &self.right {
-            count += ptr.as_ref().borrow().count_leaves_node();
-        }
-        return count;
+    fn get_root(&self) -> MaybeRedBlackTree {
+        self.root
     }
 
-    fn get_height_node(&self, depth: usize) -> usize {
-        // recursive helper function for get_height of tree
-        if self.is_leaf() {
-            return depth;
-        }
-        // find max depth of either branch
-        let mut m: usize = usize::MIN;
-        // otherwise, first go left for lower values
-        if let Some(ptr) = &self.left {
-            m = std::cmp::max(m, ptr.as_ref().borrow().get_height_node(depth + 1));
+    fn set_root(&mut self, node: MaybeRedBlackTree) {
+        self.root = node;
+        if let Some(n) = node {
+            self.set_color(n, NodeColor::Black);
         }
-        // then go right for higher values
-        if let Some(ptr) = &self.right {
-            m = std::cmp::max(m, ptr.as_ref().borrow().get_height_node(depth + 1));
-        }
-        return m;
     }
-}
-
-impl<T: Ord + Copy + std::fmt::Debug + std::fmt::Display> Tree<T> for RedBlackTree<T> {
-    type Node = RedBlackTreeNode<T>;
 
-    fn new() -> Self {
-        Self { root: None }
+    fn nodes(&self) -> &Arena<RedBlackTreeNode<T, V>> {
+        &self.nodes
     }
 
-    fn get_root(&self) -> &MaybeRedBlackTree<T> {
-        &self.root
+    fn nodes_mut(&mut self) -> &mut Arena<RedBlackTreeNode<T, V>> {
+        &mut self.nodes
     }
 
-    fn set_root(&mut self, node: Option<Rc<RefCell<RedBlackTreeNode<T>>>>) {
-        self.root = node.clone();
-        match node {
-            None => (),
-            Some(ptr) => {
-                let mut n = ptr.as_ref().borrow_mut();
-                n.set_color(NodeColor::Black);
-            }
-        }
-    }
-
-    fn rotate(&mut self, side: Side, x: Rc<RefCell<RedBlackTreeNode<T>>>) {
+    fn rotate(&mut self, side: Side, x: NodeId) {
         // LEFT ROTATE
         // y = x.right
-        if self.get_child(x.clone(), !side).is_none() {
-            return
-        }
-        let y = self.get_child(x.clone(), !side).unwrap();
+        let y = match self.get_child(x, !side) {
+            None => return,
+            Some(y) => y,
+        };
 
         // x.right = y.left (A)
-        let y_left = self.get_child(y.clone(), side);
-        self.set_child(x.clone(), !side, y_left.clone());
+        let y_left = self.get_child(y, side);
+        self.set_child(x, !side, y_left);
 
         // if (y.left) != NULL
         //     (y.left).parent = x
-        if let Some(yl) = y_left.clone() {
-            // let key = x.as_ref().borrow().get_key();
-            // let child_side = self.insert_side(yl.clone(), key.clone());
-            self.set_parent(yl.clone(), Some(!side), Some(x.clone()));
+        if let Some(yl) = y_left {
+            self.set_parent(yl, Some(!side), Some(x));
         }
 
         // y.parent = x.parent
-        let x_parent = self.get_parent(x.clone());
-        let x_is_child = self.get_is_child(x.clone());
-
-        // if x.parent == NULL //x is root
-        //     T.root = y
-        if x_parent.is_none() {
-            self.set_parent(y.clone(), None, None);
-            self.set_root(Some(y.clone()));
-        } else {
-            self.set_parent(y.clone(), x_is_child.clone(), x_parent.clone());
-            let child_side = x_is_child.clone().unwrap();
-            self.set_child(x_parent.unwrap().clone(), child_side.clone(), Some(y.clone()));
+        let x_parent = self.get_parent(x);
+        let x_is_child = self.get_is_child(x);
+
+        // if x.parent == NULL //x is the root of the tree or of a detached subtree
+        //     the caller links y in x's place
+        match x_parent {
+            None => self.set_parent(y, None, None),
+            Some(xp) => {
+                self.set_parent(y, x_is_child, Some(xp));
+                let child_side = x_is_child.unwrap();
+                self.set_child(xp, child_side, Some(y));
+            }
         }
 
         // y.left = x
         // x.parent = y
-        let y_val = self.get_key(y.clone());
-        let child_side = !self.insert_side(x.clone(), y_val).unwrap();
-        self.set_child(y.clone(), side, Some(x.clone()));
-        self.set_parent(x.clone(), Some(child_side), Some(y.clone()));
+        self.set_child(y, side, Some(x));
+        self.set_parent(x, Some(side), Some(y));
+
+        // x is now below y, so its size has to be fixed first
+        self.update_size(x);
+        self.update_size(y);
 
     }
 
 
-    fn insert_fix(&mut self, node: Rc<RefCell<RedBlackTreeNode<T>>>) -> Rc<RefCell<RedBlackTreeNode<T>>> {
-        let p = self.get_parent(node.clone());
-        if p.is_none() || !self.is_red(p.clone()) {
-            return self.climb_to_root(node.clone())
-            // return
+    fn build_fix(&mut self, node: NodeId, depth: usize, levels: usize) {
+        // all black except the last level, which may be partly filled, so it is made red
+        // to keep the black height of every path the same
+        let color = if depth > 0 && depth + 1 == levels { NodeColor::Red } else { NodeColor::Black };
+        self.set_color(node, color);
+    }
+
+    fn join_nodes(&mut self, left: MaybeRedBlackTree, node: NodeId, right: MaybeRedBlackTree) -> NodeId {
+        self.join_colored(left, node, right)
+    }
+
+    fn insert_fix(&mut self, node: NodeId) -> NodeId {
+        let p = self.get_parent(node);
+        if p.is_none() || !self.is_red(p) {
+            return self.climb_to_root(node)
         }
 
-        let mut parent = p.unwrap();
-        let gp = self.get_grandparent(node.clone());
+        let parent = p.unwrap();
+        let gp = self.get_grandparent(node);
         if gp.is_none() {
-            self.set_color(parent.clone(), NodeColor::Black);
-            return self.climb_to_root(node.clone())
-            // return
+            self.set_color(parent, NodeColor::Black);
+            return self.climb_to_root(node)
         }
 
         let grandparent = gp.unwrap();
-        let uncle = self.get_uncle(node.clone());
-        if uncle.is_some() && self.is_red(uncle.clone()) {
-            self.recolor_ins(node.clone());
-            self.insert_fix(grandparent.clone());
+        let uncle = self.get_uncle(node);
+        if uncle.is_some() && self.is_red(uncle) {
+            self.recolor_ins(node);
+            self.insert_fix(grandparent);
         }
 
         else {
-            self.rotate_ins(node.clone());
+            self.rotate_ins(node);
         }
 
-        self.climb_to_root(node.clone())
+        self.climb_to_root(node)
     }
 
-    fn delete(&mut self, k: T) {
-        let search = self.bst_find(self.get_root().clone(), k);
-        if search.is_none() {
-            return;
-        }
-
-        let node = search.unwrap();
+    fn remove_node(&mut self, node: NodeId) -> (T, V) {
         let deleted_color;
         let moved_up_node;
+        let removed;
 
-        if self.left(node.clone()).is_none() || self.right(node.clone()).is_none() {
-            moved_up_node = self.replace(node.clone());
-            deleted_color = node.as_ref().borrow().get_color();
+        if self.left(node).is_none() || self.right(node).is_none() {
+            deleted_color = self.get_color(node);
+            moved_up_node = self.replace(node);
+            removed = node;
         } else {
-            let left_child: Rc<RefCell<RedBlackTreeNode<T>>> = self.left(node.clone()).unwrap();
-            let successor = self.find_max(left_child.clone());
-            let new_key = self.get_key(successor.clone());
-            self.set_key(node.clone(), new_key);
-            deleted_color = successor.as_ref().borrow().get_color();
-            moved_up_node = self.replace(successor.clone());
+            let left_child = self.left(node).unwrap();
+            let predecessor = self.find_max(left_child);
+            // the in-order predecessor's entry moves up, the predecessor now holds the deleted key and value
+            self.swap_entry(node, predecessor);
+            deleted_color = self.get_color(predecessor);
+            moved_up_node = self.replace(predecessor);
+            removed = predecessor;
         }
 
+        // every ancestor of the removed node lost a key, fix the sizes before any rotations
+        self.refresh_sizes(self.get_parent(removed));
+
         if deleted_color == NodeColor::Black {
-            if self.is_red(moved_up_node.clone()) {
-                self.set_color(moved_up_node.clone().unwrap(), NodeColor::Black);
+            if self.is_red(moved_up_node) {
+                self.set_color(moved_up_node.unwrap(), NodeColor::Black);
             } else {
-                self.delete_fix(moved_up_node.clone().unwrap());
+                self.delete_fix(moved_up_node.unwrap());
+            }
+            // the fixes may have rotated the root down, whatever ended up above it is the new one
+            if let Some(root) = self.root {
+                let root = self.climb_to_root(root);
+                self.set_root(Some(root));
             }
             // remove nil node after done fixing
-            if moved_up_node.is_some() && self.is_nil(moved_up_node.clone().unwrap()) {
-                let nil_parent = self.get_parent(moved_up_node.unwrap().clone());
-                self.replace_parent_child(nil_parent, node.clone(), None);  
+            if let Some(moved_up) = moved_up_node.filter(|n| self.is_nil(*n)) {
+                let nil_parent = self.get_parent(moved_up);
+                self.replace_parent_child(nil_parent, moved_up, None);
+                self.nodes.remove(moved_up);
             }
-        }   
-    }
-
-    fn print_inorder(&self) {
-        // PART 1.5 print in-order traversal of tree
-        println!("-------- Tree In-Order -------");
-        if let Some(ptr) = &self.root {
-            let root = ptr.as_ref().borrow();
-            root.print_inorder_node();
         }
-        else {
-            println!("Empty tree");
-        }
-        println!("------------------------------");
-    }
 
-    fn print_structure(&self) {
-        // PART 1.7 print tree showing structure and colours
-        println!("------- Tree Structure -------");
-        if let Some(ptr) = &self.root {
-            let root = ptr.as_ref().borrow();
-            root.print_structure_node(0, Side::Neither);
-        }
-        else {
-            println!("Empty tree");
-        }       
-        println!("------------------------------");
+        self.nodes.remove(removed).into_entry()
     }
 
-    fn count_leaves(&self) -> usize {
-        // PART 1.3 count leaves in tree
-        if let Some(ptr) = &self.root {
-            let root = ptr.as_ref().borrow();
-            return root.count_leaves_node();
-        }
-        else {
-            return 0;
-        }
+    fn structure_label(&self, node: NodeId) -> String where T: std::fmt::Display {
+        format!("{} ({})", self.get_key(node), self.get_color(node).letter())
     }
 
-    fn is_empty(&self) -> bool {
-        // PART 1.6 check if tree empty
-        if let None = &self.root {
-            return true;
-        } else {
-            return false;
-        }
-    } 
+    fn dot_attributes(&self, node: NodeId) -> String where T: std::fmt::Display {
+        let fill = self.get_color(node).name();
+        format!("label=\"{}\", style=filled, fillcolor={fill}, fontcolor=white", dot_escape(self.get_key(node)))
+    }
 
-    fn get_height(&self) -> usize {
-        // PART 1.4 get height of tree
-        if let Some(ptr) = &self.root {
-            let root: std::cell::Ref<'_, RedBlackTreeNode<T>> = ptr.as_ref().borrow();
-            return root.get_height_node(1);
-        }
-        else {
-            return 0;
+    fn validate<'a>(&'a self) -> Result<(), ValidationError<&'a T>> where Self::Node: 'a {
+        if let Some(root) = self.root {
+            // colours first, this also catches nil nodes before anything asks them for a key
+            self.validate_colors(root)?;
+            if self.is_red(Some(root)) {
+                return Err(ValidationError::RedRoot { key: self.get_key(root) });
+            }
         }
+        self.validate_bst()
     }
 }
 
-impl<T> CLIPrintable for RedBlackTree<T> {
+impl<T, V, C> CLIPrintable for RedBlackTree<T, V, C> {
     fn pretty_name() -> &'static str {
         "Red-Black Tree"
     }
 }
 
-impl<T> RedBlackTree<T> 
-where 
-    T: Ord + Copy + std::fmt::Debug + std::fmt::Display
-{
-    
-    fn get_sibling(&self, node: Rc<RefCell<RedBlackTreeNode<T>>>) -> MaybeRedBlackTree<T> {
-        node.as_ref().borrow().get_sibling()
+tree_impls!(RedBlackTree, RedBlackTreeNode);
+
+impl<T, V, C: Default> Default for RedBlackTree<T, V, C> {
+    fn default() -> Self {
+        Self { root: None, nodes: Arena::new(), compare: C::default() }
     }
+}
 
-    fn get_uncle(&self, node: Rc<RefCell<RedBlackTreeNode<T>>>) -> MaybeRedBlackTree<T> {
-        node.as_ref().borrow().get_uncle()
+impl<T, V, C: Comparator<T>> RedBlack<T, V> for RedBlackTree<T, V, C> {
+    fn get_color(&self, node: NodeId) -> NodeColor {
+        self.nodes[node].get_color()
     }
 
-    fn get_grandparent(&self, node: Rc<RefCell<RedBlackTreeNode<T>>>) -> MaybeRedBlackTree<T> {
-        node.as_ref().borrow().get_grandparent()
+    fn set_color(&mut self, node: NodeId, color: NodeColor) {
+        self.nodes[node].set_color(color);
     }
+}
+
+impl<T, V, C> RedBlackTree<T, V, C>
+where
+    C: Comparator<T>
+{
 
-    fn get_color(&self, node: Rc<RefCell<RedBlackTreeNode<T>>>) -> NodeColor {
-        node.as_ref().borrow().get_color()
+    fn is_nil(&self, node: NodeId) -> bool {
+        self.nodes[node].is_nil()
     }
 
-    fn set_color(&self, node: Rc<RefCell<RedBlackTreeNode<T>>>, color: NodeColor) {
-        node.as_ref().borrow_mut().set_color(color);
-    } 
 
-    fn equal(&self, a: MaybeRedBlackTree<T>, b: MaybeRedBlackTree<T>) -> bool {
-        if a.is_none() && b.is_none() {
-            true
-        } else if a.is_some() && b.is_some() {
-            let a_ptr = a.unwrap();
-            let b_ptr = b.unwrap();
-            if self.is_nil(a_ptr.clone()) && self.is_nil(b_ptr.clone()) {
-                true
-            } else if !self.is_nil(a_ptr.clone()) && !self.is_nil(b_ptr.clone()) {
-                a_ptr.as_ref().borrow().equal(b_ptr.as_ref().borrow().get_key().clone())
-            } else {
-                false
+    // check the red-black rules below a node, returning its black height
+    fn validate_colors(&self, node: NodeId) -> Result<usize, ValidationError<&T>> {
+        if self.is_nil(node) {
+            let parent = self.get_parent(node).map(|p| self.get_key(p));
+            return Err(ValidationError::LeftoverNil { parent });
+        }
+        let key = self.get_key(node);
+
+        let mut heights = [0, 0];
+        for (i, side) in [Side::Left, Side::Right].into_iter().enumerate() {
+            if let Some(child) = self.get_child(node, side) {
+                if self.is_red(Some(node)) && self.is_red(Some(child)) {
+                    return Err(ValidationError::RedRed { key: self.get_key(child), parent: key });
+                }
+                heights[i] = self.validate_colors(child)?;
             }
-        } else {
-            false
         }
-    }
 
-    fn is_red(&self, node: MaybeRedBlackTree<T>) -> bool {
-        match node {
-            None => false,
-            Some(n) => n.as_ref().borrow().is_red(),
+        if heights[0] != heights[1] {
+            return Err(ValidationError::BlackHeight { key, left: heights[0], right: heights[1] });
         }
+        let own = if self.is_red(Some(node)) { 0 } else { 1 };
+        Ok(heights[0] + own)
     }
 
-    fn is_nil(&self, node: Rc<RefCell<RedBlackTreeNode<T>>>) -> bool {
-        node.as_ref().borrow().is_nil()
-    }
+    fn replace(&mut self, node: NodeId) -> MaybeRedBlackTree {
+        let parent = self.get_parent(node);
 
-    fn replace(&mut self, node: Rc<RefCell<RedBlackTreeNode<T>>>) -> Option<Rc<RefCell<RedBlackTreeNode<T>>>> {
-        let parent = self.get_parent(node.clone());
+        if let Some(left) = self.left(node)  {
+            self.replace_parent_child(parent, node, Some(left));
+            Some(left)
 
-        if let Some(left) = self.left(node.clone())  {
-            self.replace_parent_child(parent.clone(), node.clone(), Some(left.clone()));
-            Some(left.clone())
-            
-        } else if let Some(right) = self.right(node.clone())  {
-            self.replace_parent_child(parent.clone(), node.clone(), Some(right.clone()));
-            Some(right.clone())
+        } else if let Some(right) = self.right(node)  {
+            self.replace_parent_child(parent, node, Some(right));
+            Some(right)
 
         } else {
-            let new_child = match self.is_red(Some(node.clone())) {
+            let new_child = match self.is_red(Some(node)) {
                 false => {
-                    let nil_node: RedBlackTreeNode<T> = RedBlackTreeNode::nil();
-                    Some(Rc::new(RefCell::new(nil_node)))
+                    let nil_node: RedBlackTreeNode<T, V> = RedBlackTreeNode::nil();
+                    Some(self.nodes.insert(nil_node))
                 },
                 true => {
                     None
                 }
             };
-            self.replace_parent_child(parent.clone(), node.clone(), new_child.clone());
-            new_child.clone()
+            self.replace_parent_child(parent, node, new_child);
+            new_child
         }
     }
 
-    fn delete_fix(&mut self, node: Rc<RefCell<RedBlackTreeNode<T>>>) {
-        
-        if self.get_parent(node.clone()).is_none() {
-            node.as_ref().borrow_mut().set_color(NodeColor::Black);
+    fn delete_fix(&mut self, node: NodeId) {
+
+        if self.get_parent(node).is_none() {
+            self.set_color(node, NodeColor::Black);
             return;
-        } 
+        }
 
-        let mut sibling_ptr = self.get_sibling(node.clone());
+        let mut sibling_ptr = self.get_sibling(node);
 
         // Case 1
-        if self.is_red(sibling_ptr.clone()) {
-            self.red_sibling_del(node.clone(), sibling_ptr.clone().unwrap());
-            sibling_ptr = sibling_ptr.unwrap().as_ref().borrow().get_sibling();
+        if self.is_red(sibling_ptr) {
+            self.red_sibling_del(node, sibling_ptr.unwrap());
+            sibling_ptr = self.get_sibling(node);
         }
 
 
         // Case 2+3
         let sibling = sibling_ptr.unwrap();
-        let sibling_left = self.left(sibling.clone());
-        let sibling_right = self.right(sibling.clone());
-        
-        if !self.is_red(sibling_left.clone()) && !self.is_red(sibling_right.clone()) {
-            sibling.as_ref().borrow_mut().set_color(NodeColor::Red);
+        let sibling_left = self.left(sibling);
+        let sibling_right = self.right(sibling);
+
+        if !self.is_red(sibling_left) && !self.is_red(sibling_right) {
+            self.set_color(sibling, NodeColor::Red);
 
-            let node_parent = self.get_parent(node.clone());
-            let p = node_parent.clone().unwrap();
+            let node_parent = self.get_parent(node);
+            let p = node_parent.unwrap();
 
-            if self.is_red(node_parent.clone()) {
-                p.as_ref().borrow_mut().set_color(NodeColor::Black);
+            if self.is_red(node_parent) {
+                self.set_color(p, NodeColor::Black);
             } else {
                 // Case 4
-                self.delete_fix(node_parent.clone().unwrap());
+                self.delete_fix(p);
             }
         } else {
-            self.black_sibling_red_nephew_del(node.clone(), sibling.clone());
+            self.black_sibling_red_nephew_del(node, sibling);
         }
 
     }
 
-    fn red_sibling_del(&mut self, node: Rc<RefCell<RedBlackTreeNode<T>>>, sibling: Rc<RefCell<RedBlackTreeNode<T>>>) {
-        let p = self.get_parent(node.clone());
-        
-        let sibling_ptr = sibling.clone();
-        let parent_ptr = p.unwrap();
+    fn red_sibling_del(&mut self, node: NodeId, sibling: NodeId) {
+        let parent = self.get_parent(node).unwrap();
 
-        sibling_ptr.as_ref().borrow_mut().set_color(NodeColor::Black);
-        parent_ptr.as_ref().borrow_mut().set_color(NodeColor::Red);
+        self.set_color(sibling, NodeColor::Black);
+        self.set_color(parent, NodeColor::Red);
 
-        let left = self.left(parent_ptr.clone());
-
-        if self.equal(Some(sibling.clone()), left.clone()) {
-            self.rotate(Side::Right, parent_ptr.clone());
+        if Some(sibling) == self.left(parent) {
+            self.rotate(Side::Right, parent);
         } else {
-            self.rotate(Side::Left, parent_ptr.clone());
+            self.rotate(Side::Left, parent);
         }
     }
 
-    fn black_sibling_red_nephew_del(&mut self, node: Rc<RefCell<RedBlackTreeNode<T>>>, sibling: Rc<RefCell<RedBlackTreeNode<T>>>) {      
-        let mut sibling_ptr = sibling.clone();
-        let parent_ptr = self.get_parent(node.clone()).unwrap();
-        let is_left_child = self.equal(Some(node.clone()), self.left(parent_ptr.clone()));
+    fn black_sibling_red_nephew_del(&mut self, node: NodeId, sibling: NodeId) {
+        let mut sibling_ptr = sibling;
+        let parent_ptr = self.get_parent(node).unwrap();
+        let is_left_child = Some(node) == self.left(parent_ptr);
 
         // right left
-        if is_left_child && !self.is_red(self.right(sibling_ptr.clone())) {
+        if is_left_child && !self.is_red(self.right(sibling_ptr)) {
 
-            let sibling_left = self.left(sibling_ptr.clone()).unwrap();
-            self.set_color(sibling_left.clone(), NodeColor::Black);
-            self.set_color(sibling_ptr.clone(), NodeColor::Red);
-            self.rotate(Side::Right, sibling_ptr.clone());
-            sibling_ptr = self.right(parent_ptr.clone()).unwrap();
+            let sibling_left = self.left(sibling_ptr).unwrap();
+            self.set_color(sibling_left, NodeColor::Black);
+            self.set_color(sibling_ptr, NodeColor::Red);
+            self.rotate(Side::Right, sibling_ptr);
+            sibling_ptr = self.right(parent_ptr).unwrap();
 
-        } else if !is_left_child && !self.is_red(self.left(sibling_ptr.clone())) {
+        } else if !is_left_child && !self.is_red(self.left(sibling_ptr)) {
         // left right
 
-            let sibling_right = self.right(sibling_ptr.clone()).unwrap();
-            self.set_color(sibling_right.clone(), NodeColor::Black);
-            self.set_color(sibling_ptr.clone(), NodeColor::Red);
-            self.rotate(Side::Left, sibling_ptr.clone());
-            sibling_ptr = self.left(parent_ptr.clone()).unwrap();
+            let sibling_right = self.right(sibling_ptr).unwrap();
+            self.set_color(sibling_right, NodeColor::Black);
+            self.set_color(sibling_ptr, NodeColor::Red);
+            self.rotate(Side::Left, sibling_ptr);
+            sibling_ptr = self.left(parent_ptr).unwrap();
         }
 
-        self.set_color(sibling_ptr.clone(), self.get_color(parent_ptr.clone()));
-        self.set_color(parent_ptr.clone(), NodeColor::Black);
+        self.set_color(sibling_ptr, self.get_color(parent_ptr));
+        self.set_color(parent_ptr, NodeColor::Black);
 
         if is_left_child {
-            let sibling_right = self.right(sibling_ptr.clone()); 
+            let sibling_right = self.right(sibling_ptr);
             if sibling_right.is_none() {
                 return
             }
-            self.set_color(sibling_right.unwrap().clone(), NodeColor::Black);
-            self.rotate(Side::Left, parent_ptr.clone());
+            self.set_color(sibling_right.unwrap(), NodeColor::Black);
+            self.rotate(Side::Left, parent_ptr);
 
         } else {
-            let sibling_left = self.left(sibling_ptr.clone()); 
+            let sibling_left = self.left(sibling_ptr);
             if sibling_left.is_none() {
                 return
             }
-            self.set_color(sibling_left.unwrap().clone(), NodeColor::Black);
-            self.rotate(Side::Right, parent_ptr.clone());
+            self.set_color(sibling_left.unwrap(), NodeColor::Black);
+            self.rotate(Side::Right, parent_ptr);
         }
     }
 
-    fn replace_parent_child(&mut self, parent: MaybeRedBlackTree<T>, node: Rc<RefCell<RedBlackTreeNode<T>>>, child: MaybeRedBlackTree<T>) {
-        match parent.clone() {
+    fn replace_parent_child(&mut self, parent: MaybeRedBlackTree, node: NodeId, child: MaybeRedBlackTree) {
+        match parent {
             None => {
-                self.set_root(child.clone());
+                if let Some(c) = child {
+                    self.set_parent(c, None, None);
+                }
+                self.set_root(child);
             },
             Some(p) => {
-                let node_side = self.get_is_child(node.clone()).unwrap();
-                self.set_child(p.clone(), node_side, child.clone());
-                if child.is_some() {
-                    self.set_parent(child.unwrap().clone(), Some(node_side), parent.clone());
+                let node_side = self.get_is_child(node).unwrap();
+                self.set_child(p, node_side, child);
+                if let Some(c) = child {
+                    self.set_parent(c, Some(node_side), parent);
                 }
             }
         }
     }
 
-    fn recolor_ins(&self, node: Rc<RefCell<RedBlackTreeNode<T>>>) {
-        let n = node.as_ref().borrow();
-        if n.get_grandparent().is_some() && n.get_uncle().is_some() {
-            n.get_grandparent().unwrap().as_ref().borrow_mut().set_color(NodeColor::Red);
-            n.get_parent().unwrap().as_ref().borrow_mut().set_color(NodeColor::Black);
-            n.get_uncle().unwrap().as_ref().borrow_mut().set_color(NodeColor::Black);
+    fn recolor_ins(&mut self, node: NodeId) {
+        if let (Some(grandparent), Some(uncle)) = (self.get_grandparent(node), self.get_uncle(node)) {
+            let parent = self.get_parent(node).unwrap();
+            self.set_color(grandparent, NodeColor::Red);
+            self.set_color(parent, NodeColor::Black);
+            self.set_color(uncle, NodeColor::Black);
         }
     }
 
-    fn rotate_ins(&mut self, node: Rc<RefCell<RedBlackTreeNode<T>>>) {
-        let mut parent = self.get_parent(node.clone()).unwrap();
-        let grandparent = self.get_grandparent(node.clone()).unwrap();
+    fn rotate_ins(&mut self, node: NodeId) {
+        let mut parent = self.get_parent(node).unwrap();
+        let grandparent = self.get_grandparent(node).unwrap();
 
-        if self.get_is_child(parent.clone()).unwrap() == Side::Left {
-            if self.get_is_child(node.clone()).unwrap() == Side::Right {
-                self.rotate(Side::Left, parent.clone());
-                parent = node.clone();
-            } 
+        if self.get_is_child(parent).unwrap() == Side::Left {
+            if self.get_is_child(node).unwrap() == Side::Right {
+                self.rotate(Side::Left, parent);
+                parent = node;
+            }
 
-            self.rotate(Side::Right, grandparent.clone());
+            self.rotate(Side::Right, grandparent);
 
-            self.set_color(parent.clone(), NodeColor::Black);
-            self.set_color(grandparent.clone(), NodeColor::Red);
+            self.set_color(parent, NodeColor::Black);
+            self.set_color(grandparent, NodeColor::Red);
 
         } else {
-            if self.get_is_child(node.clone()).unwrap() == Side::Left {
-                self.rotate(Side::Right, parent.clone());
-                parent = node.clone();
-            } 
+            if self.get_is_child(node).unwrap() == Side::Left {
+                self.rotate(Side::Right, parent);
+                parent = node;
+            }
 
-            self.rotate(Side::Left, grandparent.clone());
+            self.rotate(Side::Left, grandparent);
 
-            self.set_color(parent.clone(), NodeColor::Black);
-            self.set_color(grandparent.clone(), NodeColor::Red);
+            self.set_color(parent, NodeColor::Black);
+            self.set_color(grandparent, NodeColor::Red);
         }
     }
 
-}
\ No newline at end of file
+}
@@ -0,0 +1,230 @@
+use std::cmp::{max, Ordering};
+use crate::tree::*;
+use crate::node::*;
+use crate::arena::Arena;
+use crate::cli::CLIPrintable;
+use crate::comparator::{Comparator, NaturalOrder};
+use crate::validate::ValidationError;
+
+type MaybeScapegoatTree = Option<NodeId>;
+
+// a scapegoat tree node keeps nothing besides its entry and subtree size
+pub type ScapegoatTreeNode<T, V = ()> = LinkedNode<T, V>;
+
+// balance factor of the trees built by `ScapegoatTree::new`
+pub const DEFAULT_ALPHA: f64 = 0.7;
+
+// whether `alpha` can be the balance factor of a scapegoat tree, at exactly 1/2 rebuilds no
+// longer pay for themselves
+pub fn is_valid_alpha(alpha: f64) -> bool {
+    alpha > 0.5 && alpha < 1.0
+}
+
+// Scapegoat tree (Galperin and Rivest). Nodes carry no balancing data at all: an insert that ends
+// up deeper than log base 1/alpha of the tree's size walks back up to the first ancestor holding
+// more than alpha of its parent's keys and rebuilds the parent's subtree perfectly balanced, and
+// once deletes shrink the tree below alpha of its size at the last full rebuild, the whole tree
+// is rebuilt. An alpha near 1/2 keeps the tree shallow by rebuilding often, one near 1 rarely
+// rebuilds. Joins and splits have nothing to balance by either, so they rebuild what they touch.
+pub struct ScapegoatTree<T, V = (), C = NaturalOrder> {
+    root: MaybeScapegoatTree,
+    nodes: Arena<ScapegoatTreeNode<T, V>>,
+    compare: C,
+    alpha: f64,
+    // the most keys the tree held since it was last rebuilt as a whole
+    max_len: usize,
+}
+
+impl<T, V, C: Comparator<T>> Tree<T, V> for ScapegoatTree<T, V, C> {
+    type Node = ScapegoatTreeNode<T, V>;
+    type Compare = C;
+
+    fn with_comparator(compare: C) -> Self {
+        Self::with_alpha_and_comparator(DEFAULT_ALPHA, compare)
+    }
+
+    fn comparator(&self) -> &C {
+        &self.compare
+    }
+
+    fn get_root(&self) -> MaybeScapegoatTree {
+        self.root
+    }
+
+    fn set_root(&mut self, node: MaybeScapegoatTree) {
+        self.root = node;
+        self.max_len = max(self.max_len, self.len());
+    }
+
+    fn nodes(&self) -> &Arena<ScapegoatTreeNode<T, V>> {
+        &self.nodes
+    }
+
+    fn nodes_mut(&mut self) -> &mut Arena<ScapegoatTreeNode<T, V>> {
+        &mut self.nodes
+    }
+
+    fn empty_like(&mut self) -> Self where C: Clone {
+        Self::with_alpha_and_comparator(self.alpha, self.compare.clone())
+    }
+
+    fn insert_fix(&mut self, node: NodeId) -> NodeId {
+        let len = self.size_of(Some(self.climb_to_root(node)));
+        if self.depth(node) > self.depth_limit(max(self.max_len, len)) {
+            // the scapegoat is the parent of the first ancestor too heavy for it
+            let mut child = node;
+            while let Some(parent) = self.get_parent(child) {
+                if self.size_of(Some(child)) as f64 > self.alpha * self.size_of(Some(parent)) as f64 {
+                    self.rebuild(parent);
+                    break;
+                }
+                child = parent;
+            }
+        }
+        self.climb_to_root(node)
+    }
+
+    fn build_fix(&mut self, _node: NodeId, _depth: usize, _levels: usize) {
+        // a sorted build is what every rebuild makes, there is nothing else to do
+    }
+
+    fn join_nodes(&mut self, left: MaybeScapegoatTree, node: NodeId, right: MaybeScapegoatTree) -> NodeId {
+        self.set_children(node, left, right);
+        self.update_size(node);
+        self.rebuild(node)
+    }
+
+    fn join_two(&mut self, left: MaybeScapegoatTree, right: MaybeScapegoatTree) -> MaybeScapegoatTree {
+        let mut ids = self.in_order(left);
+        ids.extend(self.in_order(right));
+        self.link(&ids)
+    }
+
+    fn split_nodes_by<F: Fn(&Self, NodeId) -> Ordering>(&mut self, root: MaybeScapegoatTree, cmp: &F) -> (MaybeScapegoatTree, MaybeScapegoatTree, MaybeScapegoatTree) {
+        // the keys are in order, so the ones below the split key come first
+        let ids = self.in_order(root);
+        let less = ids.partition_point(|&n| cmp(self, n) == Ordering::Less);
+        let found = ids.get(less).copied().filter(|&n| cmp(self, n) == Ordering::Equal);
+        let greater = less + usize::from(found.is_some());
+        if let Some(f) = found {
+            self.detach(f);
+        }
+        (self.link(&ids[..less]), found, self.link(&ids[greater..]))
+    }
+
+    fn remove_node(&mut self, node: NodeId) -> (T, V) {
+        let (removed, _) = self.bst_unlink(node);
+        // deletes never make the tree deeper, but once enough of them leave it too deep for its
+        // size the whole tree is rebuilt
+        if (self.len() as f64) < self.alpha * self.max_len as f64 {
+            if let Some(root) = self.root {
+                self.root = Some(self.rebuild(root));
+            }
+            self.max_len = self.len();
+        }
+        self.nodes.remove(removed).into_entry()
+    }
+
+    fn validate<'a>(&'a self) -> Result<(), ValidationError<&'a T>> where Self::Node: 'a {
+        self.validate_bst()?;
+        let limit = self.depth_limit(self.max_len);
+        let mut stack: Vec<(NodeId, usize)> = self.root.map(|r| (r, 0)).into_iter().collect();
+        while let Some((node, depth)) = stack.pop() {
+            if depth > limit {
+                return Err(ValidationError::TooDeep { key: self.get_key(node), depth, max: limit });
+            }
+            for child in [self.left(node), self.right(node)].into_iter().flatten() {
+                stack.push((child, depth + 1));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T, V, C: Comparator<T>> ScapegoatTree<T, V, C> {
+    // an empty tree with balance factor `alpha`, above 1/2 and less than 1
+    pub fn with_alpha_and_comparator(alpha: f64, compare: C) -> Self {
+        assert!(is_valid_alpha(alpha), "the alpha of a scapegoat tree has to be above 0.5 and less than 1");
+        Self { root: None, nodes: Arena::new(), compare, alpha, max_len: 0 }
+    }
+
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    // the deepest a node may be in a tree of `len` keys, log base 1/alpha of `len`
+    fn depth_limit(&self, len: usize) -> usize {
+        ((len as f64).ln() / (1.0 / self.alpha).ln()).floor() as usize
+    }
+
+    fn depth(&self, node: NodeId) -> usize {
+        let mut depth = 0;
+        let mut current = node;
+        while let Some(parent) = self.get_parent(current) {
+            depth += 1;
+            current = parent;
+        }
+        depth
+    }
+
+    // the nodes of a subtree in key order
+    fn in_order(&self, root: MaybeScapegoatTree) -> Vec<NodeId> {
+        let mut ids = Vec::with_capacity(self.size_of(root));
+        let mut stack = Vec::new();
+        let mut current = root;
+        while current.is_some() || !stack.is_empty() {
+            while let Some(n) = current {
+                stack.push(n);
+                current = self.left(n);
+            }
+            let n = stack.pop().unwrap();
+            ids.push(n);
+            current = self.right(n);
+        }
+        ids
+    }
+
+    // link nodes in key order up perfectly balanced as a detached subtree
+    fn link(&mut self, ids: &[NodeId]) -> MaybeScapegoatTree {
+        let levels = (usize::BITS - ids.len().leading_zeros()) as usize;
+        let root = self.link_sorted(ids, 0, levels);
+        if let Some(r) = root {
+            self.set_parent(r, None, None);
+        }
+        root
+    }
+
+    // relink a subtree perfectly balanced in the same place, returning its new root
+    fn rebuild(&mut self, node: NodeId) -> NodeId {
+        let parent = self.get_parent(node);
+        let side = self.get_is_child(node);
+        let ids = self.in_order(Some(node));
+        let root = self.link(&ids).unwrap();
+        if let (Some(p), Some(s)) = (parent, side) {
+            self.set_child(p, s, Some(root));
+            self.set_parent(root, Some(s), Some(p));
+        }
+        root
+    }
+}
+
+impl<T, V, C> CLIPrintable for ScapegoatTree<T, V, C> {
+    fn pretty_name() -> &'static str {
+        "Scapegoat Tree"
+    }
+}
+
+tree_impls!(ScapegoatTree, ScapegoatTreeNode);
+
+impl<T> ScapegoatTree<T> {
+    // an empty tree of bare keys with balance factor `alpha`, above 1/2 and less than 1
+    pub fn with_alpha(alpha: f64) -> Self where T: Ord {
+        Self::with_alpha_and_comparator(alpha, NaturalOrder)
+    }
+}
+
+impl<T, V, C: Default> Default for ScapegoatTree<T, V, C> {
+    fn default() -> Self {
+        Self { root: None, nodes: Arena::new(), compare: C::default(), alpha: DEFAULT_ALPHA, max_len: 0 }
+    }
+}
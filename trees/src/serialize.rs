@@ -0,0 +1,251 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::marker::PhantomData;
+use serde::de::{Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use super::tree::*;
+use super::node::*;
+use super::comparator::Comparator;
+use super::rb_tree::{NodeColor, RedBlackTree, RedBlackTreeNode};
+use super::avl_tree::{AvlTree, AvlTreeNode};
+use super::splay_tree::SplayTree;
+use super::treap::Treap;
+use super::llrb_tree::LeftLeaningRedBlackTree;
+use super::aa_tree::AaTree;
+use super::scapegoat_tree::ScapegoatTree;
+use super::weight_balanced_tree::WeightBalancedTree;
+use super::tree_map::TreeMap;
+
+// Trees of bare keys are written out as their keys in order and maps as maps, so the
+// output doesn't depend on how the tree happens to be balanced. Reading sorted input back
+// builds the tree bottom up in linear time. A comparator can't be read from the input,
+// so deserializing needs one that is `Default`.
+
+// a tree of bare keys written out as a sequence and read back from one
+macro_rules! serialize_as_keys {
+    ($($tree:ident),* $(,)?) => {$(
+        impl<T: Serialize, C: Comparator<T>> Serialize for $tree<T, (), C> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_seq(self.iter())
+            }
+        }
+
+        impl<'de, T: Deserialize<'de>, C: Comparator<T> + Default> Deserialize<'de> for $tree<T, (), C> {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                deserializer.deserialize_seq(KeysVisitor(PhantomData))
+            }
+        }
+    )*};
+}
+
+serialize_as_keys!(
+    RedBlackTree,
+    AvlTree,
+    SplayTree,
+    Treap,
+    LeftLeaningRedBlackTree,
+    AaTree,
+    ScapegoatTree,
+    WeightBalancedTree,
+);
+
+impl<K: Serialize, V: Serialize, B: Tree<K, V>> Serialize for TreeMap<K, V, B> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.tree().entries())
+    }
+}
+
+impl<'de, K, V, B> Deserialize<'de> for TreeMap<K, V, B>
+where
+    K: Deserialize<'de>,
+    V: Deserialize<'de>,
+    B: Tree<K, V>,
+    B::Compare: Default,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let tree = deserializer.deserialize_map(EntriesVisitor(PhantomData))?;
+        Ok(TreeMap::from_tree(tree))
+    }
+}
+
+// don't trust a size hint from the input with more than this many entries up front
+const MAX_PREALLOCATE: usize = 4096;
+
+// a tree built from entries in the order they were read, in linear time if they are sorted.
+// like `BTreeMap`, a key that comes up again replaces the value read before it
+fn build<T, V, B>(entries: Vec<(T, V)>) -> B
+where
+    B: Tree<T, V>,
+    B::Compare: Default,
+{
+    let mut tree = B::new();
+    if entries.windows(2).all(|w| tree.compare(&w[0].0, &w[1].0) == Ordering::Less) {
+        tree.build_sorted(entries);
+    } else {
+        for (k, v) in entries {
+            tree.insert_entry(k, v);
+        }
+    }
+    tree
+}
+
+struct KeysVisitor<T, B>(PhantomData<(T, B)>);
+
+impl<'de, T: Deserialize<'de>, B: Tree<T>> Visitor<'de> for KeysVisitor<T, B>
+where
+    B::Compare: Default,
+{
+    type Value = B;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a sequence of keys")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<B, A::Error> {
+        let mut entries = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(MAX_PREALLOCATE));
+        while let Some(key) = seq.next_element()? {
+            entries.push((key, ()));
+        }
+        Ok(build(entries))
+    }
+}
+
+struct EntriesVisitor<K, V, B>(PhantomData<(K, V, B)>);
+
+impl<'de, K, V, B> Visitor<'de> for EntriesVisitor<K, V, B>
+where
+    K: Deserialize<'de>,
+    V: Deserialize<'de>,
+    B: Tree<K, V>,
+    B::Compare: Default,
+{
+    type Value = B;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<B, A::Error> {
+        let mut entries = Vec::with_capacity(map.size_hint().unwrap_or(0).min(MAX_PREALLOCATE));
+        while let Some(entry) = map.next_entry()? {
+            entries.push(entry);
+        }
+        Ok(build(entries))
+    }
+}
+
+// Serializes the exact layout of a tree for debugging, e.g. `serde_json::to_string(&Shape::new(&tree))`.
+// The nodes are written as a flat list in pre-order, so the root comes first. Each node has its key, value
+// and balancing state (colour, height, priority or level), and its children as their positions in the list,
+// an empty subtree as a unit/null. Nothing nests, so a tree of any depth can be written out.
+// This is one way only, trees are read back from their keys.
+pub struct Shape<'a, T, V, B> {
+    tree: &'a B,
+    marker: PhantomData<(T, V)>,
+}
+
+impl<'a, T, V, B: Tree<T, V>> Shape<'a, T, V, B> {
+    pub fn new(tree: &'a B) -> Self {
+        Self { tree, marker: PhantomData }
+    }
+}
+
+// the balancing state a node stores, written out as one more field of the node
+pub trait ShapeNode {
+    // how many fields `serialize_balance` writes
+    const BALANCE_FIELDS: usize = 1;
+
+    fn serialize_balance<S: SerializeStruct>(&self, node: &mut S) -> Result<(), S::Error>;
+}
+
+impl<T, V> ShapeNode for RedBlackTreeNode<T, V> {
+    fn serialize_balance<S: SerializeStruct>(&self, node: &mut S) -> Result<(), S::Error> {
+        node.serialize_field("color", self.get_color().name())
+    }
+}
+
+impl<T, V> ShapeNode for AvlTreeNode<T, V> {
+    fn serialize_balance<S: SerializeStruct>(&self, node: &mut S) -> Result<(), S::Error> {
+        node.serialize_field("height", &self.height)
+    }
+}
+
+// splay, scapegoat and weight-balanced tree nodes keep nothing besides their entry
+impl<T, V> ShapeNode for LinkedNode<T, V> {
+    const BALANCE_FIELDS: usize = 0;
+
+    fn serialize_balance<S: SerializeStruct>(&self, _node: &mut S) -> Result<(), S::Error> {
+        Ok(())
+    }
+}
+
+impl<T, V> ShapeNode for LinkedNode<T, V, u64> {
+    fn serialize_balance<S: SerializeStruct>(&self, node: &mut S) -> Result<(), S::Error> {
+        node.serialize_field("priority", self.get_balance())
+    }
+}
+
+impl<T, V> ShapeNode for LinkedNode<T, V, NodeColor> {
+    fn serialize_balance<S: SerializeStruct>(&self, node: &mut S) -> Result<(), S::Error> {
+        node.serialize_field("color", self.get_balance().name())
+    }
+}
+
+impl<T, V> ShapeNode for LinkedNode<T, V, usize> {
+    fn serialize_balance<S: SerializeStruct>(&self, node: &mut S) -> Result<(), S::Error> {
+        node.serialize_field("level", self.get_balance())
+    }
+}
+
+impl<T, V, B> Serialize for Shape<'_, T, V, B>
+where
+    T: Serialize,
+    V: Serialize,
+    B: Tree<T, V>,
+    B::Node: ShapeNode,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let tree = self.tree;
+        // nodes still to write, with their positions in the list
+        let mut stack: Vec<(NodeId, usize)> = tree.get_root().map(|r| (r, 0)).into_iter().collect();
+        let nodes = std::iter::from_fn(move || {
+            let (node, index) = stack.pop()?;
+            // the left subtree comes right after the node, the right one after all of the left
+            let left = tree.left(node).map(|l| (l, index + 1));
+            let right = tree.right(node).map(|r| (r, index + 1 + tree.size_of(tree.left(node))));
+            stack.extend(right);
+            stack.extend(left);
+            Some(ShapeOf { shape: self, node, left: left.map(|(_, i)| i), right: right.map(|(_, i)| i) })
+        });
+        serializer.collect_seq(nodes)
+    }
+}
+
+// one node of a `Shape`, with the positions of its children
+struct ShapeOf<'s, 'a, T, V, B> {
+    shape: &'s Shape<'a, T, V, B>,
+    node: NodeId,
+    left: Option<usize>,
+    right: Option<usize>,
+}
+
+impl<T, V, B> Serialize for ShapeOf<'_, '_, T, V, B>
+where
+    T: Serialize,
+    V: Serialize,
+    B: Tree<T, V>,
+    B::Node: ShapeNode,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let tree = self.shape.tree;
+
+        // key, value, left and right, plus whatever the node keeps to stay balanced
+        let mut node = serializer.serialize_struct("Node", 4 + B::Node::BALANCE_FIELDS)?;
+        node.serialize_field("key", tree.get_key(self.node))?;
+        node.serialize_field("value", tree.get_value(self.node))?;
+        tree.node(self.node).serialize_balance(&mut node)?;
+        node.serialize_field("left", &self.left)?;
+        node.serialize_field("right", &self.right)?;
+        node.end()
+    }
+}
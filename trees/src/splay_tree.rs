@@ -0,0 +1,273 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use crate::tree::*;
+use crate::node::*;
+use crate::arena::Arena;
+use crate::cli::CLIPrintable;
+use crate::comparator::{Comparator, NaturalOrder};
+
+type MaybeSplayTree = Option<NodeId>;
+
+pub type SplayTreeNode<T, V = ()> = LinkedNode<T, V>;
+
+// Self-adjusting search tree (Sleator and Tarjan). Nodes keep no balancing data, instead every
+// insert, delete and lookup moves the node it reached up to the root with splay rotations,
+// so recently used keys stay near the top and any sequence of m operations costs O(m log n).
+// Lookups go through `Tree::search`, `get` and `get_mut`, which splay. `Tree::bst_search` and
+// the other `&self` queries can't restructure the tree and leave it as it is.
+pub struct SplayTree<T, V = (), C = NaturalOrder> {
+    root: MaybeSplayTree,
+    nodes: Arena<SplayTreeNode<T, V>>,
+    compare: C,
+}
+
+impl<T, V, C: Comparator<T>> Tree<T, V> for SplayTree<T, V, C> {
+    type Node = SplayTreeNode<T, V>;
+    type Compare = C;
+
+    fn with_comparator(compare: C) -> Self {
+        Self { root: None, nodes: Arena::new(), compare }
+    }
+
+    fn comparator(&self) -> &C {
+        &self.compare
+    }
+
+    fn get_root(&self) -> MaybeSplayTree {
+        self.root
+    }
+
+    fn set_root(&mut self, node: MaybeSplayTree) {
+        self.root = node;
+    }
+
+    fn nodes(&self) -> &Arena<SplayTreeNode<T, V>> {
+        &self.nodes
+    }
+
+    fn nodes_mut(&mut self) -> &mut Arena<SplayTreeNode<T, V>> {
+        &mut self.nodes
+    }
+
+    fn search<Q: ?Sized>(&mut self, key: &Q) -> bool where T: Borrow<Q>, C: Comparator<Q> {
+        // splay the key to the root, or the last node on the way to it if it is missing
+        self.splay_to(key).is_some()
+    }
+
+    fn insert_fix(&mut self, node: NodeId) -> NodeId {
+        // the new node is the most recently used one, so it goes to the root
+        self.splay(node);
+        node
+    }
+
+    fn build_fix(&mut self, _node: NodeId, _depth: usize, _levels: usize) {
+        // a perfectly balanced tree is a fine splay tree, there is nothing to keep up to date
+    }
+
+    fn join_nodes(&mut self, left: MaybeSplayTree, node: NodeId, right: MaybeSplayTree) -> NodeId {
+        // no balance to restore, the node simply goes on top
+        self.set_children(node, left, right);
+        self.update_size(node);
+        node
+    }
+
+    fn join_two(&mut self, left: MaybeSplayTree, right: MaybeSplayTree) -> MaybeSplayTree {
+        let left = match left {
+            None => return right,
+            Some(l) => l,
+        };
+        // splaying the largest key of the left tree leaves it at the top with no right child
+        let last = self.find_max(left);
+        self.splay(last);
+        self.set_children(last, self.left(last), right);
+        self.update_size(last);
+        Some(last)
+    }
+
+    fn split_nodes_by<F: Fn(&Self, NodeId) -> Ordering>(&mut self, root: MaybeSplayTree, cmp: &F) -> (MaybeSplayTree, MaybeSplayTree, MaybeSplayTree) {
+        let mut node = match root {
+            None => return (None, None, None),
+            Some(n) => n,
+        };
+        // splay the node where the search for the key ends, then cut next to it
+        loop {
+            let next = match cmp(self, node) {
+                Ordering::Greater => self.left(node),
+                Ordering::Less => self.right(node),
+                Ordering::Equal => None,
+            };
+            match next {
+                Some(n) => node = n,
+                None => break,
+            }
+        }
+        self.splay(node);
+
+        let order = cmp(self, node);
+        let (left, right) = self.detach(node);
+        match order {
+            Ordering::Greater => (left, None, Some(self.join_nodes(None, node, right))),
+            Ordering::Less => (Some(self.join_nodes(left, node, None)), None, right),
+            Ordering::Equal => (left, Some(node), right),
+        }
+    }
+
+    // the defaults recurse once per level, which a splay tree that has become a long path can't afford
+    fn union_nodes(&mut self, a: MaybeSplayTree, b: MaybeSplayTree) -> MaybeSplayTree {
+        self.combine_nodes(a, b, [true, true, true])
+    }
+
+    fn intersection_nodes(&mut self, a: MaybeSplayTree, b: MaybeSplayTree) -> MaybeSplayTree {
+        self.combine_nodes(a, b, [false, false, true])
+    }
+
+    fn difference_nodes(&mut self, a: MaybeSplayTree, b: MaybeSplayTree) -> MaybeSplayTree {
+        self.combine_nodes(a, b, [true, false, false])
+    }
+
+    fn symmetric_difference_nodes(&mut self, a: MaybeSplayTree, b: MaybeSplayTree) -> MaybeSplayTree {
+        self.combine_nodes(a, b, [true, true, false])
+    }
+
+    fn remove_node(&mut self, node: NodeId) -> (T, V) {
+        // bring the node to the root, then join the subtrees it leaves behind
+        self.splay(node);
+        let (left, right) = self.detach(node);
+        self.root = self.join_two(left, right);
+        self.nodes.remove(node).into_entry()
+    }
+}
+
+impl<T, V, C> CLIPrintable for SplayTree<T, V, C> {
+    fn pretty_name() -> &'static str {
+        "Splay Tree"
+    }
+}
+
+tree_impls!(SplayTree, SplayTreeNode);
+
+impl<T, V, C: Default> Default for SplayTree<T, V, C> {
+    fn default() -> Self {
+        Self { root: None, nodes: Arena::new(), compare: C::default() }
+    }
+}
+
+impl<T, V, C> SplayTree<T, V, C>
+where
+    C: Comparator<T>
+{
+    // like `Tree::search`, handing back the value stored with the key
+    pub fn get<Q: ?Sized>(&mut self, key: &Q) -> Option<&V> where T: Borrow<Q>, C: Comparator<Q> {
+        let node = self.splay_to(key)?;
+        Some(self.get_value(node))
+    }
+
+    pub fn get_mut<Q: ?Sized>(&mut self, key: &Q) -> Option<&mut V> where T: Borrow<Q>, C: Comparator<Q> {
+        let node = self.splay_to(key)?;
+        Some(self.get_value_mut(node))
+    }
+
+    fn splay_to<Q: ?Sized>(&mut self, key: &Q) -> Option<NodeId> where T: Borrow<Q>, C: Comparator<Q> {
+        let mut node = self.root?;
+        let found = loop {
+            let next = match self.compare_key(node, key) {
+                Ordering::Greater => self.left(node),
+                Ordering::Less => self.right(node),
+                Ordering::Equal => break true,
+            };
+            match next {
+                Some(n) => node = n,
+                None => break false,
+            }
+        };
+        self.splay(node);
+        self.root = Some(node);
+        found.then_some(node)
+    }
+
+    // A set operation on two detached subtrees, `keep` says which keys make it into the result:
+    // the ones only in `a`, the ones only in `b` and the ones in both, which keep their entry
+    // from `a`. The nodes of the smaller subtree go through the larger one in key order, each
+    // splitting off the keys of the larger one below it with a splay, so nothing recurses.
+    fn combine_nodes(&mut self, a: MaybeSplayTree, b: MaybeSplayTree, keep: [bool; 3]) -> MaybeSplayTree {
+        let [only_a, only_b, both] = keep;
+        let a_is_smaller = self.size_of(a) <= self.size_of(b);
+        let (small, mut rest, only_small, only_large) = match a_is_smaller {
+            true => (a, b, only_a, only_b),
+            false => (b, a, only_b, only_a),
+        };
+
+        // everything done so far, all of it below the keys still to come
+        let mut done = None;
+        for node in self.take_in_order(small) {
+            let (less, found, greater) = self.split_nodes_at(rest, node);
+            rest = greater;
+            done = self.keep_or_free(done, less, only_large);
+            let kept = match found {
+                Some(f) => {
+                    let (from_a, from_b) = if a_is_smaller { (node, f) } else { (f, node) };
+                    self.free_nodes(Some(from_b));
+                    self.keep_or_free(None, Some(from_a), both)
+                }
+                None => self.keep_or_free(None, Some(node), only_small),
+            };
+            if let Some(k) = kept {
+                // the node is above everything done, so it goes on top
+                done = Some(self.join_nodes(done, k, None));
+            }
+        }
+        self.keep_or_free(done, rest, only_large)
+    }
+
+    // append a detached subtree whose keys are all above the ones in `done`, or free it
+    fn keep_or_free(&mut self, done: MaybeSplayTree, part: MaybeSplayTree, keep: bool) -> MaybeSplayTree {
+        if keep {
+            self.join_two(done, part)
+        } else {
+            self.free_nodes(part);
+            done
+        }
+    }
+
+    // the nodes of a detached subtree in key order, each of them detached
+    fn take_in_order(&mut self, root: MaybeSplayTree) -> Vec<NodeId> {
+        let mut ids = Vec::new();
+        let mut stack = Vec::new();
+        let mut current = root;
+        while current.is_some() || !stack.is_empty() {
+            while let Some(n) = current {
+                stack.push(n);
+                current = self.left(n);
+            }
+            let n = stack.pop().unwrap();
+            ids.push(n);
+            current = self.right(n);
+        }
+        for &n in &ids {
+            self.detach(n);
+        }
+        ids
+    }
+
+    // rotate a node up until it is the root of the (sub)tree it is in,
+    // it is up to the caller to make it the root of the tree
+    fn splay(&mut self, node: NodeId) {
+        while let Some(parent) = self.get_parent(node) {
+            let side = self.get_is_child(node).unwrap();
+            match self.get_parent(parent) {
+                // zig: the parent is the root, one rotation finishes it
+                None => self.rotate(!side, parent),
+                Some(grandparent) if self.get_is_child(parent) == Some(side) => {
+                    // zig-zig: rotate the grandparent first so that the path gets shorter
+                    self.rotate(!side, grandparent);
+                    self.rotate(!side, parent);
+                }
+                Some(grandparent) => {
+                    // zig-zag: the node comes up between its parent and grandparent
+                    self.rotate(!side, parent);
+                    self.rotate(side, grandparent);
+                }
+            }
+        }
+    }
+}
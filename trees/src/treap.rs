@@ -0,0 +1,267 @@
+use std::cmp::Ordering;
+use crate::tree::*;
+use crate::node::*;
+use crate::arena::Arena;
+use crate::cli::CLIPrintable;
+use crate::comparator::{Comparator, NaturalOrder};
+use crate::validate::ValidationError;
+
+type MaybeTreap = Option<NodeId>;
+
+// the balancing data of a treap node is its priority
+pub type TreapNode<T, V = ()> = LinkedNode<T, V, u64>;
+
+// seed used unless the tree is built `with_seed`
+pub const DEFAULT_SEED: u64 = 0x5EED;
+
+// Randomized search tree (Seidel and Aragon). Every node gets a random priority and the tree
+// is a heap on those priorities, so its shape is that of a BST built by inserting the keys in
+// random order and is balanced in expectation. Priorities come from a generator seeded at
+// construction, so the same seed and the same operations always give the same tree.
+pub struct Treap<T, V = (), C = NaturalOrder> {
+    root: MaybeTreap,
+    nodes: Arena<TreapNode<T, V>>,
+    compare: C,
+    state: u64,
+}
+
+impl<T, V, C: Comparator<T>> Tree<T, V> for Treap<T, V, C> {
+    type Node = TreapNode<T, V>;
+    type Compare = C;
+
+    fn with_comparator(compare: C) -> Self {
+        Self::with_seed_and_comparator(DEFAULT_SEED, compare)
+    }
+
+    fn comparator(&self) -> &C {
+        &self.compare
+    }
+
+    fn get_root(&self) -> MaybeTreap {
+        self.root
+    }
+
+    fn set_root(&mut self, node: MaybeTreap) {
+        self.root = node;
+    }
+
+    fn nodes(&self) -> &Arena<TreapNode<T, V>> {
+        &self.nodes
+    }
+
+    fn nodes_mut(&mut self) -> &mut Arena<TreapNode<T, V>> {
+        &mut self.nodes
+    }
+
+    fn empty_like(&mut self) -> Self where C: Clone {
+        // the other half carries on with a generator of its own, seeded from this one
+        let seed = self.next_priority();
+        Self::with_seed_and_comparator(seed, self.compare.clone())
+    }
+
+    fn insert_fix(&mut self, node: NodeId) -> NodeId {
+        // the new leaf gets its priority and is rotated up until its parent's is higher
+        let priority = self.next_priority();
+        self.node_mut(node).set_balance(priority);
+        while let Some(parent) = self.get_parent(node) {
+            if self.priority(parent) >= priority {
+                break;
+            }
+            let side = self.get_is_child(node).unwrap();
+            self.rotate(!side, parent);
+        }
+        self.climb_to_root(node)
+    }
+
+    fn build_fix(&mut self, _node: NodeId, _depth: usize, _levels: usize) {
+        // `link_sorted` draws the priorities as it links the nodes
+    }
+
+    fn link_sorted(&mut self, ids: &[NodeId], _depth: usize, _levels: usize) -> MaybeTreap {
+        // every node gets a fresh priority and the nodes are linked into the one treap those priorities
+        // give, keeping the right spine on a stack. a node is popped once a higher priority comes along,
+        // and by then its subtree is complete, so every node is linked and sized once
+        let mut spine: Vec<NodeId> = Vec::new();
+        for &node in ids {
+            let priority = self.next_priority();
+            self.node_mut(node).set_balance(priority);
+            let mut below = None;
+            while let Some(&top) = spine.last() {
+                if self.priority(top) >= priority {
+                    break;
+                }
+                spine.pop();
+                self.update_size(top);
+                below = Some(top);
+            }
+            self.set_child(node, Side::Left, below);
+            if let Some(b) = below {
+                self.set_parent(b, Some(Side::Left), Some(node));
+            }
+            if let Some(&top) = spine.last() {
+                self.set_child(top, Side::Right, Some(node));
+                self.set_parent(node, Some(Side::Right), Some(top));
+            }
+            spine.push(node);
+        }
+        let root = spine.first().copied();
+        while let Some(node) = spine.pop() {
+            self.update_size(node);
+        }
+        root
+    }
+
+    fn join_nodes(&mut self, left: MaybeTreap, node: NodeId, right: MaybeTreap) -> NodeId {
+        // the node starts on top with a fresh priority and sinks until the heap order holds
+        let priority = self.next_priority();
+        self.node_mut(node).set_balance(priority);
+        self.set_children(node, left, right);
+        self.update_size(node);
+
+        let mut root = None;
+        loop {
+            let higher = [self.left(node), self.right(node)].into_iter().flatten()
+                .filter(|&c| self.priority(c) > priority)
+                .max_by_key(|&c| self.priority(c));
+            let child = match higher {
+                Some(c) => c,
+                None => break,
+            };
+            let side = self.get_is_child(child).unwrap();
+            self.rotate(!side, node);
+            // the first child rotated up is the new root of the subtree
+            root.get_or_insert(child);
+        }
+        root.unwrap_or(node)
+    }
+
+    fn join_two(&mut self, left: MaybeTreap, right: MaybeTreap) -> MaybeTreap {
+        let (l, r) = match (left, right) {
+            (None, tree) | (tree, None) => return tree,
+            (Some(l), Some(r)) => (l, r),
+        };
+        // the root with the higher priority stays on top and the other tree merges into its inner side
+        if self.priority(l) >= self.priority(r) {
+            let inner = self.take_child(l, Side::Right);
+            if let Some(i) = inner {
+                self.set_parent(i, None, None);
+            }
+            let merged = self.join_two(inner, Some(r));
+            self.set_children(l, self.left(l), merged);
+            self.update_size(l);
+            Some(l)
+        } else {
+            let inner = self.take_child(r, Side::Left);
+            if let Some(i) = inner {
+                self.set_parent(i, None, None);
+            }
+            let merged = self.join_two(Some(l), inner);
+            self.set_children(r, merged, self.right(r));
+            self.update_size(r);
+            Some(r)
+        }
+    }
+
+    fn split_nodes_by<F: Fn(&Self, NodeId) -> Ordering>(&mut self, root: MaybeTreap, cmp: &F) -> (MaybeTreap, MaybeTreap, MaybeTreap) {
+        let node = match root {
+            None => return (None, None, None),
+            Some(n) => n,
+        };
+        // every node keeps its priority, so cutting along the search path leaves two treaps
+        let (left, right) = self.detach(node);
+        match cmp(self, node) {
+            Ordering::Greater => {
+                let (less, found, greater) = self.split_nodes_by(left, cmp);
+                self.set_children(node, greater, right);
+                self.update_size(node);
+                (less, found, Some(node))
+            }
+            Ordering::Less => {
+                let (less, found, greater) = self.split_nodes_by(right, cmp);
+                self.set_children(node, left, less);
+                self.update_size(node);
+                (Some(node), found, greater)
+            }
+            Ordering::Equal => (left, Some(node), right),
+        }
+    }
+
+    fn remove_node(&mut self, node: NodeId) -> (T, V) {
+        // merge the node's subtrees and put the result where the node was
+        let parent = self.get_parent(node);
+        let node_is_child = self.get_is_child(node);
+        let (left, right) = self.detach(node);
+        let merged = self.join_two(left, right);
+
+        match (parent, node_is_child) {
+            (Some(p), Some(side)) => {
+                self.set_child(p, side, merged);
+                if let Some(m) = merged {
+                    self.set_parent(m, Some(side), Some(p));
+                }
+                self.refresh_sizes(Some(p));
+            }
+            _ => self.root = merged,
+        }
+        self.nodes.remove(node).into_entry()
+    }
+
+    fn validate<'a>(&'a self) -> Result<(), ValidationError<&'a T>> where Self::Node: 'a {
+        self.validate_bst()?;
+        let mut stack: Vec<NodeId> = self.root.into_iter().collect();
+        while let Some(node) = stack.pop() {
+            for child in [self.left(node), self.right(node)].into_iter().flatten() {
+                if self.priority(child) > self.priority(node) {
+                    return Err(ValidationError::HeapOrder { key: self.get_key(child), parent: self.get_key(node) });
+                }
+                stack.push(child);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T, V, C> CLIPrintable for Treap<T, V, C> {
+    fn pretty_name() -> &'static str {
+        "Treap"
+    }
+}
+
+tree_impls!(Treap, TreapNode);
+
+impl<T, V, C: Comparator<T>> Treap<T, V, C> {
+    // an empty tree whose priorities are drawn from a generator started at `seed`
+    pub fn with_seed(seed: u64) -> Self where C: Default {
+        Self::with_seed_and_comparator(seed, C::default())
+    }
+
+    pub fn with_seed_and_comparator(seed: u64, compare: C) -> Self {
+        Self { root: None, nodes: Arena::new(), compare, state: seed }
+    }
+}
+
+impl<T, V, C> Treap<T, V, C> {
+    // restart the priority generator, the nodes already in the tree keep theirs
+    pub fn reseed(&mut self, seed: u64) {
+        self.state = seed;
+    }
+
+    fn priority(&self, node: NodeId) -> u64 {
+        *self.nodes[node].get_balance()
+    }
+
+    // splitmix64, a handful of operations per priority and no dependencies
+    fn next_priority(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+impl<T, V, C: Default> Default for Treap<T, V, C> {
+    fn default() -> Self {
+        Self { root: None, nodes: Arena::new(), compare: C::default(), state: DEFAULT_SEED }
+    }
+}
@@ -1,186 +1,1294 @@
-use std::borrow::{Borrow, BorrowMut};
-use std::cell::RefCell;
-use std::iter::{successors, Successors};
-use std::rc::Rc;
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt::{Debug, Display, Write};
+use std::ops::{Bound, RangeBounds};
 use super::node::*;
-use std::cmp::max;
+use super::arena::*;
+use super::comparator::*;
+use super::iter::*;
+use super::rb_tree::NodeColor;
+use super::validate::ValidationError;
 
-pub trait Tree<T: Ord + Copy + std::fmt::Debug + std::fmt::Display> {
-    type Node: Node<T>;
-    fn new() -> Self;
+// Keys are ordered by the tree's comparator, their natural order unless the tree was built `with_comparator`.
+// Lookups take any borrowed form of the key the comparator can order, the way `BTreeMap` does,
+// so a tree of `String` can be searched with a `&str`.
+pub trait Tree<T, V = ()> {
+    type Node: Node<T, V>;
+    type Compare: Comparator<T>;
+
+    fn new() -> Self where Self: Sized, Self::Compare: Default {
+        Self::with_comparator(Self::Compare::default())
+    }
+
+    // an empty tree that orders its keys with `compare`
+    fn with_comparator(compare: Self::Compare) -> Self;
+    fn comparator(&self) -> &Self::Compare;
 
     // ========== functions used by CLI
 
-    fn insert(&mut self, key: T) {
-        // first insert node as though in a BST
-        let root = self.get_root().clone();
-        let new_node = self.bst_insert(root.clone(), key);
-        let new_root = match new_node.clone() {
-            None => root.clone(),
-            Some(n) => Some(self.insert_fix(new_node.clone().unwrap()).clone()),
-        };
-        self.set_root(new_root.clone());
+    // insert a key with a default value, returning false if it was already in the tree.
+    // like `BTreeSet`, an existing key and its value are left as they are
+    fn insert(&mut self, key: T) -> bool where V: Default {
+        if self.search(&key) {
+            return false;
+        }
+        self.insert_new(key, V::default());
+        true
     }
 
-    fn get_height(&self) -> usize;
+    // whether a key is in the tree. trees that adjust themselves to lookups (the splay tree)
+    // override this, `bst_search` never changes the tree
+    fn search<Q: ?Sized>(&mut self, key: &Q) -> bool where T: Borrow<Q>, Self::Compare: Comparator<Q> {
+        self.bst_search(key)
+    }
 
-    fn print_inorder(&self);
+    // number of nodes on the longest path down from the root
+    fn get_height(&self) -> usize {
+        let mut height = 0;
+        let mut stack: Vec<(NodeId, usize)> = self.get_root().map(|r| (r, 1)).into_iter().collect();
+        while let Some((node, depth)) = stack.pop() {
+            height = height.max(depth);
+            stack.extend([self.left(node), self.right(node)].into_iter().flatten().map(|c| (c, depth + 1)));
+        }
+        height
+    }
 
-    fn is_empty(&self) -> bool;
-    
-    fn print_structure(&self);
+    fn print_inorder(&self) where T: Debug {
+        println!("-------- Tree In-Order -------");
+        if self.is_empty() {
+            println!("Empty tree");
+        }
+        for key in self.iter() {
+            println!("{key:?}");
+        }
+        println!("------------------------------");
+    }
+
+    fn is_empty(&self) -> bool {
+        self.get_root().is_none()
+    }
+
+    // print the tree on its side, with the root on the left and smaller keys above it
+    fn print_structure(&self) where T: Display {
+        println!("------- Tree Structure -------");
+        if self.is_empty() {
+            println!("Empty tree");
+        } else {
+            println!("{}", self.format_sideways());
+        }
+        println!("------------------------------");
+    }
 
-    fn count_leaves(&self) -> usize;
+    // like `print_structure`, but drawn top-down with the root on the first line
+    fn print_top_down(&self) where T: Display {
+        println!("------- Tree Structure -------");
+        if self.is_empty() {
+            println!("Empty tree");
+        } else {
+            println!("{}", self.format_top_down());
+        }
+        println!("------------------------------");
+    }
 
-    fn delete(&mut self, k: T);
+    // the tree drawn top-down, every node gets as many columns as its label needs and
+    // the labels are laid out left to right in key order, so no two of them overlap
+    fn format_top_down(&self) -> String where T: Display {
+        // (node, depth, label) in order, and the column each label starts at
+        let mut placed: Vec<(NodeId, usize, Vec<char>)> = Vec::new();
+        let mut columns = std::collections::HashMap::new();
+        let mut stack = Vec::new();
+        let mut current = self.get_root().map(|r| (r, 0));
+        let mut width = 0;
+        while current.is_some() || !stack.is_empty() {
+            while let Some((node, depth)) = current {
+                stack.push((node, depth));
+                current = self.left(node).map(|l| (l, depth + 1));
+            }
+            let (node, depth) = stack.pop().unwrap();
+            let label: Vec<char> = self.structure_label(node).chars().collect();
+            columns.insert(node, width + label.len() / 2);
+            width += label.len() + 1;
+            placed.push((node, depth, label));
+            current = self.right(node).map(|r| (r, depth + 1));
+        }
+
+        // a line of labels for every level, with a line of connectors below all but the last
+        let levels = placed.iter().map(|(_, depth, _)| depth + 1).max().unwrap_or(0);
+        let mut grid = vec![vec![' '; width]; (2 * levels).saturating_sub(1)];
+        for (node, depth, label) in &placed {
+            let center = columns[node];
+            let start = center - label.len() / 2;
+            grid[2 * depth][start..start + label.len()].copy_from_slice(label);
+
+            let (left, right) = (self.left(*node), self.right(*node));
+            if left.is_none() && right.is_none() {
+                continue;
+            }
+            let line = &mut grid[2 * depth + 1];
+            if let Some(l) = left {
+                line[columns[&l]] = '┌';
+                line[columns[&l] + 1..center].fill('─');
+            }
+            if let Some(r) = right {
+                line[center + 1..columns[&r]].fill('─');
+                line[columns[&r]] = '┐';
+            }
+            line[center] = match (left, right) {
+                (Some(_), Some(_)) => '┴',
+                (Some(_), None) => '┘',
+                _ => '└',
+            };
+        }
+
+        let lines: Vec<String> = grid.iter().map(|line| line.iter().collect::<String>().trim_end().to_string()).collect();
+        lines.join("\n")
+    }
+
+    // the tree drawn on its side, a line per node in key order indented by its depth.
+    // nodes deeper than `SIDEWAYS_LEVELS` stay at that indentation with their depth in
+    // front, so a tree that is a long path still fits on the screen
+    fn format_sideways(&self) -> String where T: Display {
+        let mut lines = Vec::with_capacity(self.len());
+        let mut stack = Vec::new();
+        let mut current = self.get_root().map(|r| (r, 0usize, Side::Neither));
+        while current.is_some() || !stack.is_empty() {
+            while let Some((node, depth, from)) = current {
+                stack.push((node, depth, from));
+                current = self.left(node).map(|l| (l, depth + 1, Side::Left));
+            }
+            let (node, depth, from) = stack.pop().unwrap();
+            // couple characters that make tree look 'smooth'
+            let smooth = match from {
+                Side::Left => "┌───────┘",
+                Side::Neither => "",
+                Side::Right => "└───────┐"
+            };
+            let levels = depth.saturating_sub(1);
+            let space = " ".repeat(levels.min(SIDEWAYS_LEVELS) * 8);
+            let deeper = match levels > SIDEWAYS_LEVELS {
+                true => format!("[depth {depth}] "),
+                false => String::new(),
+            };
+            lines.push(format!("{space}{smooth}{deeper}{}", self.structure_label(node)));
+            current = self.right(node).map(|r| (r, depth + 1, Side::Right));
+        }
+        lines.join("\n")
+    }
+
+    // how a node is shown when printing the structure of the tree
+    fn structure_label(&self, node: NodeId) -> String where T: Display {
+        self.get_key(node).to_string()
+    }
+
+    fn count_leaves(&self) -> usize {
+        let mut count = 0;
+        let mut stack: Vec<NodeId> = self.get_root().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            if self.is_leaf(node) {
+                count += 1;
+            }
+            stack.extend([self.left(node), self.right(node)].into_iter().flatten());
+        }
+        count
+    }
+
+    // remove a key from the tree, returning the stored key if it was there
+    fn delete<Q: ?Sized>(&mut self, k: &Q) -> Option<T> where T: Borrow<Q>, Self::Compare: Comparator<Q> {
+        self.remove_entry(k).map(|(key, _)| key)
+    }
+
+    // the tree as a Graphviz DOT graph, e.g. for `dot -Tsvg tree.dot`.
+    // with `show_nil` every missing child is drawn as a NIL leaf, otherwise a lone child
+    // gets an invisible sibling so that it is still drawn on its own side
+    fn to_dot(&self, show_nil: bool) -> String where T: Display {
+        let mut dot = String::from("digraph Tree {\n    node [shape=circle, fontname=\"Helvetica\"];\n");
+        let mut nils = 0;
+        let mut stack: Vec<NodeId> = self.get_root().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            writeln!(dot, "    n{node} [{}];", self.dot_attributes(node)).unwrap();
+            let children = [self.left(node), self.right(node)];
+            for child in children {
+                match child {
+                    Some(c) => writeln!(dot, "    n{node} -> n{c};").unwrap(),
+                    None if show_nil => {
+                        writeln!(dot, "    nil{nils} [shape=box, label=\"NIL\", style=filled, fillcolor=black, fontcolor=white, fontsize=8];").unwrap();
+                        writeln!(dot, "    n{node} -> nil{nils};").unwrap();
+                        nils += 1;
+                    }
+                    None if children.iter().any(Option::is_some) => {
+                        writeln!(dot, "    nil{nils} [style=invis];").unwrap();
+                        writeln!(dot, "    n{node} -> nil{nils} [style=invis];").unwrap();
+                        nils += 1;
+                    }
+                    None => {}
+                }
+            }
+            // push the right child first so that the left subtree is written out first
+            stack.extend(children.into_iter().rev().flatten());
+        }
+        dot.push_str("}\n");
+        dot
+    }
 
     // ========== other functions
 
-    fn get_root(&self) -> &Option<Rc<RefCell<Self::Node>>>;
-    fn set_root(&mut self, node: Option<Rc<RefCell<Self::Node>>>);
-    fn insert_fix(&mut self, node: Rc<RefCell<Self::Node>>) -> Rc<RefCell<Self::Node>>;
-    fn rotate(&mut self, side: Side, node: Rc<RefCell<Self::Node>>);
+    // build a tree out of keys that are already sorted, in linear time
+    fn from_sorted_iter<I: IntoIterator<Item = T>>(keys: I) -> Self where Self: Sized, Self::Compare: Default, V: Default {
+        Self::from_sorted_entries(keys.into_iter().map(|k| (k, V::default())))
+    }
 
-    fn bst_insert(&mut self, root: Option<Rc<RefCell<Self::Node>>>, k: T) ->  Option<Rc<RefCell<Self::Node>>> {
-        let mut node = root.clone();
-        let mut parent = None;
-        let mut n;
-        
-        while node.is_some() {
-            parent = node.clone();
-            n = node.clone().unwrap();
-            if n.as_ref().borrow().greater(k) {
-                node = self.left(n.clone());
-            } else if n.as_ref().borrow().less(k) {
-                node = self.right(n.clone());
+    // build a tree out of entries whose keys are strictly increasing, in linear time.
+    // the nodes are linked up perfectly balanced, `build_fix` then does each tree's own bookkeeping
+    fn from_sorted_entries<I: IntoIterator<Item = (T, V)>>(entries: I) -> Self where Self: Sized, Self::Compare: Default {
+        let mut tree = Self::new();
+        tree.build_sorted(entries);
+        tree
+    }
+
+    // the sorted build into an empty tree, keeping its comparator
+    fn build_sorted<I: IntoIterator<Item = (T, V)>>(&mut self, entries: I) {
+        let mut ids = Vec::new();
+        for (k, v) in entries {
+            if let Some(&last) = ids.last() {
+                assert!(self.compare(self.get_key(last), &k) == Ordering::Less, "keys for a sorted build must be strictly increasing");
+            }
+            ids.push(self.nodes_mut().insert(Self::Node::new(k, v)));
+        }
+
+        // every level but the last one ends up full
+        let levels = (usize::BITS - ids.len().leading_zeros()) as usize;
+        let root = self.link_sorted(&ids, 0, levels);
+        self.set_root(root);
+    }
+
+    // insert many keys, taking the linear time build if the tree is empty and the keys come sorted
+    fn insert_all<I: IntoIterator<Item = T>>(&mut self, keys: I) where Self: Sized, V: Default {
+        let keys: Vec<T> = keys.into_iter().collect();
+        if self.is_empty() && keys.windows(2).all(|w| self.compare(&w[0], &w[1]) == Ordering::Less) {
+            self.build_sorted(keys.into_iter().map(|k| (k, V::default())));
+        } else {
+            for k in keys {
+                self.insert(k);
+            }
+        }
+    }
+
+    // insert a key with its value, if the key already exists only its value is replaced
+    // and the old value is returned
+    fn insert_entry(&mut self, key: T, value: V) -> Option<V> {
+        let root = self.get_root();
+        if let Some(node) = self.bst_find(root, &key) {
+            return Some(std::mem::replace(self.get_value_mut(node), value));
+        }
+        self.insert_new(key, value);
+        None
+    }
+
+    // insert a key that is not in the tree yet, returning the node that holds it
+    fn insert_new(&mut self, key: T, value: V) -> NodeId {
+        // first insert node as though in a BST
+        let root = self.get_root();
+        let new_node = self.bst_insert(root, key, value).expect("key is already in the tree");
+        let new_root = self.insert_fix(new_node);
+        self.set_root(Some(new_root));
+        new_node
+    }
+
+    // remove a key from the tree, returning the stored key together with its value if it existed
+    fn remove_entry<Q: ?Sized>(&mut self, k: &Q) -> Option<(T, V)> where T: Borrow<Q>, Self::Compare: Comparator<Q> {
+        let node = self.bst_find(self.get_root(), k)?;
+        Some(self.remove_node(node))
+    }
+
+    // take a node out of the tree, returning the entry it held
+    fn remove_node(&mut self, node: NodeId) -> (T, V);
+
+    // number of keys in the tree
+    fn len(&self) -> usize {
+        self.size_of(self.get_root())
+    }
+
+    // the k-th smallest key, counting from 0
+    fn select<'a>(&'a self, k: usize) -> Option<&'a T> where Self::Node: 'a {
+        let mut node = self.get_root();
+        let mut k = k;
+        while let Some(n) = node {
+            let left_size = self.size_of(self.left(n));
+            match k.cmp(&left_size) {
+                Ordering::Less => node = self.left(n),
+                Ordering::Equal => return Some(self.get_key(n)),
+                Ordering::Greater => {
+                    // skip the left subtree and this node
+                    k -= left_size + 1;
+                    node = self.right(n);
+                }
+            }
+        }
+        None
+    }
+
+    // number of keys in the tree that are less than `key`
+    fn rank<Q: ?Sized>(&self, key: &Q) -> usize where T: Borrow<Q>, Self::Compare: Comparator<Q> {
+        let mut node = self.get_root();
+        let mut rank = 0;
+        while let Some(n) = node {
+            if self.compare_key(n, key) == Ordering::Less {
+                // this node and everything left of it is smaller
+                rank += self.size_of(self.left(n)) + 1;
+                node = self.right(n);
             } else {
-                return None
-            } 
+                node = self.left(n);
+            }
         }
+        rank
+    }
+
+    // split the tree into the keys less than `key` and the keys greater than it,
+    // handing back the entry for `key` itself if it was in the tree.
+    // the smaller half has its nodes moved into a new tree, so this is O(log n + size of the smaller half)
+    fn split<Q: ?Sized>(mut self, key: &Q) -> (Self, Option<(T, V)>, Self) where Self: Sized, T: Borrow<Q>, Self::Compare: Comparator<Q> + Clone {
+        let root = self.get_root();
+        let (left, found, right) = self.split_nodes(root, key);
+        let entry = found.map(|n| self.nodes_mut().remove(n).into_entry());
 
-        let rc = Some(Rc::new(RefCell::new(Self::Node::new(k))));
-        let new_node = rc.clone().unwrap();
-        if parent.is_none() {
-            self.set_root(rc.clone());
+        let mut other = self.empty_like();
+        if self.size_of(left) < self.size_of(right) {
+            let moved = left.map(|l| other.adopt(self.nodes_mut(), l));
+            other.set_root(moved);
+            self.set_root(right);
+            (other, entry, self)
         } else {
-            let p_ptr = parent.clone().unwrap();
-            let mut p = p_ptr.as_ref().borrow_mut();
-            if p.greater(k) {
-                p.set_child(Side::Left, rc.clone());
-                new_node.as_ref().borrow_mut().set_parent(Some(Side::Left), parent.clone());
+            let moved = right.map(|r| other.adopt(self.nodes_mut(), r));
+            other.set_root(moved);
+            self.set_root(left);
+            (self, entry, other)
+        }
+    }
+
+    // an empty tree set up like this one, for the other half of a split
+    fn empty_like(&mut self) -> Self where Self: Sized, Self::Compare: Clone {
+        Self::with_comparator(self.comparator().clone())
+    }
+
+    // join two trees and a key between them, every key in `left` has to be less than `key`
+    // and every key in `right` greater
+    fn join(left: Self, key: T, right: Self) -> Self where Self: Sized, V: Default {
+        Self::join_entry(left, key, V::default(), right)
+    }
+
+    fn join_entry(left: Self, key: T, value: V, right: Self) -> Self where Self: Sized {
+        assert!(
+            left.last().is_none_or(|k| left.compare(k, &key) == Ordering::Less)
+                && right.first().is_none_or(|k| right.compare(k, &key) == Ordering::Greater),
+            "keys of the left tree must be less than the joining key and keys of the right tree greater"
+        );
+        let left_is_smaller = left.len() < right.len();
+        let (mut tree, mut other) = if left_is_smaller { (right, left) } else { (left, right) };
+
+        // the nodes of the smaller tree move over to the bigger one
+        let kept = tree.get_root();
+        let moved = other.get_root().map(|r| tree.adopt(other.nodes_mut(), r));
+        let node = tree.nodes_mut().insert(Self::Node::new(key, value));
+        let root = match left_is_smaller {
+            true => tree.join_nodes(moved, node, kept),
+            false => tree.join_nodes(kept, node, moved),
+        };
+        tree.set_root(Some(root));
+        tree
+    }
+
+    // join two trees without a key between them, every key in `left` has to be less than every key in `right`
+    fn merge(left: Self, right: Self) -> Self where Self: Sized {
+        assert!(
+            left.last().zip(right.first()).is_none_or(|(l, r)| left.compare(l, r) == Ordering::Less),
+            "keys of the left tree must be less than the keys of the right tree"
+        );
+        let left_is_smaller = left.len() < right.len();
+        let (mut tree, mut other) = if left_is_smaller { (right, left) } else { (left, right) };
+
+        let kept = tree.get_root();
+        let moved = other.get_root().map(|r| tree.adopt(other.nodes_mut(), r));
+        let root = match left_is_smaller {
+            true => tree.join_two(moved, kept),
+            false => tree.join_two(kept, moved),
+        };
+        tree.set_root(root);
+        tree
+    }
+
+    // all keys that are in either tree, keeping the entry from `self` for keys in both
+    fn union(self, other: Self) -> Self where Self: Sized {
+        self.combine(other, Self::union_nodes)
+    }
+
+    // the keys that are in both trees, with their entries from `self`
+    fn intersection(self, other: Self) -> Self where Self: Sized {
+        self.combine(other, Self::intersection_nodes)
+    }
+
+    // the keys of `self` that are not in `other`
+    fn difference(self, other: Self) -> Self where Self: Sized {
+        self.combine(other, Self::difference_nodes)
+    }
+
+    // the keys that are in exactly one of the trees
+    fn symmetric_difference(self, other: Self) -> Self where Self: Sized {
+        self.combine(other, Self::symmetric_difference_nodes)
+    }
+
+    // the smallest key in the tree
+    fn first<'a>(&'a self) -> Option<&'a T> where Self::Node: 'a {
+        self.get_root().map(|r| self.get_key(self.find_min(r)))
+    }
+
+    // the largest key in the tree
+    fn last<'a>(&'a self) -> Option<&'a T> where Self::Node: 'a {
+        self.get_root().map(|r| self.get_key(self.find_max(r)))
+    }
+
+    // remove the smallest key, returning it together with its value
+    fn pop_first(&mut self) -> Option<(T, V)> {
+        let node = self.find_min(self.get_root()?);
+        Some(self.remove_node(node))
+    }
+
+    // remove the largest key, returning it together with its value
+    fn pop_last(&mut self) -> Option<(T, V)> {
+        let node = self.find_max(self.get_root()?);
+        Some(self.remove_node(node))
+    }
+
+    // the largest key less than or equal to `key`
+    fn floor<'a, Q: ?Sized>(&'a self, key: &Q) -> Option<&'a T> where T: Borrow<Q>, Self::Compare: Comparator<Q>, Self::Node: 'a {
+        self.upper_bound(Bound::Included(key)).map(|n| self.get_key(n))
+    }
+
+    // the smallest key greater than or equal to `key`
+    fn ceiling<'a, Q: ?Sized>(&'a self, key: &Q) -> Option<&'a T> where T: Borrow<Q>, Self::Compare: Comparator<Q>, Self::Node: 'a {
+        self.lower_bound(Bound::Included(key)).map(|n| self.get_key(n))
+    }
+
+    // the largest key strictly less than `key`, which doesn't have to be in the tree itself
+    fn predecessor<'a, Q: ?Sized>(&'a self, key: &Q) -> Option<&'a T> where T: Borrow<Q>, Self::Compare: Comparator<Q>, Self::Node: 'a {
+        self.upper_bound(Bound::Excluded(key)).map(|n| self.get_key(n))
+    }
+
+    // the smallest key strictly greater than `key`, which doesn't have to be in the tree itself
+    fn successor<'a, Q: ?Sized>(&'a self, key: &Q) -> Option<&'a T> where T: Borrow<Q>, Self::Compare: Comparator<Q>, Self::Node: 'a {
+        self.lower_bound(Bound::Excluded(key)).map(|n| self.get_key(n))
+    }
+
+    // in-order iterator over the keys of the tree
+    fn iter(&self) -> Iter<'_, T, V, Self::Node> {
+        let root = self.get_root();
+        Iter::new(self.nodes(), root.map(|r| self.find_min(r)), root.map(|r| self.find_max(r)))
+    }
+
+    // in-order iterator over the keys of the tree and their values
+    fn entries(&self) -> Entries<'_, T, V, Self::Node> {
+        Entries::new(self.iter())
+    }
+
+    // in-order iterator over the keys that fall inside `range`, e.g. `tree.range(3..10)`
+    fn range<Q: ?Sized, R: RangeBounds<Q>>(&self, range: R) -> Iter<'_, T, V, Self::Node> where T: Borrow<Q>, Self::Compare: Comparator<Q> {
+        let front = self.lower_bound(range.start_bound());
+        let back = self.upper_bound(range.end_bound());
+        match (front, back) {
+            (Some(f), Some(b)) if self.compare(self.get_key(f), self.get_key(b)) != Ordering::Greater => Iter::new(self.nodes(), Some(f), Some(b)),
+            _ => Iter::new(self.nodes(), None, None),
+        }
+    }
+
+    // the attributes of a node in `to_dot`, trees add the state they balance by
+    fn dot_attributes(&self, node: NodeId) -> String where T: Display {
+        format!("label=\"{}\"", dot_escape(self.get_key(node)))
+    }
+
+    // check that the tree is a valid tree of its kind, returning the first broken rule found
+    fn validate<'a>(&'a self) -> Result<(), ValidationError<&'a T>> where Self::Node: 'a {
+        self.validate_bst()
+    }
+
+    // the rules every binary search tree here follows: keys in order, sizes adding up, parent and child links
+    // agreeing with each other, and no nodes stored that aren't in the tree
+    fn validate_bst<'a>(&'a self) -> Result<(), ValidationError<&'a T>> where Self::Node: 'a {
+        let root = match self.get_root() {
+            None => return match self.nodes().len() {
+                0 => Ok(()),
+                stored => Err(ValidationError::Unreachable { reachable: 0, stored }),
+            },
+            Some(root) => root,
+        };
+        if self.get_parent(root).is_some() {
+            return Err(ValidationError::RootHasParent { key: self.get_key(root) });
+        }
+
+        // nodes still to check, with the closest ancestors they must be greater and less than
+        let mut stack = vec![(root, None, None)];
+        let mut reachable = 0;
+        while let Some((node, low, high)) = stack.pop() {
+            reachable += 1;
+            let key = self.get_key(node);
+            if let Some(low) = low.filter(|l| self.compare(key, *l) != Ordering::Greater) {
+                return Err(ValidationError::OutOfOrder { key, ancestor: low, side: Side::Right });
+            }
+            if let Some(high) = high.filter(|h| self.compare(key, *h) != Ordering::Less) {
+                return Err(ValidationError::OutOfOrder { key, ancestor: high, side: Side::Left });
+            }
+
+            // sizes only need checking locally, the children are checked in turn
+            let size = 1 + self.size_of(self.left(node)) + self.size_of(self.right(node));
+            if self.node(node).get_size() != size {
+                return Err(ValidationError::WrongSize { key, stored: self.node(node).get_size(), actual: size });
+            }
+
+            for side in [Side::Left, Side::Right] {
+                let child = match self.get_child(node, side) {
+                    None => continue,
+                    Some(child) => child,
+                };
+                if self.get_parent(child) != Some(node) {
+                    return Err(ValidationError::BadParent { key: self.get_key(child), parent: key });
+                }
+                if self.get_is_child(child) != Some(side) {
+                    return Err(ValidationError::BadIsChild { key: self.get_key(child), side });
+                }
+                match side {
+                    Side::Left => stack.push((child, low, Some(key))),
+                    _ => stack.push((child, Some(key), high)),
+                }
+            }
+        }
+
+        if reachable != self.nodes().len() {
+            return Err(ValidationError::Unreachable { reachable, stored: self.nodes().len() });
+        }
+        Ok(())
+    }
+
+    // the node with the smallest key that is inside the given lower bound
+    fn lower_bound<Q: ?Sized>(&self, bound: Bound<&Q>) -> Option<NodeId> where T: Borrow<Q>, Self::Compare: Comparator<Q> {
+        let mut node = self.get_root();
+        let mut found = None;
+        while let Some(n) = node {
+            let inside = match bound {
+                Bound::Included(b) => self.compare_key(n, b) != Ordering::Less,
+                Bound::Excluded(b) => self.compare_key(n, b) == Ordering::Greater,
+                Bound::Unbounded => true,
+            };
+            // a match may still have a smaller match in its left subtree
+            if inside {
+                found = Some(n);
+                node = self.left(n);
+            } else {
+                node = self.right(n);
+            }
+        }
+        found
+    }
+
+    // the node with the largest key that is inside the given upper bound
+    fn upper_bound<Q: ?Sized>(&self, bound: Bound<&Q>) -> Option<NodeId> where T: Borrow<Q>, Self::Compare: Comparator<Q> {
+        let mut node = self.get_root();
+        let mut found = None;
+        while let Some(n) = node {
+            let inside = match bound {
+                Bound::Included(b) => self.compare_key(n, b) != Ordering::Greater,
+                Bound::Excluded(b) => self.compare_key(n, b) == Ordering::Less,
+                Bound::Unbounded => true,
+            };
+            if inside {
+                found = Some(n);
+                node = self.right(n);
             } else {
-                p.set_child(Side::Right, rc.clone());
-                new_node.as_ref().borrow_mut().set_parent(Some(Side::Right), parent.clone());
+                node = self.left(n);
+            }
+        }
+        found
+    }
+
+    fn get_root(&self) -> Option<NodeId>;
+    fn set_root(&mut self, node: Option<NodeId>);
+    fn nodes(&self) -> &Arena<Self::Node>;
+    fn nodes_mut(&mut self) -> &mut Arena<Self::Node>;
+    fn insert_fix(&mut self, node: NodeId) -> NodeId;
+    // rotate the subtree at `node` towards `side`, so the child on the other side takes its place.
+    // trees that store more than sizes in their nodes update that on top of this
+    fn rotate(&mut self, side: Side, node: NodeId) {
+        let parent = self.get_parent(node);
+        let node_is_child = self.get_is_child(node);
+        let child = self.get_child(node, !side).expect("nothing to rotate into the node's place");
+
+        // the child's inner subtree moves across to the node
+        let inner = self.get_child(child, side);
+        self.set_child(node, !side, inner);
+        if let Some(i) = inner {
+            self.set_parent(i, Some(!side), Some(node));
+        }
+
+        self.set_child(child, side, Some(node));
+        self.set_parent(node, Some(side), Some(child));
+        self.set_parent(child, node_is_child, parent);
+        if let (Some(p), Some(p_side)) = (parent, node_is_child) {
+            self.set_child(p, p_side, Some(child));
+        }
+
+        self.update_size(node);
+        self.update_size(child);
+    }
+    // called bottom up on every node of a sorted build, with its depth and the number of levels
+    fn build_fix(&mut self, node: NodeId, depth: usize, levels: usize);
+
+    // join two detached subtrees with a detached node whose key lies between them,
+    // rebalancing as the tree requires and returning the root of the result
+    fn join_nodes(&mut self, left: Option<NodeId>, node: NodeId, right: Option<NodeId>) -> NodeId;
+
+    // split a detached subtree into the keys less than and greater than `key`,
+    // and the node holding `key` itself if there is one
+    fn split_nodes<Q: ?Sized>(&mut self, root: Option<NodeId>, key: &Q) -> (Option<NodeId>, Option<NodeId>, Option<NodeId>) where T: Borrow<Q>, Self::Compare: Comparator<Q> {
+        self.split_nodes_by(root, &|tree: &Self, n| tree.compare_key(n, key))
+    }
+
+    // split a detached subtree around the key of a node that is not part of it
+    fn split_nodes_at(&mut self, root: Option<NodeId>, pivot: NodeId) -> (Option<NodeId>, Option<NodeId>, Option<NodeId>) {
+        self.split_nodes_by(root, &|tree: &Self, n| tree.compare(tree.node(n).get_key(), tree.node(pivot).get_key()))
+    }
+
+    // the split itself, `cmp` tells where a node's key lies compared to the key being split around.
+    // the key is compared through the tree so it can be one of the tree's own keys
+    fn split_nodes_by<F: Fn(&Self, NodeId) -> Ordering>(&mut self, root: Option<NodeId>, cmp: &F) -> (Option<NodeId>, Option<NodeId>, Option<NodeId>) {
+        let node = match root {
+            None => return (None, None, None),
+            Some(n) => n,
+        };
+        let (left, right) = self.detach(node);
+        match cmp(self, node) {
+            Ordering::Greater => {
+                let (less, found, greater) = self.split_nodes_by(left, cmp);
+                (less, found, Some(self.join_nodes(greater, node, right)))
             }
+            Ordering::Less => {
+                let (less, found, greater) = self.split_nodes_by(right, cmp);
+                (Some(self.join_nodes(left, node, less)), found, greater)
+            }
+            Ordering::Equal => (left, Some(node), right),
+        }
+    }
+
+    // join two detached subtrees without a key between them
+    fn join_two(&mut self, left: Option<NodeId>, right: Option<NodeId>) -> Option<NodeId> {
+        let left = match left {
+            None => return right,
+            Some(l) => l,
+        };
+        let (rest, last) = self.split_last(left);
+        Some(self.join_nodes(rest, last, right))
+    }
+
+    // take the largest node off a detached subtree, returning what is left and the node
+    fn split_last(&mut self, node: NodeId) -> (Option<NodeId>, NodeId) {
+        // take the right spine apart, then join it back up from the bottom without its last node
+        let mut spine = Vec::new();
+        let mut current = Some(node);
+        while let Some(n) = current {
+            let (left, right) = self.detach(n);
+            spine.push((n, left));
+            current = right;
+        }
+        let (last, mut rest) = spine.pop().unwrap();
+        while let Some((n, left)) = spine.pop() {
+            rest = Some(self.join_nodes(left, n, rest));
         }
-        rc.clone()
+        (rest, last)
     }
 
-    fn bst_search(&self, key:T) -> bool{
-        let root = self.get_root().clone();
-        let node = self.bst_find(root.clone(), key);
+    // unlink a node from its parent and children, returning the children as detached subtrees
+    fn detach(&mut self, node: NodeId) -> (Option<NodeId>, Option<NodeId>) {
+        let left = self.take_child(node, Side::Left);
+        let right = self.take_child(node, Side::Right);
+        for child in [left, right].into_iter().flatten() {
+            self.set_parent(child, None, None);
+        }
+        self.set_parent(node, None, None);
+        (left, right)
+    }
+
+    // move a subtree out of another tree's arena into this one, returning where its root ended up.
+    // the moved subtree is detached, it is up to the caller to link it in
+    fn adopt(&mut self, from: &mut Arena<Self::Node>, node: NodeId) -> NodeId {
+        // nodes still to move, with the moved parent and side they go under
+        let mut stack = vec![(node, None)];
+        let mut root = node;
+        while let Some((old, parent)) = stack.pop() {
+            let mut moved = from.remove(old);
+            let left = moved.take_child(Side::Left);
+            let right = moved.take_child(Side::Right);
+            moved.set_parent(None, None);
+            let id = self.nodes_mut().insert(moved);
+            match parent {
+                None => root = id,
+                Some((p, side)) => {
+                    self.set_child(p, side, Some(id));
+                    self.set_parent(id, Some(side), Some(p));
+                }
+            }
+            for (side, child) in [(Side::Left, left), (Side::Right, right)] {
+                if let Some(c) = child {
+                    stack.push((c, Some((id, side))));
+                }
+            }
+        }
+        root
+    }
+
+    // free every node of a detached subtree
+    fn free_nodes(&mut self, node: Option<NodeId>) {
+        let mut stack: Vec<NodeId> = node.into_iter().collect();
+        while let Some(n) = stack.pop() {
+            let (left, right) = self.detach(n);
+            self.nodes_mut().remove(n);
+            stack.extend([left, right].into_iter().flatten());
+        }
+    }
+
+    // bring two trees into one arena and run a set operation on their roots.
+    // the smaller tree's nodes are the ones moved, so this stays within the cost of the operation
+    fn combine(self, other: Self, op: fn(&mut Self, Option<NodeId>, Option<NodeId>) -> Option<NodeId>) -> Self where Self: Sized {
+        let self_is_smaller = self.len() < other.len();
+        let (mut tree, mut moving) = if self_is_smaller { (other, self) } else { (self, other) };
+
+        let kept = tree.get_root();
+        let moved = moving.get_root().map(|r| tree.adopt(moving.nodes_mut(), r));
+        let root = match self_is_smaller {
+            true => op(&mut tree, moved, kept),
+            false => op(&mut tree, kept, moved),
+        };
+        tree.set_root(root);
+        tree
+    }
+
+    fn union_nodes(&mut self, a: Option<NodeId>, b: Option<NodeId>) -> Option<NodeId> {
+        let (a, b) = match (a, b) {
+            (None, b) => return b,
+            (a, None) => return a,
+            (Some(a), Some(b)) => (a, b),
+        };
+        let (a_left, a_right) = self.detach(a);
+        let (b_left, duplicate, b_right) = self.split_nodes_at(Some(b), a);
+        self.free_nodes(duplicate);
+        let left = self.union_nodes(a_left, b_left);
+        let right = self.union_nodes(a_right, b_right);
+        Some(self.join_nodes(left, a, right))
+    }
+
+    fn intersection_nodes(&mut self, a: Option<NodeId>, b: Option<NodeId>) -> Option<NodeId> {
+        let (a, b) = match (a, b) {
+            (Some(a), Some(b)) => (a, b),
+            (a, b) => {
+                self.free_nodes(a);
+                self.free_nodes(b);
+                return None;
+            }
+        };
+        let (a_left, a_right) = self.detach(a);
+        let (b_left, found, b_right) = self.split_nodes_at(Some(b), a);
+        let left = self.intersection_nodes(a_left, b_left);
+        let right = self.intersection_nodes(a_right, b_right);
+        match found {
+            Some(_) => {
+                self.free_nodes(found);
+                Some(self.join_nodes(left, a, right))
+            }
+            None => {
+                self.free_nodes(Some(a));
+                self.join_two(left, right)
+            }
+        }
+    }
+
+    fn difference_nodes(&mut self, a: Option<NodeId>, b: Option<NodeId>) -> Option<NodeId> {
+        let (a, b) = match (a, b) {
+            (None, b) => {
+                self.free_nodes(b);
+                return None;
+            }
+            (a, None) => return a,
+            (Some(a), Some(b)) => (a, b),
+        };
+        // split `a` around the root of `b`, which goes either way
+        let (b_left, b_right) = self.detach(b);
+        let (a_left, found, a_right) = self.split_nodes_at(Some(a), b);
+        self.free_nodes(Some(b));
+        self.free_nodes(found);
+        let left = self.difference_nodes(a_left, b_left);
+        let right = self.difference_nodes(a_right, b_right);
+        self.join_two(left, right)
+    }
+
+    fn symmetric_difference_nodes(&mut self, a: Option<NodeId>, b: Option<NodeId>) -> Option<NodeId> {
+        let (a, b) = match (a, b) {
+            (None, b) => return b,
+            (a, None) => return a,
+            (Some(a), Some(b)) => (a, b),
+        };
+        let (a_left, a_right) = self.detach(a);
+        let (b_left, found, b_right) = self.split_nodes_at(Some(b), a);
+        let left = self.symmetric_difference_nodes(a_left, b_left);
+        let right = self.symmetric_difference_nodes(a_right, b_right);
+        match found {
+            Some(_) => {
+                // the key is in both, so it goes
+                self.free_nodes(found);
+                self.free_nodes(Some(a));
+                self.join_two(left, right)
+            }
+            None => Some(self.join_nodes(left, a, right)),
+        }
+    }
+
+    // link the middle node over the halves on each side, returning it as the root of the subtree
+    fn link_sorted(&mut self, ids: &[NodeId], depth: usize, levels: usize) -> Option<NodeId> {
+        if ids.is_empty() {
+            return None;
+        }
+        let mid = ids.len() / 2;
+        let node = ids[mid];
+        for (side, half) in [(Side::Left, &ids[..mid]), (Side::Right, &ids[mid + 1..])] {
+            let child = self.link_sorted(half, depth + 1, levels);
+            self.set_child(node, side, child);
+            if let Some(c) = child {
+                self.set_parent(c, Some(side), Some(node));
+            }
+        }
+        self.update_size(node);
+        self.build_fix(node, depth, levels);
+        Some(node)
+    }
+
+    fn bst_insert(&mut self, root: Option<NodeId>, k: T, v: V) -> Option<NodeId> {
+        let mut node = root;
+        let mut parent = None;
+
+        while let Some(n) = node {
+            // remember which side we went down, the key is moved into the new node below
+            match self.compare(self.get_key(n), &k) {
+                Ordering::Greater => {
+                    parent = Some((n, Side::Left));
+                    node = self.left(n);
+                }
+                Ordering::Less => {
+                    parent = Some((n, Side::Right));
+                    node = self.right(n);
+                }
+                Ordering::Equal => return None,
+            }
+        }
+
+        let new_node = self.nodes_mut().insert(Self::Node::new(k, v));
+        match parent {
+            None => self.set_root(Some(new_node)),
+            Some((p, side)) => {
+                self.set_child(p, side, Some(new_node));
+                self.set_parent(new_node, Some(side), Some(p));
+                // every node on the way down gained a key
+                self.refresh_sizes(Some(p));
+            }
+        }
+        Some(new_node)
+    }
+
+    // unlink a node the way a plain BST delete does, moving its predecessor's entry into it first
+    // if it has two children. returns the node taken out, which holds the entry, and its parent
+    fn bst_unlink(&mut self, node: NodeId) -> (NodeId, Option<NodeId>) {
+        let mut node = node;
+        if let (Some(left), Some(_)) = (self.left(node), self.right(node)) {
+            let predecessor = self.find_max(left);
+            self.swap_entry(node, predecessor);
+            node = predecessor;
+        }
+
+        let parent = self.get_parent(node);
+        let child = self.left(node).or(self.right(node));
+        match (parent, self.get_is_child(node)) {
+            (Some(p), Some(side)) => {
+                self.set_child(p, side, child);
+                if let Some(c) = child {
+                    self.set_parent(c, Some(side), Some(p));
+                }
+                self.refresh_sizes(Some(p));
+            }
+            _ => {
+                if let Some(c) = child {
+                    self.set_parent(c, None, None);
+                }
+                self.set_root(child);
+            }
+        }
+        (node, parent)
+    }
+
+    fn bst_search<Q: ?Sized>(&self, key: &Q) -> bool where T: Borrow<Q>, Self::Compare: Comparator<Q> {
+        let root = self.get_root();
+        let node = self.bst_find(root, key);
         node.is_some()
     }
-    
-    fn bst_find(&self, root: Option<Rc<RefCell<Self::Node>>>, k: T) -> Option<Rc<RefCell<Self::Node>>> {
+
+    fn bst_find<Q: ?Sized>(&self, root: Option<NodeId>, k: &Q) -> Option<NodeId> where T: Borrow<Q>, Self::Compare: Comparator<Q> {
         let mut current_node = root;
         // Find then replace
         loop {
-            if current_node.is_none() {
-                return None
-            }
-            let n = current_node.clone().unwrap();
-            if n.as_ref().borrow().equal(k) {
-                return current_node.clone()
-            }
-            else if n.as_ref().borrow().greater(k) {
-                current_node = n.as_ref().borrow().get_child(Side::Left);
-            }
-            else {
-                current_node = n.as_ref().borrow().get_child(Side::Right);
+            let n = current_node?;
+            match self.compare_key(n, k) {
+                Ordering::Equal => return current_node,
+                Ordering::Greater => current_node = self.get_child(n, Side::Left),
+                Ordering::Less => current_node = self.get_child(n, Side::Right),
             }
         }
     }
 
-    fn get_parent(&self, node: Rc<RefCell<Self::Node>>) -> Option<Rc<RefCell<Self::Node>>> {
-        node.as_ref().borrow().get_parent().clone()
+    fn node(&self, node: NodeId) -> &Self::Node {
+        &self.nodes()[node]
+    }
+
+    fn node_mut(&mut self, node: NodeId) -> &mut Self::Node {
+        &mut self.nodes_mut()[node]
     }
 
-    fn get_child(&self, node: Rc<RefCell<Self::Node>>, side: Side) -> Option<Rc<RefCell<Self::Node>>> {
-        node.as_ref().borrow().get_child(side).clone()
+    fn get_parent(&self, node: NodeId) -> Option<NodeId> {
+        self.node(node).get_parent()
     }
 
-    fn take_child(&mut self, node: Rc<RefCell<Self::Node>>, side: Side) -> Option<Rc<RefCell<Self::Node>>> {
-        node.as_ref().borrow_mut().take_child(side)
+    fn get_child(&self, node: NodeId, side: Side) -> Option<NodeId> {
+        self.node(node).get_child(side)
     }
 
-    fn is_leaf(&self, node: Rc<RefCell<Self::Node>>) -> bool {
-        node.as_ref().borrow().is_leaf()
+    fn take_child(&mut self, node: NodeId, side: Side) -> Option<NodeId> {
+        self.node_mut(node).take_child(side)
     }
 
-    fn right(&self, node: Rc<RefCell<Self::Node>>) -> Option<Rc<RefCell<Self::Node>>> {
-        node.as_ref().borrow().right().clone()
+    fn is_leaf(&self, node: NodeId) -> bool {
+        self.node(node).is_leaf()
     }
 
-    fn left(&self, node: Rc<RefCell<Self::Node>>) -> Option<Rc<RefCell<Self::Node>>> {
-        node.as_ref().borrow().left().clone()
+    fn right(&self, node: NodeId) -> Option<NodeId> {
+        self.node(node).right()
     }
 
-    fn get_is_child(&self, node: Rc<RefCell<Self::Node>>) -> Option<Side> {
-        node.as_ref().borrow().get_is_child().clone()
+    fn left(&self, node: NodeId) -> Option<NodeId> {
+        self.node(node).left()
     }
 
-    fn get_key(&self, node: Rc<RefCell<Self::Node>>) -> T {
-        node.as_ref().borrow().get_key().clone()
+    fn get_is_child(&self, node: NodeId) -> Option<Side> {
+        *self.node(node).get_is_child()
     }
 
-    fn set_key(&self, node: Rc<RefCell<Self::Node>>, key: T) {
-        node.as_ref().borrow_mut().set_key(key);
+    fn get_sibling(&self, node: NodeId) -> Option<NodeId> {
+        let parent = self.get_parent(node)?;
+        match self.get_is_child(node) {
+            Some(side) => self.get_child(parent, !side),
+            None => None,
+        }
     }
 
-    fn set_child(&self, parent: Rc<RefCell<Self::Node>>, side: Side, child: Option<Rc<RefCell<Self::Node>>>) {
-        parent.as_ref().borrow_mut().set_child(side, child.clone());
+    fn get_uncle(&self, node: NodeId) -> Option<NodeId> {
+        let parent = self.get_parent(node)?;
+        self.get_sibling(parent)
     }
 
-    
-    fn set_parent(&self, child: Rc<RefCell<Self::Node>>, side: Option<Side>, parent: Option<Rc<RefCell<Self::Node>>>) {
-        child.as_ref().borrow_mut().set_parent(side, parent.clone());
+    fn get_grandparent(&self, node: NodeId) -> Option<NodeId> {
+        let parent = self.get_parent(node)?;
+        self.get_parent(parent)
     }
 
-    fn insert_side(&self, node: Rc<RefCell<Self::Node>>, k: T) -> Option<Side> {
-        let n = node.as_ref().borrow();
-        if n.greater(k) {
-            Some(Side::Left)
-        } else if n.less(k) {
-            Some(Side::Right)
-        } else {
-            None
+    fn get_key<'a>(&'a self, node: NodeId) -> &'a T where Self::Node: 'a {
+        self.node(node).get_key()
+    }
+
+    fn get_value<'a>(&'a self, node: NodeId) -> &'a V where Self::Node: 'a {
+        self.node(node).get_value()
+    }
+
+    fn get_value_mut<'a>(&'a mut self, node: NodeId) -> &'a mut V where Self::Node: 'a {
+        self.node_mut(node).get_value_mut()
+    }
+
+    // swap the keys and values of two nodes, leaving the nodes themselves where they are in the tree
+    fn swap_entry(&mut self, a: NodeId, b: NodeId) {
+        let (a, b) = self.nodes_mut().pair_mut(a, b);
+        std::mem::swap(a.get_key_mut(), b.get_key_mut());
+        std::mem::swap(a.get_value_mut(), b.get_value_mut());
+    }
+
+    fn set_child(&mut self, parent: NodeId, side: Side, child: Option<NodeId>) {
+        self.node_mut(parent).set_child(side, child);
+    }
+
+    fn set_parent(&mut self, child: NodeId, side: Option<Side>, parent: Option<NodeId>) {
+        self.node_mut(child).set_parent(side, parent);
+    }
+
+    // make `left` and `right` the children of `node`, linking them back to it
+    fn set_children(&mut self, node: NodeId, left: Option<NodeId>, right: Option<NodeId>) {
+        for (side, child) in [(Side::Left, left), (Side::Right, right)] {
+            self.set_child(node, side, child);
+            if let Some(c) = child {
+                self.set_parent(c, Some(side), Some(node));
+            }
         }
     }
 
-    fn climb_to_root(&self, node: Rc<RefCell<Self::Node>>) -> Rc<RefCell<Self::Node>> {
-        let parent = node.as_ref().borrow().get_parent();
-        if parent.is_none() {
-            node
-        } else {
-           self.climb_to_root(parent.clone().unwrap())
+    fn size_of(&self, node: Option<NodeId>) -> usize {
+        match node {
+            Some(n) => self.node(n).get_size(),
+            None => 0,
         }
     }
 
-    fn find_min(&self, node: Rc<RefCell<Self::Node>>) -> Rc<RefCell<Self::Node>> {
-        let n = node.as_ref().borrow();
-        match n.left() {
-            None => node.clone(),
-            Some(child) => self.find_min(n.left().clone().unwrap().clone()),
+    // recompute the size of a node from its children
+    fn update_size(&mut self, node: NodeId) {
+        let size = 1 + self.size_of(self.left(node)) + self.size_of(self.right(node));
+        self.node_mut(node).set_size(size);
+    }
+
+    // recompute the sizes of a node and all of its ancestors
+    fn refresh_sizes(&mut self, node: Option<NodeId>) {
+        let mut current = node;
+        while let Some(n) = current {
+            self.update_size(n);
+            current = self.get_parent(n);
         }
     }
 
-    fn find_max(&self, node: Rc<RefCell<Self::Node>>) -> Rc<RefCell<Self::Node>> {
-        let n = node.as_ref().borrow();
-        match n.right() {
-            None => node.clone(),
-            Some(child) => self.find_max(n.right().clone().unwrap().clone()),
+    fn insert_side<Q: ?Sized>(&self, node: NodeId, k: &Q) -> Option<Side> where T: Borrow<Q>, Self::Compare: Comparator<Q> {
+        match self.compare_key(node, k) {
+            Ordering::Greater => Some(Side::Left),
+            Ordering::Less => Some(Side::Right),
+            Ordering::Equal => None,
+        }
+    }
+
+    // order two keys the way this tree does
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        self.comparator().compare(a, b)
+    }
+
+    // order the key of a node against a query, e.g. `Greater` if the node's key is greater
+    fn compare_key<Q: ?Sized>(&self, node: NodeId, key: &Q) -> Ordering where T: Borrow<Q>, Self::Compare: Comparator<Q> {
+        self.comparator().compare(self.get_key(node).borrow(), key)
+    }
+
+    fn climb_to_root(&self, node: NodeId) -> NodeId {
+        let mut current = node;
+        while let Some(parent) = self.get_parent(current) {
+            current = parent;
+        }
+        current
+    }
+
+    fn find_min(&self, node: NodeId) -> NodeId {
+        last_node(self.nodes(), node, Side::Left)
+    }
+
+    fn find_max(&self, node: NodeId) -> NodeId {
+        last_node(self.nodes(), node, Side::Right)
+    }
+
+}
+
+// The constructors and iterator impls every tree in its own module has the same way, for a tree
+// type with `root` and `nodes` fields and `C` as its comparator parameter. `Default` stays with
+// each tree since it has to fill in the tree's own fields.
+macro_rules! tree_impls {
+    ($tree:ident, $node:ident) => {
+        impl<T> $tree<T> {
+            // a tree of bare keys, so `new()` works without naming a value type
+            pub fn new() -> Self {
+                Self::default()
+            }
+
+            // same as `Tree::from_sorted_iter`, but without naming a value type
+            pub fn from_sorted_iter<I: IntoIterator<Item = T>>(keys: I) -> Self
+            where
+                T: Ord,
+            {
+                <Self as $crate::tree::Tree<T>>::from_sorted_iter(keys)
+            }
+        }
+
+        impl<T, C: $crate::comparator::Comparator<T>> $tree<T, (), C> {
+            // a tree of bare keys ordered by `compare` instead of their natural order
+            pub fn with_comparator(compare: C) -> Self {
+                <Self as $crate::tree::Tree<T>>::with_comparator(compare)
+            }
+        }
+
+        impl<T, V: Default, C: $crate::comparator::Comparator<T> + Default> FromIterator<T> for $tree<T, V, C> {
+            fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+                let mut tree = Self::default();
+                $crate::tree::Tree::insert_all(&mut tree, iter);
+                tree
+            }
+        }
+
+        impl<T, V: Default, C: $crate::comparator::Comparator<T>> Extend<T> for $tree<T, V, C> {
+            fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+                $crate::tree::Tree::insert_all(self, iter);
+            }
+        }
+
+        impl<'a, T, V, C: $crate::comparator::Comparator<T>> IntoIterator for &'a $tree<T, V, C> {
+            type Item = &'a T;
+            type IntoIter = $crate::iter::Iter<'a, T, V, $node<T, V>>;
+
+            fn into_iter(self) -> Self::IntoIter {
+                $crate::tree::Tree::iter(self)
+            }
         }
+
+        impl<T, V, C> IntoIterator for $tree<T, V, C> {
+            type Item = T;
+            type IntoIter = $crate::iter::IntoIter<T, V, $node<T, V>>;
+
+            fn into_iter(self) -> Self::IntoIter {
+                $crate::iter::IntoIter::new(self.nodes, self.root)
+            }
+        }
+    };
+}
+pub(crate) use tree_impls;
+
+// how many levels `format_sideways` indents before it starts writing depths out instead
+const SIDEWAYS_LEVELS: usize = 32;
+
+// a key as it can go inside a quoted DOT string
+pub(crate) fn dot_escape<T: Display>(key: &T) -> String {
+    key.to_string().replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+// The sorted build of the trees that are binary forms of a 2-3 tree, whose rules a perfectly
+// balanced tree doesn't always meet. They link the keys up as a 2-3 tree with every leaf
+// `height_2_3` levels down, 2-nodes wherever the keys allow and 3-nodes where there are too many.
+pub(crate) fn height_2_3(len: usize) -> usize {
+    (usize::BITS - (len + 1).leading_zeros() - 1) as usize
+}
+
+// how many of `len` keys go below the first child of a 2-3 node `height` levels above the leaves,
+// and below the middle child if it needs two keys. the rest go below the last child
+pub(crate) fn split_2_3(len: usize, height: usize) -> (usize, Option<usize>) {
+    // the most keys a 2-3 tree one level lower can hold
+    let most = 3usize.saturating_pow(height as u32 - 1) - 1;
+    if len - 1 <= most.saturating_mul(2) {
+        let below = len - 1;
+        (below - below / 2, None)
+    } else {
+        let below = len - 2;
+        let (third, extra) = (below / 3, below % 3);
+        (third + usize::from(extra > 0), Some(third + usize::from(extra > 1)))
+    }
+}
+
+// The trees that are binary forms of a 2-3 tree draw a 3-node their own way, the rest of
+// linking sorted keys up as a 2-3 tree is the same for all of them.
+pub(crate) trait TwoThreeTree<T, V>: Tree<T, V> {
+    // mark a node as the top of a 2-3 node `height` levels above the leaves
+    fn set_2_3_height(&mut self, node: NodeId, height: usize);
+
+    // hang the two keys of a 3-node `height` levels up over its three subtrees, returning the top one
+    fn link_3_node(&mut self, low: NodeId, high: NodeId, children: [Option<NodeId>; 3], height: usize) -> NodeId;
+
+    // link sorted nodes up as a 2-3 tree `height` levels high
+    fn link_2_3(&mut self, ids: &[NodeId], height: usize) -> Option<NodeId> {
+        if ids.is_empty() {
+            return None;
+        }
+        let node = match split_2_3(ids.len(), height) {
+            (first, None) => {
+                let left = self.link_2_3(&ids[..first], height - 1);
+                let right = self.link_2_3(&ids[first + 1..], height - 1);
+                self.set_children(ids[first], left, right);
+                ids[first]
+            }
+            (first, Some(middle)) => {
+                let (low, high) = (ids[first], ids[first + middle + 1]);
+                let left = self.link_2_3(&ids[..first], height - 1);
+                let between = self.link_2_3(&ids[first + 1..first + middle + 1], height - 1);
+                let right = self.link_2_3(&ids[first + middle + 2..], height - 1);
+                self.link_3_node(low, high, [left, between, right], height)
+            }
+        };
+        self.set_2_3_height(node, height);
+        self.update_size(node);
+        Some(node)
+    }
+}
+
+// What the red-black trees have in common once they can read and set the colour of a node.
+pub(crate) trait RedBlack<T, V>: Tree<T, V> {
+    fn get_color(&self, node: NodeId) -> NodeColor;
+    fn set_color(&mut self, node: NodeId, color: NodeColor);
+
+    fn is_red(&self, node: Option<NodeId>) -> bool {
+        node.is_some_and(|n| self.get_color(n) == NodeColor::Red)
+    }
+
+    // number of black nodes on the way down from a node, all paths have the same count
+    fn black_height(&self, node: Option<NodeId>) -> usize {
+        let mut height = 0;
+        let mut current = node;
+        while let Some(n) = current {
+            if !self.is_red(current) {
+                height += 1;
+            }
+            current = self.left(n);
+        }
+        height
+    }
+
+    // `Tree::join_nodes` for a red-black tree, `insert_fix` puts right whatever the join breaks
+    fn join_colored(&mut self, left: Option<NodeId>, node: NodeId, right: Option<NodeId>) -> NodeId {
+        // making the roots black only adds to the black height of each side, it can't break a rule
+        for root in [left, right].into_iter().flatten() {
+            self.set_color(root, NodeColor::Black);
+        }
+        let left_height = self.black_height(left);
+        let right_height = self.black_height(right);
+
+        let (side, tall, short, short_height) = match left_height.cmp(&right_height) {
+            Ordering::Equal => {
+                self.set_children(node, left, right);
+                self.set_color(node, NodeColor::Black);
+                self.update_size(node);
+                return node;
+            }
+            Ordering::Greater => (Side::Right, left.unwrap(), right, right_height),
+            Ordering::Less => (Side::Left, right.unwrap(), left, left_height),
+        };
+
+        // walk down the inner edge of the taller side to a black subtree with the same black height
+        // as the shorter side, the node takes its place as a red node over both of them
+        let mut parent = tall;
+        let mut height = left_height.max(right_height) - 1;
+        let mut child = self.get_child(parent, side);
+        while self.is_red(child) || height != short_height {
+            if !self.is_red(child) {
+                height -= 1;
+            }
+            parent = child.unwrap();
+            child = self.get_child(parent, side);
+        }
+
+        match side {
+            Side::Right => self.set_children(node, child, short),
+            _ => self.set_children(node, short, child),
+        }
+        self.set_color(node, NodeColor::Red);
+        self.set_child(parent, side, Some(node));
+        self.set_parent(node, Some(side), Some(parent));
+        self.refresh_sizes(Some(node));
+
+        // the only rule that can be broken is a red parent, same as after an insert
+        let root = self.insert_fix(node);
+        self.set_color(root, NodeColor::Black);
+        root
     }
-    
 }
@@ -0,0 +1,206 @@
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+use super::node::NodeId;
+use super::tree::*;
+use super::comparator::Comparator;
+use super::rb_tree::RedBlackTree;
+use super::avl_tree::AvlTree;
+
+// A sorted map from keys to values, backed by one of the balanced trees.
+// The tree decides how the map is kept balanced and keeps count of its entries.
+pub struct TreeMap<K, V, B = RedBlackTree<K, V>> {
+    tree: B,
+    marker: PhantomData<(K, V)>,
+}
+
+pub type RedBlackTreeMap<K, V> = TreeMap<K, V, RedBlackTree<K, V>>;
+pub type AvlTreeMap<K, V> = TreeMap<K, V, AvlTree<K, V>>;
+
+impl<K, V, B> TreeMap<K, V, B>
+where
+    B: Tree<K, V>,
+{
+    pub fn new() -> Self where B::Compare: Default {
+        Self { tree: B::new(), marker: PhantomData }
+    }
+
+    // an empty map ordering its keys with `compare`
+    pub fn with_comparator(compare: B::Compare) -> Self {
+        Self { tree: B::with_comparator(compare), marker: PhantomData }
+    }
+
+    // a map over a tree that already holds its entries
+    pub fn from_tree(tree: B) -> Self {
+        Self { tree, marker: PhantomData }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    // the underlying tree, e.g. to print its structure
+    pub fn tree(&self) -> &B {
+        &self.tree
+    }
+
+    // like `BTreeMap`, keys can be looked up by any borrowed form of them, e.g. `&str` for `String` keys
+    pub fn contains_key<Q: ?Sized>(&self, key: &Q) -> bool where K: Borrow<Q>, B::Compare: Comparator<Q> {
+        self.tree.bst_search(key)
+    }
+
+    pub fn get<Q: ?Sized>(&self, key: &Q) -> Option<&V> where K: Borrow<Q>, B::Compare: Comparator<Q> {
+        self.find(key).map(|node| self.tree.get_value(node))
+    }
+
+    pub fn get_mut<Q: ?Sized>(&mut self, key: &Q) -> Option<&mut V> where K: Borrow<Q>, B::Compare: Comparator<Q> {
+        self.find(key).map(|node| self.tree.get_value_mut(node))
+    }
+
+    // insert a key-value pair, returning the value it replaced if the key was already present
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.tree.insert_entry(key, value)
+    }
+
+    pub fn remove<Q: ?Sized>(&mut self, key: &Q) -> Option<V> where K: Borrow<Q>, B::Compare: Comparator<Q> {
+        self.remove_entry(key).map(|(_, v)| v)
+    }
+
+    pub fn remove_entry<Q: ?Sized>(&mut self, key: &Q) -> Option<(K, V)> where K: Borrow<Q>, B::Compare: Comparator<Q> {
+        self.tree.remove_entry(key)
+    }
+
+    // in-place access to the entry for a key, whether or not it is in the map.
+    // an occupied entry keeps the key that is already in the map and drops the one passed in
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, B> {
+        match self.find(&key) {
+            Some(node) => Entry::Occupied(OccupiedEntry { node, map: self }),
+            None => Entry::Vacant(VacantEntry { key, map: self }),
+        }
+    }
+
+    fn find<Q: ?Sized>(&self, key: &Q) -> Option<NodeId> where K: Borrow<Q>, B::Compare: Comparator<Q> {
+        self.tree.bst_find(self.tree.get_root(), key)
+    }
+}
+
+impl<K, V, B> Default for TreeMap<K, V, B>
+where
+    B: Tree<K, V>,
+    B::Compare: Default,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub enum Entry<'a, K, V, B: Tree<K, V>> {
+    Vacant(VacantEntry<'a, K, V, B>),
+    Occupied(OccupiedEntry<'a, K, V, B>),
+}
+
+pub struct VacantEntry<'a, K, V, B> {
+    key: K,
+    map: &'a mut TreeMap<K, V, B>,
+}
+
+pub struct OccupiedEntry<'a, K, V, B> {
+    node: NodeId,
+    map: &'a mut TreeMap<K, V, B>,
+}
+
+impl<'a, K, V, B> Entry<'a, K, V, B>
+where
+    B: Tree<K, V>,
+{
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Vacant(e) => e.key(),
+            Entry::Occupied(e) => e.key(),
+        }
+    }
+
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Vacant(e) => e.insert(default()),
+            Entry::Occupied(e) => e.into_mut(),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    // run `f` on the value if the key is present, before any `or_insert`
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(mut e) => {
+                f(e.get_mut());
+                Entry::Occupied(e)
+            }
+            Entry::Vacant(e) => Entry::Vacant(e),
+        }
+    }
+}
+
+impl<'a, K, V, B> VacantEntry<'a, K, V, B>
+where
+    B: Tree<K, V>,
+{
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    pub fn insert(self, value: V) -> &'a mut V {
+        let node = self.map.tree.insert_new(self.key, value);
+        self.map.tree.get_value_mut(node)
+    }
+}
+
+impl<'a, K, V, B> OccupiedEntry<'a, K, V, B>
+where
+    B: Tree<K, V>,
+{
+    pub fn key(&self) -> &K {
+        self.map.tree.get_key(self.node)
+    }
+
+    pub fn get(&self) -> &V {
+        self.map.tree.get_value(self.node)
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        self.map.tree.get_value_mut(self.node)
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        self.map.tree.get_value_mut(self.node)
+    }
+
+    // replace the value of the entry, returning the old one
+    pub fn insert(&mut self, value: V) -> V {
+        std::mem::replace(self.get_mut(), value)
+    }
+
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    pub fn remove_entry(self) -> (K, V) {
+        self.map.tree.remove_node(self.node)
+    }
+}
@@ -0,0 +1,116 @@
+use std::fmt::{Debug, Display, Formatter};
+use super::node::Side;
+
+// The first broken invariant found by `Tree::validate`, naming the key of the offending node.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValidationError<T> {
+    // the root still points at a parent
+    RootHasParent { key: T },
+    // a key sits on the wrong side of one of its ancestors
+    OutOfOrder { key: T, ancestor: T, side: Side },
+    // a child's parent link does not point back at the node it hangs from
+    BadParent { key: T, parent: T },
+    // a child does not know which side of its parent it hangs from
+    BadIsChild { key: T, side: Side },
+    // the subtree size stored in a node doesn't add up from its children
+    WrongSize { key: T, stored: usize, actual: usize },
+    // the arena holds nodes that can't be reached from the root
+    Unreachable { reachable: usize, stored: usize },
+    // red-black: the root is red
+    RedRoot { key: T },
+    // red-black: a red node has a red child
+    RedRed { key: T, parent: T },
+    // red-black: the paths below a node pass through a different number of black nodes
+    BlackHeight { key: T, left: usize, right: usize },
+    // red-black: a nil sentinel from deleting was left in the tree
+    LeftoverNil { parent: Option<T> },
+    // left-leaning red-black: a red node is a right child
+    RightRed { key: T, parent: T },
+    // avl: the height stored in a node is not the height of its subtree
+    WrongHeight { key: T, stored: usize, actual: usize },
+    // avl: the heights of a node's subtrees differ by more than one
+    Unbalanced { key: T, balance: isize },
+    // treap: a node has a higher priority than its parent
+    HeapOrder { key: T, parent: T },
+    // aa: a child's level is neither one below its parent's nor, for a right child, the same
+    BadLevel { key: T, level: usize, parent: T, parent_level: usize },
+    // aa: a node without two children is above level 1
+    MissingChild { key: T, level: usize },
+    // aa: a right grandchild is on the same level as its grandparent
+    HorizontalRun { key: T, grandparent: T },
+    // scapegoat: a node is deeper than the tree's size since its last rebuild allows
+    TooDeep { key: T, depth: usize, max: usize },
+    // weight-balanced: one side of a node weighs less than alpha of the whole, sizes counted in keys
+    WeightBalance { key: T, left: usize, right: usize },
+    // b-tree: a node holds more keys than the order allows, named by its first key
+    Overfull { key: T, keys: usize, max: usize },
+    // b-tree: a node other than the root holds fewer keys than half the order
+    Underfull { key: Option<T>, keys: usize, min: usize },
+    // b-tree: an inner node doesn't have one more child than it has keys
+    ChildCount { key: T, keys: usize, children: usize },
+    // b-tree: the leaves are not all on the same level
+    LeafDepth { key: T, depth: usize, expected: usize },
+    // b+ tree: following the links between leaves doesn't visit them in key order
+    LeafLink { key: T },
+}
+
+impl<T: Display> Display for ValidationError<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationError::RootHasParent { key } =>
+                write!(f, "root {key} has a parent"),
+            ValidationError::OutOfOrder { key, ancestor, side } =>
+                write!(f, "{key} is in the {side:?} subtree of {ancestor}"),
+            ValidationError::BadParent { key, parent } =>
+                write!(f, "{key} is a child of {parent} but does not point back at it"),
+            ValidationError::BadIsChild { key, side } =>
+                write!(f, "{key} is a {side:?} child but does not know it"),
+            ValidationError::WrongSize { key, stored, actual } =>
+                write!(f, "{key} stores size {stored} but its subtree has {actual} keys"),
+            ValidationError::Unreachable { reachable, stored } =>
+                write!(f, "only {reachable} of {stored} stored nodes are reachable from the root"),
+            ValidationError::RedRoot { key } =>
+                write!(f, "root {key} is red"),
+            ValidationError::RedRed { key, parent } =>
+                write!(f, "red node {key} has a red parent {parent}"),
+            ValidationError::BlackHeight { key, left, right } =>
+                write!(f, "{key} has black height {left} on the left but {right} on the right"),
+            ValidationError::LeftoverNil { parent: Some(parent) } =>
+                write!(f, "a nil node was left below {parent}"),
+            ValidationError::LeftoverNil { parent: None } =>
+                write!(f, "a nil node was left in the tree"),
+            ValidationError::RightRed { key, parent } =>
+                write!(f, "red node {key} is the right child of {parent}"),
+            ValidationError::WrongHeight { key, stored, actual } =>
+                write!(f, "{key} stores height {stored} but its subtree has height {actual}"),
+            ValidationError::Unbalanced { key, balance } =>
+                write!(f, "{key} has balance factor {balance}"),
+            ValidationError::HeapOrder { key, parent } =>
+                write!(f, "{key} has a higher priority than its parent {parent}"),
+            ValidationError::BadLevel { key, level, parent, parent_level } =>
+                write!(f, "{key} on level {level} can't be a child of {parent} on level {parent_level}"),
+            ValidationError::MissingChild { key, level } =>
+                write!(f, "{key} is missing a child but is on level {level}, not 1"),
+            ValidationError::HorizontalRun { key, grandparent } =>
+                write!(f, "{key} is on the same level as its grandparent {grandparent}"),
+            ValidationError::TooDeep { key, depth, max } =>
+                write!(f, "{key} is at depth {depth}, the tree allows at most {max}"),
+            ValidationError::WeightBalance { key, left, right } =>
+                write!(f, "{key} has {left} keys on the left and {right} on the right, too far out of balance"),
+            ValidationError::Overfull { key, keys, max } =>
+                write!(f, "the node starting at {key} holds {keys} keys, at most {max} fit"),
+            ValidationError::Underfull { key: Some(key), keys, min } =>
+                write!(f, "the node starting at {key} holds {keys} keys, it needs at least {min}"),
+            ValidationError::Underfull { key: None, keys, min } =>
+                write!(f, "a node holds {keys} keys, it needs at least {min}"),
+            ValidationError::ChildCount { key, keys, children } =>
+                write!(f, "the node starting at {key} holds {keys} keys but has {children} children"),
+            ValidationError::LeafDepth { key, depth, expected } =>
+                write!(f, "the leaf starting at {key} is at depth {depth}, other leaves are at {expected}"),
+            ValidationError::LeafLink { key } =>
+                write!(f, "the leaf starting at {key} is not linked in key order"),
+        }
+    }
+}
+
+impl<T: Debug + Display> std::error::Error for ValidationError<T> {}
@@ -0,0 +1,212 @@
+use std::f64::consts::FRAC_1_SQRT_2;
+use crate::tree::*;
+use crate::node::*;
+use crate::arena::Arena;
+use crate::cli::CLIPrintable;
+use crate::comparator::{Comparator, NaturalOrder};
+use crate::validate::ValidationError;
+
+type MaybeWeightBalancedTree = Option<NodeId>;
+
+// a weight-balanced node balances by the subtree size every node keeps anyway
+pub type WeightBalancedTreeNode<T, V = ()> = LinkedNode<T, V>;
+
+// balance factor of the trees built by `WeightBalancedTree::new`
+pub const DEFAULT_ALPHA: f64 = 0.25;
+
+// whether `alpha` can be the balance factor of a weight-balanced tree, above 2/11 and at most
+// 1 - 1/sqrt(2) is what a single or double rotation is always enough to restore
+pub fn is_valid_alpha(alpha: f64) -> bool {
+    alpha > 2.0 / 11.0 && alpha <= 1.0 - FRAC_1_SQRT_2
+}
+
+// Weight-balanced tree, BB[alpha] (Nievergelt and Reingold). The weight of a subtree is its size
+// plus one, and each side of every node has to weigh at least alpha of the whole. After an
+// insert or a delete every node on the way back up that is out of balance gets a single or a
+// double rotation, whichever leaves both nodes it moves in balance. A larger alpha keeps the
+// tree closer to perfectly balanced at the cost of more rotations.
+pub struct WeightBalancedTree<T, V = (), C = NaturalOrder> {
+    root: MaybeWeightBalancedTree,
+    nodes: Arena<WeightBalancedTreeNode<T, V>>,
+    compare: C,
+    alpha: f64,
+}
+
+impl<T, V, C: Comparator<T>> Tree<T, V> for WeightBalancedTree<T, V, C> {
+    type Node = WeightBalancedTreeNode<T, V>;
+    type Compare = C;
+
+    fn with_comparator(compare: C) -> Self {
+        Self::with_alpha_and_comparator(DEFAULT_ALPHA, compare)
+    }
+
+    fn comparator(&self) -> &C {
+        &self.compare
+    }
+
+    fn get_root(&self) -> MaybeWeightBalancedTree {
+        self.root
+    }
+
+    fn set_root(&mut self, node: MaybeWeightBalancedTree) {
+        self.root = node;
+    }
+
+    fn nodes(&self) -> &Arena<WeightBalancedTreeNode<T, V>> {
+        &self.nodes
+    }
+
+    fn nodes_mut(&mut self) -> &mut Arena<WeightBalancedTreeNode<T, V>> {
+        &mut self.nodes
+    }
+
+    fn empty_like(&mut self) -> Self where C: Clone {
+        Self::with_alpha_and_comparator(self.alpha, self.compare.clone())
+    }
+
+    fn insert_fix(&mut self, node: NodeId) -> NodeId {
+        self.rebalance_above(node)
+    }
+
+    fn build_fix(&mut self, _node: NodeId, _depth: usize, _levels: usize) {
+        // the two sides of a perfectly balanced node differ by at most one key, that is always in balance
+    }
+
+    fn join_nodes(&mut self, left: MaybeWeightBalancedTree, node: NodeId, right: MaybeWeightBalancedTree) -> NodeId {
+        let (left_weight, right_weight) = (self.weight(left), self.weight(right));
+        if self.balanced(left_weight, right_weight) {
+            self.set_children(node, left, right);
+            self.update_size(node);
+            return node;
+        }
+        let (side, heavy, light, light_weight) = if left_weight > right_weight {
+            (Side::Right, left.unwrap(), right, right_weight)
+        } else {
+            (Side::Left, right.unwrap(), left, left_weight)
+        };
+
+        // walk down the inner edge of the heavier side until a subtree is light enough to be in
+        // balance with the lighter side, the node takes its place over both of them
+        let mut parent = heavy;
+        let mut child = self.get_child(parent, side);
+        while !self.balanced(self.weight(child), light_weight) {
+            parent = child.unwrap();
+            child = self.get_child(parent, side);
+        }
+
+        match side {
+            Side::Right => self.set_children(node, child, light),
+            _ => self.set_children(node, light, child),
+        }
+        self.update_size(node);
+        self.set_child(parent, side, Some(node));
+        self.set_parent(node, Some(side), Some(parent));
+        self.refresh_sizes(Some(parent));
+
+        // every node above got heavier on that side, same as after an insert
+        self.rebalance_above(node)
+    }
+
+    fn remove_node(&mut self, node: NodeId) -> (T, V) {
+        let (removed, parent) = self.bst_unlink(node);
+        if let Some(p) = parent {
+            let root = self.rebalance(p);
+            self.root = Some(self.rebalance_above(root));
+        }
+        self.nodes.remove(removed).into_entry()
+    }
+
+    fn validate<'a>(&'a self) -> Result<(), ValidationError<&'a T>> where Self::Node: 'a {
+        self.validate_bst()?;
+        let mut stack: Vec<NodeId> = self.root.into_iter().collect();
+        while let Some(node) = stack.pop() {
+            let (left, right) = (self.left(node), self.right(node));
+            if !self.balanced(self.weight(left), self.weight(right)) {
+                return Err(ValidationError::WeightBalance { key: self.get_key(node), left: self.size_of(left), right: self.size_of(right) });
+            }
+            stack.extend([left, right].into_iter().flatten());
+        }
+        Ok(())
+    }
+}
+
+impl<T, V, C: Comparator<T>> WeightBalancedTree<T, V, C> {
+    // an empty tree with balance factor `alpha`, above 2/11 and at most 1 - 1/sqrt(2)
+    pub fn with_alpha_and_comparator(alpha: f64, compare: C) -> Self {
+        assert!(is_valid_alpha(alpha), "the alpha of a weight-balanced tree has to be above 2/11 and at most 1 - 1/sqrt(2)");
+        Self { root: None, nodes: Arena::new(), compare, alpha }
+    }
+
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    fn weight(&self, node: MaybeWeightBalancedTree) -> usize {
+        self.size_of(node) + 1
+    }
+
+    // whether two sides of these weights can hang from the same node
+    fn balanced(&self, left: usize, right: usize) -> bool {
+        left.min(right) as f64 >= self.alpha * (left + right) as f64
+    }
+
+    // put a node back in balance with a single or a double rotation, returning the root of its subtree
+    fn rebalance(&mut self, node: NodeId) -> NodeId {
+        let (left, right) = (self.weight(self.left(node)), self.weight(self.right(node)));
+        if self.balanced(left, right) {
+            return node;
+        }
+        // rotate towards the lighter side, the heavier one weighs at least two so it isn't empty
+        let (side, light) = if left > right { (Side::Right, right) } else { (Side::Left, left) };
+        let Some(heavy) = self.get_child(node, !side) else {
+            return node;
+        };
+        let (inner, outer) = (self.get_child(heavy, side), self.get_child(heavy, !side));
+
+        // a single rotation hangs the inner grandchild under the node, unless that leaves either
+        // of them out of balance the inner grandchild comes up instead. for any valid alpha one of
+        // the two works, and without an inner grandchild it is always the single rotation
+        let inner_weight = self.weight(inner);
+        match inner {
+            Some(inner) if !(self.balanced(light, inner_weight) && self.balanced(light + inner_weight, self.weight(outer))) => {
+                self.rotate(!side, heavy);
+                self.rotate(side, node);
+                inner
+            }
+            _ => {
+                self.rotate(side, node);
+                heavy
+            }
+        }
+    }
+
+    // rebalance every node above one whose subtree changed, returning the root
+    fn rebalance_above(&mut self, node: NodeId) -> NodeId {
+        let mut top = node;
+        while let Some(parent) = self.get_parent(top) {
+            top = self.rebalance(parent);
+        }
+        top
+    }
+}
+
+impl<T, V, C> CLIPrintable for WeightBalancedTree<T, V, C> {
+    fn pretty_name() -> &'static str {
+        "Weight-Balanced Tree"
+    }
+}
+
+tree_impls!(WeightBalancedTree, WeightBalancedTreeNode);
+
+impl<T> WeightBalancedTree<T> {
+    // an empty tree of bare keys with balance factor `alpha`, above 2/11 and at most 1 - 1/sqrt(2)
+    pub fn with_alpha(alpha: f64) -> Self where T: Ord {
+        Self::with_alpha_and_comparator(alpha, NaturalOrder)
+    }
+}
+
+impl<T, V, C: Default> Default for WeightBalancedTree<T, V, C> {
+    fn default() -> Self {
+        Self { root: None, nodes: Arena::new(), compare: C::default(), alpha: DEFAULT_ALPHA }
+    }
+}
@@ -0,0 +1,155 @@
+// The scapegoat tree and the weight-balanced tree are both tuned by an alpha chosen when the tree
+// is made, so they are checked across a range of them.
+use lib::comparator::NaturalOrder;
+use lib::scapegoat_tree::ScapegoatTree;
+use lib::tree::Tree;
+use lib::weight_balanced_tree::WeightBalancedTree;
+
+const SCAPEGOAT_ALPHAS: [f64; 4] = [0.55, 0.6, 0.75, 0.9];
+const WEIGHT_BALANCED_ALPHAS: [f64; 4] = [0.19, 0.22, 0.25, 0.29];
+
+// every node of a tree that is balanced by alpha has at most 1 - alpha of its parent's weight
+fn height_bound(alpha: f64, n: usize) -> usize {
+    (((n + 1) as f64).ln() / (1.0 / (1.0 - alpha)).ln()) as usize + 1
+}
+
+fn churn<B: Tree<i32, Compare = NaturalOrder>>(mut tree: B) {
+    let keys: Vec<i32> = (0..2000).map(|i| (i * 7919) % 2000).collect();
+    for (i, &k) in keys.iter().enumerate() {
+        tree.insert(k);
+        if i % 100 == 0 {
+            tree.validate().unwrap();
+        }
+    }
+    tree.validate().unwrap();
+    for (i, &k) in keys.iter().filter(|&&k| k % 4 != 0).enumerate() {
+        assert_eq!(tree.delete(&k), Some(k));
+        if i % 100 == 0 {
+            tree.validate().unwrap();
+        }
+    }
+    tree.validate().unwrap();
+    assert!(tree.iter().copied().eq((0..2000).step_by(4)));
+}
+
+#[test]
+fn scapegoat_inserts_and_deletes_for_every_alpha() {
+    for alpha in SCAPEGOAT_ALPHAS {
+        churn(ScapegoatTree::with_alpha(alpha));
+    }
+}
+
+#[test]
+fn weight_balanced_inserts_and_deletes_for_every_alpha() {
+    for alpha in WEIGHT_BALANCED_ALPHAS {
+        churn(WeightBalancedTree::with_alpha(alpha));
+    }
+}
+
+#[test]
+fn scapegoat_ascending_keys() {
+    let n = 100_000;
+    for alpha in SCAPEGOAT_ALPHAS {
+        let mut tree = ScapegoatTree::with_alpha(alpha);
+        for k in 0..n {
+            tree.insert(k);
+        }
+        tree.validate().unwrap();
+        // no node is deeper than log base 1/alpha of the size
+        assert!(tree.get_height() <= ((n as f64).ln() / (1.0 / alpha).ln()) as usize + 1);
+    }
+}
+
+#[test]
+fn weight_balanced_ascending_keys() {
+    let n = 100_000;
+    for alpha in WEIGHT_BALANCED_ALPHAS {
+        let mut tree = WeightBalancedTree::with_alpha(alpha);
+        for k in 0..n {
+            tree.insert(k);
+        }
+        tree.validate().unwrap();
+        assert!(tree.get_height() <= height_bound(alpha, n as usize));
+    }
+}
+
+#[test]
+fn weight_balanced_alphas_at_the_ends_of_the_range() {
+    // the rotations are only proven to restore balance for alpha above 2/11 and up to 1 - 1/sqrt(2)
+    let n = 20_000;
+    for alpha in [2.0 / 11.0 + 1e-9, 1.0 - std::f64::consts::FRAC_1_SQRT_2] {
+        churn(WeightBalancedTree::with_alpha(alpha));
+        for keys in [(0..n).collect::<Vec<_>>(), (0..n).rev().collect()] {
+            let mut tree = WeightBalancedTree::with_alpha(alpha);
+            for &k in &keys {
+                tree.insert(k);
+            }
+            tree.validate().unwrap();
+            assert!(tree.get_height() <= height_bound(alpha, n as usize));
+            for &k in keys.iter().step_by(3) {
+                assert_eq!(tree.delete(&k), Some(k));
+            }
+            tree.validate().unwrap();
+        }
+    }
+}
+
+#[test]
+fn scapegoat_split_and_merge_keep_alpha() {
+    let mut tree = ScapegoatTree::with_alpha(0.6);
+    tree.extend((0..1000).map(|i| (i * 7919) % 1000));
+    let (less, found, greater) = tree.split(&400);
+    assert_eq!(found, Some((400, ())));
+    assert_eq!((less.alpha(), greater.alpha()), (0.6, 0.6));
+    less.validate().unwrap();
+    greater.validate().unwrap();
+    assert!(less.iter().copied().eq(0..400));
+    assert!(greater.iter().copied().eq(401..1000));
+
+    let mut merged = ScapegoatTree::merge(less, greater);
+    assert_eq!(merged.alpha(), 0.6);
+    merged.validate().unwrap();
+    assert!(merged.iter().copied().eq((0..1000).filter(|&k| k != 400)));
+    merged.insert(400);
+    merged.validate().unwrap();
+}
+
+#[test]
+fn weight_balanced_split_and_merge_keep_alpha() {
+    let mut tree = WeightBalancedTree::with_alpha(0.2);
+    tree.extend((0..1000).map(|i| (i * 7919) % 1000));
+    let (less, found, greater) = tree.split(&400);
+    assert_eq!(found, Some((400, ())));
+    assert_eq!((less.alpha(), greater.alpha()), (0.2, 0.2));
+    less.validate().unwrap();
+    greater.validate().unwrap();
+    assert!(less.iter().copied().eq(0..400));
+    assert!(greater.iter().copied().eq(401..1000));
+
+    // very uneven joins have to walk down the bigger tree
+    let (small, _, _) = greater.split(&405);
+    let merged = WeightBalancedTree::merge(less, small);
+    assert_eq!(merged.alpha(), 0.2);
+    merged.validate().unwrap();
+    assert!(merged.iter().copied().eq((0..405).filter(|&k| k != 400)));
+}
+
+#[test]
+fn sorted_builds_are_valid() {
+    for n in 0..300 {
+        ScapegoatTree::from_sorted_iter(0..n).validate().unwrap();
+        WeightBalancedTree::from_sorted_iter(0..n).validate().unwrap();
+    }
+}
+
+#[test]
+#[should_panic(expected = "the alpha of a scapegoat tree has to be above 0.5 and less than 1")]
+fn scapegoat_rejects_alpha_of_one_half() {
+    ScapegoatTree::<i32>::with_alpha(0.5);
+}
+
+#[test]
+#[should_panic(expected = "the alpha of a weight-balanced tree has to be above 2/11 and at most 1 - 1/sqrt(2)")]
+fn weight_balanced_rejects_alpha_above_the_limit() {
+    WeightBalancedTree::<i32>::with_alpha(0.3);
+}
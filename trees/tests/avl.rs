@@ -0,0 +1,114 @@
+// AVL deletion, case by case: every removal has to leave a valid tree of the expected shape.
+use lib::avl_tree::AvlTree;
+use lib::tree::Tree;
+
+// the tree written out as nested `(key left right)`, with `.` for a missing child
+fn shape(tree: &AvlTree<i32>) -> String {
+    fn below(tree: &AvlTree<i32>, node: Option<usize>) -> String {
+        match node {
+            None => ".".to_string(),
+            Some(n) => match (tree.left(n), tree.right(n)) {
+                (None, None) => tree.get_key(n).to_string(),
+                (left, right) => format!("({} {} {})", tree.get_key(n), below(tree, left), below(tree, right)),
+            },
+        }
+    }
+    below(tree, tree.get_root())
+}
+
+fn built(keys: &[i32]) -> AvlTree<i32> {
+    let mut tree = AvlTree::new();
+    for &k in keys {
+        tree.insert(k);
+    }
+    tree.validate().unwrap();
+    tree
+}
+
+// delete a key that is in the tree, check the result and hand back its shape
+fn removed(keys: &[i32], key: i32) -> String {
+    let mut tree = built(keys);
+    assert_eq!(tree.delete(&key), Some(key));
+    tree.validate().unwrap();
+    assert!(tree.iter().copied().eq(keys.iter().copied().filter(|&k| k != key).collect::<std::collections::BTreeSet<_>>()));
+    shape(&tree)
+}
+
+#[test]
+fn delete_a_leaf() {
+    assert_eq!(removed(&[2, 1, 3], 1), "(2 . 3)");
+    assert_eq!(removed(&[2, 1, 3], 3), "(2 1 .)");
+}
+
+#[test]
+fn delete_a_node_with_one_child() {
+    // the child takes the node's place
+    assert_eq!(removed(&[2, 1, 3, 4], 3), "(2 1 4)");
+    assert_eq!(removed(&[3, 2, 4, 1], 2), "(3 1 4)");
+}
+
+#[test]
+fn delete_a_node_with_two_children() {
+    // the predecessor's entry moves up into the node
+    assert_eq!(removed(&[2, 1, 4, 3, 5], 4), "(2 1 (3 . 5))");
+    assert_eq!(removed(&[2, 1, 3], 2), "(1 . 3)");
+}
+
+#[test]
+fn delete_the_last_keys() {
+    assert_eq!(removed(&[1, 2], 1), "2");
+    assert_eq!(removed(&[1], 1), ".");
+
+    let mut tree = built(&[1, 2, 3]);
+    assert_eq!(tree.delete(&7), None);
+    tree.validate().unwrap();
+    assert_eq!(shape(&tree), "(2 1 3)");
+}
+
+#[test]
+fn delete_rebalances_with_single_rotations() {
+    // right-right: the right child leans the same way, one left rotation
+    assert_eq!(removed(&[2, 1, 3, 4], 1), "(3 2 4)");
+    // left-left
+    assert_eq!(removed(&[3, 2, 4, 1], 4), "(2 1 3)");
+    // a balanced child also takes a single rotation, unlike after an insert
+    assert_eq!(removed(&[2, 1, 4, 3, 5], 1), "(4 (2 . 3) 5)");
+    assert_eq!(removed(&[4, 2, 5, 1, 3], 5), "(2 1 (4 3 .))");
+}
+
+#[test]
+fn delete_rebalances_with_double_rotations() {
+    // right-left: the right child leans left, so it is rotated first
+    assert_eq!(removed(&[2, 1, 4, 3], 1), "(3 2 4)");
+    // left-right
+    assert_eq!(removed(&[3, 1, 4, 2], 4), "(2 1 3)");
+}
+
+#[test]
+fn delete_rebalances_all_the_way_up() {
+    // a minimal AVL tree of height 5, taking out its shallowest leaf unbalances two levels in turn
+    let keys = [8, 5, 11, 3, 7, 10, 12, 2, 4, 6, 1, 9];
+    let mut tree = built(&keys);
+    assert_eq!(tree.get_height(), 5);
+    assert_eq!(tree.delete(&9), Some(9));
+    tree.validate().unwrap();
+    assert_eq!(tree.get_height(), 4);
+    assert_eq!(shape(&tree), "(5 (3 (2 1 .) 4) (8 (7 6 .) (11 10 12)))");
+}
+
+#[test]
+fn delete_everything_in_many_orders() {
+    let n = 500;
+    for step in [1, 7, 13, 101, 499] {
+        let mut tree = built(&(0..n).collect::<Vec<_>>());
+        let mut left: std::collections::BTreeSet<i32> = (0..n).collect();
+        for i in 0..n {
+            let k = (i * step) % n;
+            assert_eq!(tree.delete(&k), Some(k));
+            left.remove(&k);
+            tree.validate().unwrap();
+            assert!(tree.iter().eq(left.iter()));
+        }
+        assert!(tree.is_empty());
+    }
+}
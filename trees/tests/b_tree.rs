@@ -0,0 +1,203 @@
+// The B-tree and the B+ tree are driven through random inserts and deletes next to a `BTreeSet`,
+// with small orders so that nodes split and merge all the time.
+use std::collections::BTreeSet;
+use std::ops::Bound::{self, Excluded, Included, Unbounded};
+use quickcheck::{Gen, QuickCheck};
+use lib::b_plus_tree::BPlusTree;
+use lib::b_tree::BTree;
+
+// the same operations on both kinds of tree
+trait Multiway {
+    fn with_order(order: usize) -> Self;
+    fn insert(&mut self, key: i8) -> bool;
+    fn delete(&mut self, key: &i8) -> Option<i8>;
+    fn contains(&self, key: &i8) -> bool;
+    fn keys(&self) -> Vec<i8>;
+    fn len(&self) -> usize;
+    fn validate(&self) -> Result<(), String>;
+}
+
+macro_rules! multiway {
+    ($tree:ident) => {
+        impl Multiway for $tree<i8> {
+            fn with_order(order: usize) -> Self {
+                $tree::with_order(order)
+            }
+            fn insert(&mut self, key: i8) -> bool {
+                self.insert(key)
+            }
+            fn delete(&mut self, key: &i8) -> Option<i8> {
+                self.delete(key)
+            }
+            fn contains(&self, key: &i8) -> bool {
+                self.contains(key)
+            }
+            fn keys(&self) -> Vec<i8> {
+                self.iter().copied().collect()
+            }
+            fn len(&self) -> usize {
+                self.len()
+            }
+            fn validate(&self) -> Result<(), String> {
+                self.validate().map_err(|e| e.to_string())
+            }
+        }
+    };
+}
+
+multiway!(BTree);
+multiway!(BPlusTree);
+
+// insert (true) or delete (false) a key
+type Op = (bool, i8);
+
+fn run<B: Multiway>(order: u8, ops: Vec<Op>) -> Result<(), String> {
+    let order = 3 + order as usize % 6;
+    let mut tree = B::with_order(order);
+    let mut set = BTreeSet::new();
+    for (step, (insert, key)) in ops.into_iter().enumerate() {
+        if insert {
+            if tree.insert(key) != set.insert(key) {
+                return Err(format!("step {step}: insert {key} disagrees"));
+            }
+        } else if tree.delete(&key) != set.take(&key) {
+            return Err(format!("step {step}: delete {key} disagrees"));
+        }
+        if let Err(e) = tree.validate() {
+            return Err(format!("step {step}: invalid tree of order {order}: {e}"));
+        }
+        if tree.len() != set.len() || !tree.keys().iter().eq(set.iter()) {
+            return Err(format!("step {step}: keys disagree"));
+        }
+        if tree.contains(&key) != set.contains(&key) {
+            return Err(format!("step {step}: contains {key} disagrees"));
+        }
+    }
+    Ok(())
+}
+
+fn check(prop: fn(u8, Vec<Op>) -> Result<(), String>) {
+    QuickCheck::new()
+        .tests(300)
+        .rng(Gen::new(400))
+        .quickcheck(prop);
+}
+
+#[test]
+fn b_tree_matches_btreeset() {
+    check(run::<BTree<i8>>);
+}
+
+#[test]
+fn b_plus_tree_matches_btreeset() {
+    check(run::<BPlusTree<i8>>);
+}
+
+fn bound(kind: u8, key: i8) -> Bound<i8> {
+    match kind % 3 {
+        0 => Included(key),
+        1 => Excluded(key),
+        _ => Unbounded,
+    }
+}
+
+#[test]
+fn b_plus_tree_ranges() {
+    fn ranges(order: u8, keys: Vec<i8>, bounds: Vec<(u8, i8, u8, i8)>) -> Result<(), String> {
+        let mut tree = BPlusTree::with_order(3 + order as usize % 6);
+        tree.extend(keys.iter().copied());
+        let set: BTreeSet<i8> = keys.into_iter().collect();
+        for (start_kind, start, end_kind, end) in bounds {
+            let range = (bound(start_kind, start), bound(end_kind, end));
+            // `BTreeSet::range` panics on a range that ends before it starts, the tree gives nothing
+            let backwards = match range {
+                (Included(s), Included(e)) => s > e,
+                (Included(s) | Excluded(s), Included(e) | Excluded(e)) => s >= e,
+                _ => false,
+            };
+            let expected: Vec<i8> = if backwards { Vec::new() } else { set.range(range).copied().collect() };
+            let got: Vec<i8> = tree.range(range).copied().collect();
+            if got != expected {
+                return Err(format!("{range:?}: got {got:?}, expected {expected:?}"));
+            }
+        }
+        Ok(())
+    }
+    QuickCheck::new()
+        .tests(300)
+        .rng(Gen::new(200))
+        .quickcheck(ranges as fn(u8, Vec<i8>, Vec<(u8, i8, u8, i8)>) -> Result<(), String>);
+}
+
+#[test]
+fn many_keys_stay_shallow() {
+    let n = 100_000;
+    let mut tree = BTree::new();
+    let mut plus = BPlusTree::new();
+    for k in 0..n {
+        tree.insert(k);
+        plus.insert((k * 7919) % n);
+    }
+    tree.validate().unwrap();
+    plus.validate().unwrap();
+    // nodes at least half full with an order of 16 means at most log_8 of the keys in levels
+    assert!(tree.get_height() <= 6);
+    assert!(plus.get_height() <= 6);
+    assert!(tree.iter().copied().eq(0..n));
+    assert!(plus.iter().copied().eq(0..n));
+    assert_eq!(plus.range(500..510).count(), 10);
+
+    for k in (0..n).filter(|k| k % 3 != 0) {
+        assert_eq!(tree.delete(&k), Some(k));
+        assert_eq!(plus.delete(&k), Some(k));
+    }
+    tree.validate().unwrap();
+    plus.validate().unwrap();
+    assert!(tree.iter().copied().eq((0..n).step_by(3)));
+    assert!(plus.iter().copied().eq((0..n).step_by(3)));
+}
+
+#[test]
+fn drawings() {
+    let mut tree = BTree::with_order(3);
+    tree.extend(1..=7);
+    let expected = [
+        "       [4]",
+        "     [2]  [6]",
+        "[1]  [3]  [5]  [7]",
+    ];
+    assert_eq!(tree.format_top_down(), expected.join("\n"));
+    assert_eq!((tree.get_height(), tree.count_leaves()), (3, 4));
+
+    let mut plus = BPlusTree::with_order(4);
+    plus.extend(1..=6);
+    let expected = [
+        "       [3 5]",
+        "[1 2]  [3 4]  [5 6]",
+    ];
+    assert_eq!(plus.format_top_down(), expected.join("\n"));
+
+    // one record per node, an edge per child and a dashed link between neighbouring leaves
+    let dot = plus.to_dot();
+    assert_eq!(dot.lines().filter(|l| l.contains("[label=")).count(), 4);
+    assert_eq!(dot.lines().filter(|l| l.contains(":c")).count(), 3);
+    assert_eq!(dot.lines().filter(|l| l.contains("style=dashed")).count(), 2);
+    assert!(dot.contains("[label=\"<c0>|3|<c1>|5|<c2>\"];"));
+    assert!(BTree::<i32>::new().format_top_down().is_empty());
+}
+
+#[test]
+fn borrowed_lookups() {
+    let mut tree = BPlusTree::with_order(5);
+    tree.extend(["pear", "apple", "fig", "kiwi", "plum", "lime"].map(String::from));
+    assert!(tree.contains("fig"));
+    assert_eq!(tree.range::<str, _>((Included("fig"), Excluded("lime"))).collect::<Vec<_>>(), ["fig", "kiwi"]);
+    assert_eq!(tree.delete("kiwi").as_deref(), Some("kiwi"));
+    assert_eq!(tree.first().map(String::as_str), Some("apple"));
+    assert_eq!(tree.last().map(String::as_str), Some("plum"));
+
+    let mut tree = BTree::with_order(4);
+    tree.extend(["b", "a", "c"].map(String::from));
+    assert_eq!(tree.get("b").map(String::as_str), Some("b"));
+    assert_eq!(tree.delete("z"), None);
+}
@@ -0,0 +1,114 @@
+// Trees built with a comparator have to order, find and delete keys by it alone.
+use std::cmp::{Ordering, Reverse};
+use std::collections::BTreeSet;
+use quickcheck::{Gen, QuickCheck};
+use lib::avl_tree::AvlTree;
+use lib::rb_tree::RedBlackTree;
+use lib::tree::Tree;
+use lib::tree_map::TreeMap;
+
+type ByKey = fn(&i16, &i16) -> Ordering;
+type Boxed = Box<dyn Fn(&i32, &i32) -> Ordering>;
+
+fn largest_first(a: &i16, b: &i16) -> Ordering {
+    b.cmp(a)
+}
+
+// whether to insert or delete, and the key
+type Update = (bool, i16);
+
+// a tree sorted largest first, next to a set of `Reverse` keys that sorts the same way
+fn reversed<B: Tree<i16, Compare = ByKey>>(ops: Vec<Update>) -> Result<(), String> {
+    let mut tree = B::with_comparator(largest_first);
+    let mut set = BTreeSet::new();
+
+    for (step, (insert, key)) in ops.into_iter().enumerate() {
+        if insert {
+            tree.insert(key);
+            set.insert(Reverse(key));
+        } else {
+            tree.delete(&key);
+            set.remove(&Reverse(key));
+        }
+        if let Err(e) = tree.validate() {
+            return Err(format!("step {step}: invalid tree: {e}"));
+        }
+        if !tree.iter().copied().eq(set.iter().map(|r| r.0)) {
+            let keys: Vec<&i16> = tree.iter().collect();
+            return Err(format!("step {step}: tree has {keys:?}, expected {set:?}"));
+        }
+        if tree.bst_search(&key) != set.contains(&Reverse(key)) {
+            return Err(format!("step {step}: search for {key} disagrees"));
+        }
+    }
+
+    // the halves of a split keep ordering their keys the same way
+    let (larger, _, smaller) = tree.split(&0);
+    if larger.iter().any(|&k| k <= 0) || smaller.iter().any(|&k| k >= 0) {
+        return Err("split put keys on the wrong side".to_string());
+    }
+    Ok(())
+}
+
+fn check(prop: fn(Vec<Update>) -> Result<(), String>) {
+    QuickCheck::new()
+        .tests(200)
+        .rng(Gen::new(300))
+        .quickcheck(prop);
+}
+
+#[test]
+fn rb_tree_reversed() {
+    check(reversed::<RedBlackTree<i16, (), ByKey>>);
+}
+
+#[test]
+fn avl_tree_reversed() {
+    check(reversed::<AvlTree<i16, (), ByKey>>);
+}
+
+#[test]
+fn case_insensitive_chars() {
+    let mut tree = AvlTree::with_comparator(|a: &char, b: &char| a.to_ascii_lowercase().cmp(&b.to_ascii_lowercase()));
+    for c in ['b', 'A', 'c', 'B', 'a'] {
+        tree.insert(c);
+    }
+    // the first spelling of each letter stays, later ones count as the same key
+    assert_eq!(tree.iter().collect::<String>(), "Abc");
+    assert!(tree.bst_search(&'C'));
+    tree.delete(&'a');
+    assert_eq!(tree.iter().collect::<String>(), "bc");
+    assert!(tree.validate().is_ok());
+}
+
+// keys that aren't `Ord` at all, ordered by one of their fields
+#[derive(Debug)]
+struct Task {
+    priority: u32,
+    name: &'static str,
+}
+
+#[test]
+fn map_ordered_by_field() {
+    let mut map = TreeMap::<Task, bool, RedBlackTree<Task, bool, _>>::with_comparator(|a: &Task, b: &Task| a.priority.cmp(&b.priority));
+    map.insert(Task { priority: 3, name: "deploy" }, false);
+    map.insert(Task { priority: 1, name: "build" }, true);
+    map.insert(Task { priority: 2, name: "test" }, false);
+
+    let names: Vec<&str> = map.tree().iter().map(|t| t.name).collect();
+    assert_eq!(names, ["build", "test", "deploy"]);
+    // any task with the same priority finds the entry
+    assert_eq!(map.get(&Task { priority: 2, name: "" }), Some(&false));
+    assert_eq!(map.remove_entry(&Task { priority: 1, name: "" }).map(|(t, _)| t.name), Some("build"));
+    assert_eq!(map.len(), 2);
+}
+
+#[test]
+fn boxed_comparator() {
+    let compare: Boxed = Box::new(|a, b| (a % 10).cmp(&(b % 10)).then(a.cmp(b)));
+    let mut tree = RedBlackTree::with_comparator(compare);
+    tree.insert_all([25, 13, 31, 40, 12]);
+    assert_eq!(tree.iter().copied().collect::<Vec<_>>(), [40, 31, 12, 13, 25]);
+    assert_eq!(tree.rank(&13), 3);
+    assert!(tree.validate().is_ok());
+}
@@ -0,0 +1,106 @@
+// Several threads update one shared tree, the end result is checked against the same updates applied in order.
+use std::collections::BTreeSet;
+use std::thread;
+use lib::avl_tree::AvlTree;
+use lib::comparator::NaturalOrder;
+use lib::concurrent::ConcurrentTree;
+use lib::rb_tree::RedBlackTree;
+use lib::tree::Tree;
+
+const THREADS: u32 = 8;
+const KEYS_PER_THREAD: u32 = 2000;
+
+fn assert_send_sync<S: Send + Sync>() {}
+
+// the updates a thread makes: insert its keys, then delete every third one.
+// each thread gets its own keys, so the result doesn't depend on how the threads interleave
+fn updates(thread: u32) -> impl Iterator<Item = (bool, u32)> {
+    let keys = (0..KEYS_PER_THREAD).map(move |i| i * THREADS + thread);
+    let inserts = keys.clone().map(|k| (true, k));
+    let deletes = keys.filter(|k| k % 3 == 0).map(|k| (false, k));
+    inserts.chain(deletes)
+}
+
+fn hammer<B>()
+where
+    B: Tree<u32, Compare = NaturalOrder> + Send + Sync,
+{
+    let tree = ConcurrentTree::<u32, (), B>::new();
+
+    thread::scope(|s| {
+        for t in 0..THREADS {
+            let tree = &tree;
+            s.spawn(move || {
+                for (insert, key) in updates(t) {
+                    if insert {
+                        assert!(tree.insert(key), "{key} was already there");
+                    } else {
+                        assert_eq!(tree.delete(&key), Some(key));
+                    }
+                }
+            });
+        }
+        // readers running alongside the writers always see a valid tree
+        for _ in 0..2 {
+            let tree = &tree;
+            s.spawn(move || {
+                for _ in 0..200 {
+                    let guard = tree.read();
+                    guard.validate().unwrap();
+                    assert_eq!(guard.iter().count(), guard.len());
+                }
+            });
+        }
+    });
+
+    let mut model = BTreeSet::new();
+    for t in 0..THREADS {
+        for (insert, key) in updates(t) {
+            if insert {
+                model.insert(key);
+            } else {
+                model.remove(&key);
+            }
+        }
+    }
+
+    let tree = tree.into_inner();
+    tree.validate().unwrap();
+    assert!(tree.iter().eq(model.iter()));
+}
+
+// every thread inserts the same keys, exactly one insert of each key may succeed
+fn contended<B>()
+where
+    B: Tree<u32, Compare = NaturalOrder> + Send + Sync,
+{
+    let tree = ConcurrentTree::<u32, (), B>::new();
+    let inserted: u32 = thread::scope(|s| {
+        let handles: Vec<_> = (0..THREADS)
+            .map(|_| s.spawn(|| (0..KEYS_PER_THREAD).filter(|&k| tree.insert(k)).count() as u32))
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).sum()
+    });
+    assert_eq!(inserted, KEYS_PER_THREAD);
+    assert_eq!(tree.len(), KEYS_PER_THREAD as usize);
+    tree.read().validate().unwrap();
+}
+
+#[test]
+fn trees_are_send_and_sync() {
+    assert_send_sync::<RedBlackTree<String, Vec<u8>>>();
+    assert_send_sync::<AvlTree<String, Vec<u8>>>();
+    assert_send_sync::<ConcurrentTree<String, Vec<u8>>>();
+}
+
+#[test]
+fn red_black_from_many_threads() {
+    hammer::<RedBlackTree<u32>>();
+    contended::<RedBlackTree<u32>>();
+}
+
+#[test]
+fn avl_from_many_threads() {
+    hammer::<AvlTree<u32>>();
+    contended::<AvlTree<u32>>();
+}
@@ -0,0 +1,495 @@
+// Randomized differential tests: the trees are driven through random operation sequences
+// next to a `BTreeSet` and have to agree with it, and stay valid, after every step.
+// quickcheck shrinks a failing sequence down to a minimal one before reporting it.
+use std::collections::BTreeSet;
+use std::fmt::{Debug, Display};
+use std::ops::Bound::{self, Excluded, Included, Unbounded};
+use quickcheck::{Arbitrary, Gen, QuickCheck};
+use lib::avl_tree::AvlTree;
+use lib::comparator::NaturalOrder;
+use lib::rb_tree::RedBlackTree;
+use lib::splay_tree::SplayTree;
+use lib::treap::Treap;
+use lib::llrb_tree::LeftLeaningRedBlackTree;
+use lib::aa_tree::AaTree;
+use lib::scapegoat_tree::ScapegoatTree;
+use lib::weight_balanced_tree::WeightBalancedTree;
+use lib::tree::Tree;
+
+#[derive(Clone, Debug)]
+enum Op<T> {
+    Insert(T),
+    Delete(T),
+    Search(T),
+    Select(usize),
+    Rank(T),
+    // floor, ceiling, predecessor and successor of a key
+    Nearest(T),
+    PopFirst,
+    PopLast,
+    // delete the key at this position (modulo length) in the set, random keys
+    // from a wide type would almost never hit a key that is present
+    DeleteExisting(usize),
+}
+
+impl<T: Arbitrary> Arbitrary for Op<T> {
+    fn arbitrary(g: &mut Gen) -> Self {
+        match u8::arbitrary(g) % 13 {
+            0..=3 => Op::Insert(T::arbitrary(g)),
+            4 => Op::Delete(T::arbitrary(g)),
+            5 => Op::Search(T::arbitrary(g)),
+            6 => Op::Select(usize::arbitrary(g)),
+            7 => Op::Rank(T::arbitrary(g)),
+            8 => Op::Nearest(T::arbitrary(g)),
+            9 => Op::PopFirst,
+            10 => Op::PopLast,
+            _ => Op::DeleteExisting(usize::arbitrary(g)),
+        }
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        match self {
+            Op::Insert(k) => Box::new(k.shrink().map(Op::Insert)),
+            Op::Delete(k) => Box::new(k.shrink().map(Op::Delete)),
+            Op::Search(k) => Box::new(k.shrink().map(Op::Search)),
+            Op::Select(i) => Box::new(i.shrink().map(Op::Select)),
+            Op::Rank(k) => Box::new(k.shrink().map(Op::Rank)),
+            Op::Nearest(k) => Box::new(k.shrink().map(Op::Nearest)),
+            Op::PopFirst | Op::PopLast => quickcheck::empty_shrinker(),
+            Op::DeleteExisting(i) => Box::new(i.shrink().map(Op::DeleteExisting)),
+        }
+    }
+}
+
+// run the operations on a tree and a set, returning a description of the first disagreement
+fn run<T, B>(ops: Vec<Op<T>>) -> Result<(), String>
+where
+    T: Ord + Clone + Debug + Display,
+    B: Tree<T, Compare = NaturalOrder>,
+{
+    let mut tree = B::new();
+    let mut set = BTreeSet::new();
+
+    for (step, op) in ops.iter().enumerate() {
+        match op {
+            Op::Insert(k) => {
+                if tree.insert(k.clone()) != set.insert(k.clone()) {
+                    return Err(format!("step {step} {op:?}: insert reported the wrong outcome"));
+                }
+            }
+            Op::Delete(k) => {
+                if tree.delete(k) != set.take(k) {
+                    return Err(format!("step {step} {op:?}: delete returned the wrong key"));
+                }
+            }
+            Op::Search(k) => {
+                if tree.bst_search(k) != set.contains(k) {
+                    return Err(format!("step {step} {op:?}: search disagrees"));
+                }
+            }
+            Op::Select(i) => {
+                // mostly in range, sometimes just past the end
+                let k = i % (set.len() + 2);
+                if tree.select(k) != set.iter().nth(k) {
+                    return Err(format!("step {step} {op:?}: select({k}) disagrees"));
+                }
+            }
+            Op::Rank(k) => {
+                if tree.rank(k) != set.range(..k).count() {
+                    return Err(format!("step {step} {op:?}: rank disagrees"));
+                }
+            }
+            Op::Nearest(k) => {
+                let expected = (
+                    set.range(..=k).next_back(),
+                    set.range(k..).next(),
+                    set.range(..k).next_back(),
+                    set.range((Excluded(k), Unbounded)).next(),
+                );
+                let found = (tree.floor(k), tree.ceiling(k), tree.predecessor(k), tree.successor(k));
+                if found != expected {
+                    return Err(format!("step {step} {op:?}: floor/ceiling/predecessor/successor {found:?}, expected {expected:?}"));
+                }
+            }
+            Op::PopFirst => {
+                if tree.pop_first().map(|(k, _)| k) != set.pop_first() {
+                    return Err(format!("step {step} {op:?}: popped the wrong key"));
+                }
+            }
+            Op::PopLast => {
+                if tree.pop_last().map(|(k, _)| k) != set.pop_last() {
+                    return Err(format!("step {step} {op:?}: popped the wrong key"));
+                }
+            }
+            Op::DeleteExisting(i) => {
+                if let Some(k) = set.iter().nth(i % set.len().max(1)).cloned() {
+                    if tree.delete(&k).as_ref() != Some(&k) {
+                        return Err(format!("step {step} {op:?}: delete missed {k}"));
+                    }
+                    set.remove(&k);
+                }
+            }
+        }
+
+        if let Err(e) = tree.validate() {
+            return Err(format!("step {step} {op:?}: invalid tree: {e}"));
+        }
+        if !tree.iter().eq(set.iter()) {
+            let keys: Vec<&T> = tree.iter().collect();
+            return Err(format!("step {step} {op:?}: tree has {keys:?}, expected {set:?}"));
+        }
+        if tree.len() != set.len() || tree.nodes().len() != set.len() || tree.is_empty() != set.is_empty() {
+            return Err(format!("step {step} {op:?}: tree has {} keys in {} nodes, expected {}", tree.len(), tree.nodes().len(), set.len()));
+        }
+        let (min, max) = (tree.first(), tree.last());
+        if min != set.first() || max != set.last() {
+            return Err(format!("step {step} {op:?}: min/max {min:?}/{max:?}, expected {:?}/{:?}", set.first(), set.last()));
+        }
+    }
+    Ok(())
+}
+
+// collect keys into a tree, sorted (the linear time build) or as they come, then extend it
+fn collect<T, B>(keys: Vec<T>, more: Vec<T>, sorted: bool) -> Result<(), String>
+where
+    T: Ord + Clone + Debug + Display,
+    B: Tree<T, Compare = NaturalOrder> + FromIterator<T> + Extend<T>,
+{
+    let mut keys = keys;
+    if sorted {
+        keys.sort();
+        keys.dedup();
+    }
+    let mut tree: B = keys.iter().cloned().collect();
+    let mut set: BTreeSet<T> = keys.iter().cloned().collect();
+
+    for stage in ["collect", "extend"] {
+        if let Err(e) = tree.validate() {
+            return Err(format!("{stage}: invalid tree: {e}"));
+        }
+        if !tree.iter().eq(set.iter()) || tree.len() != set.len() {
+            let found: Vec<&T> = tree.iter().collect();
+            return Err(format!("{stage}: tree has {found:?}, expected {set:?}"));
+        }
+        tree.extend(more.iter().cloned());
+        set.extend(more.iter().cloned());
+    }
+    Ok(())
+}
+
+// split, join, merge and the set operations on trees built from two key lists, against the same on sets
+fn set_ops<T, B>(a: Vec<T>, b: Vec<T>, key: T) -> Result<(), String>
+where
+    T: Ord + Clone + Debug + Display,
+    B: Tree<T, Compare = NaturalOrder> + FromIterator<T>,
+{
+    let set_a: BTreeSet<T> = a.iter().cloned().collect();
+    let set_b: BTreeSet<T> = b.iter().cloned().collect();
+    let tree_a = || a.iter().cloned().collect::<B>();
+    let tree_b = || b.iter().cloned().collect::<B>();
+
+    let results = [
+        ("union", tree_a().union(tree_b()), set_a.union(&set_b).cloned().collect::<BTreeSet<T>>()),
+        ("intersection", tree_a().intersection(tree_b()), set_a.intersection(&set_b).cloned().collect()),
+        ("difference", tree_a().difference(tree_b()), set_a.difference(&set_b).cloned().collect()),
+        ("symmetric_difference", tree_a().symmetric_difference(tree_b()), set_a.symmetric_difference(&set_b).cloned().collect()),
+    ];
+    let (less, found, greater) = tree_a().split(&key);
+    let (below, _, above) = tree_a().split(&key);
+    let halves = [
+        ("split less", less, set_a.range(..&key).cloned().collect()),
+        ("split greater", greater, set_a.range((Excluded(&key), Unbounded)).cloned().collect()),
+        // merging the halves gives back everything but the key
+        ("merge", B::merge(below, above), set_a.iter().filter(|k| **k != key).cloned().collect()),
+    ];
+    if found.as_ref().map(|(k, _)| k) != set_a.get(&key) {
+        return Err(format!("split: found {found:?} for {key}"));
+    }
+
+    for (name, tree, expected) in results.into_iter().chain(halves) {
+        if let Err(e) = tree.validate() {
+            return Err(format!("{name}: invalid tree: {e}"));
+        }
+        if !tree.iter().eq(expected.iter()) || tree.len() != expected.len() {
+            let keys: Vec<&T> = tree.iter().collect();
+            return Err(format!("{name}: tree has {keys:?}, expected {expected:?}"));
+        }
+    }
+
+    // joining the halves back together around the key gives the whole tree again
+    let (less, _, greater) = tree_a().split(&key);
+    let joined = B::join(less, key.clone(), greater);
+    let mut expected = set_a.clone();
+    expected.insert(key);
+    if let Err(e) = joined.validate() {
+        return Err(format!("join: invalid tree: {e}"));
+    }
+    if !joined.iter().eq(expected.iter()) {
+        return Err(format!("join: wrong keys, expected {expected:?}"));
+    }
+    Ok(())
+}
+
+// long sequences, the size of the generator is the maximum length of the operation list
+fn check<T>(prop: fn(Vec<Op<T>>) -> Result<(), String>)
+where
+    T: Arbitrary + Ord + Clone + Debug + Display,
+{
+    QuickCheck::new()
+        .tests(200)
+        .rng(Gen::new(300))
+        .quickcheck(prop);
+}
+
+fn check_set_ops<T>(prop: fn(Vec<T>, Vec<T>, T) -> Result<(), String>)
+where
+    T: Arbitrary + Ord + Clone + Debug + Display,
+{
+    QuickCheck::new()
+        .tests(200)
+        .rng(Gen::new(300))
+        .quickcheck(prop);
+}
+
+fn check_collect<T>(prop: fn(Vec<T>, Vec<T>, bool) -> Result<(), String>)
+where
+    T: Arbitrary + Ord + Clone + Debug + Display,
+{
+    QuickCheck::new()
+        .tests(200)
+        .rng(Gen::new(300))
+        .quickcheck(prop);
+}
+
+#[test]
+fn rb_tree_i8() {
+    check(run::<i8, RedBlackTree<i8>>);
+}
+
+#[test]
+fn rb_tree_i32() {
+    check(run::<i32, RedBlackTree<i32>>);
+}
+
+#[test]
+fn rb_tree_char() {
+    check(run::<char, RedBlackTree<char>>);
+}
+
+#[test]
+fn rb_tree_string() {
+    check(run::<String, RedBlackTree<String>>);
+}
+
+#[test]
+fn avl_tree_i8() {
+    check(run::<i8, AvlTree<i8>>);
+}
+
+#[test]
+fn avl_tree_i32() {
+    check(run::<i32, AvlTree<i32>>);
+}
+
+#[test]
+fn avl_tree_char() {
+    check(run::<char, AvlTree<char>>);
+}
+
+#[test]
+fn avl_tree_string() {
+    check(run::<String, AvlTree<String>>);
+}
+
+#[test]
+fn splay_tree_i8() {
+    check(run::<i8, SplayTree<i8>>);
+}
+
+#[test]
+fn splay_tree_i32() {
+    check(run::<i32, SplayTree<i32>>);
+}
+
+#[test]
+fn splay_tree_string() {
+    check(run::<String, SplayTree<String>>);
+}
+
+#[test]
+fn treap_i8() {
+    check(run::<i8, Treap<i8>>);
+}
+
+#[test]
+fn treap_i32() {
+    check(run::<i32, Treap<i32>>);
+}
+
+#[test]
+fn treap_string() {
+    check(run::<String, Treap<String>>);
+}
+
+#[test]
+fn llrb_tree_i8() {
+    check(run::<i8, LeftLeaningRedBlackTree<i8>>);
+}
+
+#[test]
+fn llrb_tree_i32() {
+    check(run::<i32, LeftLeaningRedBlackTree<i32>>);
+}
+
+#[test]
+fn llrb_tree_string() {
+    check(run::<String, LeftLeaningRedBlackTree<String>>);
+}
+
+#[test]
+fn aa_tree_i8() {
+    check(run::<i8, AaTree<i8>>);
+}
+
+#[test]
+fn aa_tree_i32() {
+    check(run::<i32, AaTree<i32>>);
+}
+
+#[test]
+fn aa_tree_string() {
+    check(run::<String, AaTree<String>>);
+}
+
+#[test]
+fn scapegoat_tree_i8() {
+    check(run::<i8, ScapegoatTree<i8>>);
+}
+
+#[test]
+fn scapegoat_tree_i32() {
+    check(run::<i32, ScapegoatTree<i32>>);
+}
+
+#[test]
+fn scapegoat_tree_string() {
+    check(run::<String, ScapegoatTree<String>>);
+}
+
+#[test]
+fn weight_balanced_tree_i8() {
+    check(run::<i8, WeightBalancedTree<i8>>);
+}
+
+#[test]
+fn weight_balanced_tree_i32() {
+    check(run::<i32, WeightBalancedTree<i32>>);
+}
+
+#[test]
+fn weight_balanced_tree_string() {
+    check(run::<String, WeightBalancedTree<String>>);
+}
+
+#[test]
+fn rb_tree_collect() {
+    check_collect(collect::<i32, RedBlackTree<i32>>);
+}
+
+#[test]
+fn avl_tree_collect() {
+    check_collect(collect::<i32, AvlTree<i32>>);
+}
+
+#[test]
+fn splay_tree_collect() {
+    check_collect(collect::<i32, SplayTree<i32>>);
+}
+
+#[test]
+fn treap_collect() {
+    check_collect(collect::<i32, Treap<i32>>);
+}
+
+#[test]
+fn llrb_tree_collect() {
+    check_collect(collect::<i32, LeftLeaningRedBlackTree<i32>>);
+}
+
+#[test]
+fn aa_tree_collect() {
+    check_collect(collect::<i32, AaTree<i32>>);
+}
+
+#[test]
+fn scapegoat_tree_collect() {
+    check_collect(collect::<i32, ScapegoatTree<i32>>);
+}
+
+#[test]
+fn weight_balanced_tree_collect() {
+    check_collect(collect::<i32, WeightBalancedTree<i32>>);
+}
+
+#[test]
+fn rb_tree_set_ops() {
+    check_set_ops(set_ops::<i8, RedBlackTree<i8>>);
+}
+
+#[test]
+fn avl_tree_set_ops() {
+    check_set_ops(set_ops::<i8, AvlTree<i8>>);
+}
+
+#[test]
+fn splay_tree_set_ops() {
+    check_set_ops(set_ops::<i8, SplayTree<i8>>);
+}
+
+#[test]
+fn treap_set_ops() {
+    check_set_ops(set_ops::<i8, Treap<i8>>);
+}
+
+#[test]
+fn llrb_tree_set_ops() {
+    check_set_ops(set_ops::<i8, LeftLeaningRedBlackTree<i8>>);
+}
+
+#[test]
+fn aa_tree_set_ops() {
+    check_set_ops(set_ops::<i8, AaTree<i8>>);
+}
+
+#[test]
+fn scapegoat_tree_set_ops() {
+    check_set_ops(set_ops::<i8, ScapegoatTree<i8>>);
+}
+
+#[test]
+fn weight_balanced_tree_set_ops() {
+    check_set_ops(set_ops::<i8, WeightBalancedTree<i8>>);
+}
+
+#[test]
+fn rb_tree_string_set_ops() {
+    check_set_ops(set_ops::<String, RedBlackTree<String>>);
+}
+
+#[test]
+fn avl_tree_string_set_ops() {
+    check_set_ops(set_ops::<String, AvlTree<String>>);
+}
+
+// string keys can be looked up by `&str` without building a `String` first
+#[test]
+fn borrowed_lookups() {
+    let mut tree: RedBlackTree<String> = ["pear", "apple", "fig"].into_iter().map(String::from).collect();
+    assert!(tree.bst_search("fig"));
+    assert_eq!(tree.rank("grape"), 2);
+    assert_eq!(tree.ceiling("b").map(String::as_str), Some("fig"));
+    let range: (Bound<&str>, Bound<&str>) = (Included("b"), Excluded("q"));
+    assert_eq!(tree.range::<str, _>(range).collect::<Vec<_>>(), ["fig", "pear"]);
+    tree.delete("apple");
+    assert_eq!(tree.first().map(String::as_str), Some("fig"));
+}
@@ -0,0 +1,59 @@
+// The DOT export has one node statement per key, with edges following the tree's links.
+use lib::avl_tree::AvlTree;
+use lib::rb_tree::RedBlackTree;
+use lib::tree::Tree;
+
+fn lines_with<'a>(dot: &'a str, pattern: &'a str) -> impl Iterator<Item = &'a str> {
+    dot.lines().filter(move |l| l.contains(pattern))
+}
+
+fn check_graph<B: Tree<i32>>(tree: &B) {
+    for show_nil in [false, true] {
+        let dot = tree.to_dot(show_nil);
+        assert!(dot.starts_with("digraph Tree {\n"));
+        assert!(dot.ends_with("}\n"));
+
+        let nodes = lines_with(&dot, " [label=").count();
+        let edges = lines_with(&dot, " -> n").filter(|l| !l.contains("-> nil")).count();
+        let nils = lines_with(&dot, "label=\"NIL\"").count();
+        assert_eq!(nodes, tree.len());
+        // every node but the root has an edge coming in
+        assert_eq!(edges, tree.len().saturating_sub(1));
+        // and a tree with n keys has n + 1 empty subtrees
+        assert_eq!(nils, if show_nil && !tree.is_empty() { tree.len() + 1 } else { 0 });
+    }
+}
+
+#[test]
+fn red_black_dot() {
+    let tree: RedBlackTree<i32> = (0..50).map(|i| (i * 37) % 101).collect();
+    check_graph(&tree);
+
+    let dot = RedBlackTree::from_sorted_iter([1, 2, 3]).to_dot(false);
+    assert!(dot.contains("[label=\"2\", style=filled, fillcolor=black, fontcolor=white];"));
+    assert_eq!(lines_with(&dot, "fillcolor=red").count(), 2);
+}
+
+#[test]
+fn avl_dot() {
+    let tree: AvlTree<i32> = (0..50).map(|i| (i * 37) % 101).collect();
+    check_graph(&tree);
+
+    let mut tree = AvlTree::new();
+    tree.insert(1);
+    tree.insert(2);
+    let dot = tree.to_dot(false);
+    assert!(dot.contains("[label=\"1\\nh=2 bf=-1\"];"));
+    assert!(dot.contains("[label=\"2\\nh=1 bf=0\"];"));
+    // the lone right child gets an invisible sibling on the left
+    assert_eq!(lines_with(&dot, "[style=invis]").count(), 2);
+}
+
+#[test]
+fn empty_and_quoted() {
+    assert_eq!(RedBlackTree::<i32>::new().to_dot(true), "digraph Tree {\n    node [shape=circle, fontname=\"Helvetica\"];\n}\n");
+
+    let mut tree = AvlTree::new();
+    tree.insert("say \"hi\"".to_string());
+    assert!(tree.to_dot(false).contains(r#"label="say \"hi\"\nh=1 bf=0""#));
+}
@@ -0,0 +1,200 @@
+// Checks that nodes and the values they hold are freed when they are deleted
+// and when the tree they belong to is dropped.
+use std::cell::Cell;
+use lib::avl_tree::AvlTree;
+use lib::comparator::NaturalOrder;
+use lib::rb_tree::RedBlackTree;
+use lib::splay_tree::SplayTree;
+use lib::treap::Treap;
+use lib::llrb_tree::LeftLeaningRedBlackTree;
+use lib::aa_tree::AaTree;
+use lib::scapegoat_tree::ScapegoatTree;
+use lib::weight_balanced_tree::WeightBalancedTree;
+use lib::tree::Tree;
+use lib::tree_map::TreeMap;
+
+thread_local! {
+    // values currently alive on this thread, tests run on separate threads so they don't interfere
+    static LIVE: Cell<usize> = const { Cell::new(0) };
+}
+
+fn live() -> usize {
+    LIVE.with(|l| l.get())
+}
+
+// a value that keeps count of how many of it exist
+struct Counted;
+
+impl Counted {
+    fn new() -> Self {
+        LIVE.with(|l| l.set(l.get() + 1));
+        Counted
+    }
+}
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        LIVE.with(|l| l.set(l.get() - 1));
+    }
+}
+
+// small xorshift so the tests don't need an rng crate
+fn keys(seed: u64, count: usize) -> Vec<i32> {
+    let mut state = seed;
+    (0..count)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % 500) as i32
+        })
+        .collect()
+}
+
+fn churn<B: Tree<i32, Counted, Compare = NaturalOrder>>() {
+    let mut tree = B::new();
+    let mut present = std::collections::BTreeSet::new();
+
+    for (i, k) in keys(42, 2000).into_iter().enumerate() {
+        if i % 3 == 0 {
+            tree.remove_entry(&k);
+            present.remove(&k);
+        } else {
+            tree.insert_entry(k, Counted::new());
+            present.insert(k);
+        }
+        // every node in the arena holds exactly one live key, nothing is left behind by deletes
+        assert_eq!(tree.nodes().len(), present.len());
+        assert_eq!(live(), present.len());
+    }
+
+    for k in present {
+        tree.remove_entry(&k);
+    }
+    assert_eq!(tree.nodes().len(), 0);
+    assert_eq!(live(), 0);
+}
+
+fn drop_full<B: Tree<i32, Counted, Compare = NaturalOrder>>() {
+    let mut tree = B::new();
+    for k in keys(7, 1000) {
+        tree.insert_entry(k, Counted::new());
+    }
+    assert!(live() > 0);
+    drop(tree);
+    assert_eq!(live(), 0);
+}
+
+#[test]
+fn rb_tree_frees_deleted_nodes() {
+    churn::<RedBlackTree<i32, Counted>>();
+}
+
+#[test]
+fn avl_tree_frees_deleted_nodes() {
+    churn::<AvlTree<i32, Counted>>();
+}
+
+#[test]
+fn splay_tree_frees_deleted_nodes() {
+    churn::<SplayTree<i32, Counted>>();
+}
+
+#[test]
+fn treap_frees_deleted_nodes() {
+    churn::<Treap<i32, Counted>>();
+}
+
+#[test]
+fn llrb_tree_frees_deleted_nodes() {
+    churn::<LeftLeaningRedBlackTree<i32, Counted>>();
+}
+
+#[test]
+fn aa_tree_frees_deleted_nodes() {
+    churn::<AaTree<i32, Counted>>();
+}
+
+#[test]
+fn scapegoat_tree_frees_deleted_nodes() {
+    churn::<ScapegoatTree<i32, Counted>>();
+}
+
+#[test]
+fn weight_balanced_tree_frees_deleted_nodes() {
+    churn::<WeightBalancedTree<i32, Counted>>();
+}
+
+#[test]
+fn rb_tree_frees_everything_on_drop() {
+    drop_full::<RedBlackTree<i32, Counted>>();
+}
+
+#[test]
+fn avl_tree_frees_everything_on_drop() {
+    drop_full::<AvlTree<i32, Counted>>();
+}
+
+#[test]
+fn splay_tree_frees_everything_on_drop() {
+    drop_full::<SplayTree<i32, Counted>>();
+}
+
+#[test]
+fn treap_frees_everything_on_drop() {
+    drop_full::<Treap<i32, Counted>>();
+}
+
+#[test]
+fn llrb_tree_frees_everything_on_drop() {
+    drop_full::<LeftLeaningRedBlackTree<i32, Counted>>();
+}
+
+#[test]
+fn aa_tree_frees_everything_on_drop() {
+    drop_full::<AaTree<i32, Counted>>();
+}
+
+#[test]
+fn scapegoat_tree_frees_everything_on_drop() {
+    drop_full::<ScapegoatTree<i32, Counted>>();
+}
+
+#[test]
+fn weight_balanced_tree_frees_everything_on_drop() {
+    drop_full::<WeightBalancedTree<i32, Counted>>();
+}
+
+#[test]
+fn partly_consumed_into_iter_frees_the_rest() {
+    let mut tree: AvlTree<i32, Counted> = AvlTree::default();
+    for k in 0..100 {
+        tree.insert_entry(k, Counted::new());
+    }
+    let mut iter = tree.into_iter();
+    assert_eq!(iter.next(), Some(0));
+    assert_eq!(iter.next_back(), Some(99));
+    drop(iter);
+    assert_eq!(live(), 0);
+}
+
+#[test]
+fn tree_map_frees_replaced_and_removed_values() {
+    let mut map: TreeMap<i32, Counted> = TreeMap::new();
+    for k in 0..100 {
+        map.insert(k, Counted::new());
+    }
+    // replacing a value hands the old one back, dropping it right away
+    for k in 0..50 {
+        map.insert(k, Counted::new());
+    }
+    assert_eq!(live(), 100);
+    for k in 0..100 {
+        if k % 2 == 0 {
+            map.remove(&k);
+        }
+    }
+    assert_eq!(live(), 50);
+    drop(map);
+    assert_eq!(live(), 0);
+}
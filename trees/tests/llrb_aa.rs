@@ -0,0 +1,110 @@
+// The left-leaning red-black tree and the AA tree are both binary forms of 2-3 trees, so their
+// sorted builds, heights and splits are checked the same way.
+use lib::aa_tree::AaTree;
+use lib::comparator::NaturalOrder;
+use lib::llrb_tree::LeftLeaningRedBlackTree;
+use lib::tree::Tree;
+
+// a 2-3 tree of n keys has at most log2(n + 1) levels, its binary form twice as many
+fn height_bound(n: usize) -> usize {
+    2 * (usize::BITS - n.leading_zeros()) as usize
+}
+
+fn sorted_builds<B: Tree<i32, Compare = NaturalOrder>>() {
+    for n in 0..300 {
+        let tree = B::from_sorted_iter(0..n);
+        if let Err(e) = tree.validate() {
+            panic!("sorted build of {n} keys: {e}");
+        }
+        assert!(tree.iter().copied().eq(0..n));
+        assert!(tree.get_height() <= height_bound(n as usize));
+    }
+}
+
+#[test]
+fn llrb_sorted_builds_of_every_size() {
+    sorted_builds::<LeftLeaningRedBlackTree<i32>>();
+}
+
+#[test]
+fn aa_sorted_builds_of_every_size() {
+    sorted_builds::<AaTree<i32>>();
+}
+
+fn ascending<B: Tree<i32, Compare = NaturalOrder>>() {
+    let n = 100_000;
+    let mut tree = B::new();
+    for k in 0..n {
+        tree.insert(k);
+    }
+    tree.validate().unwrap();
+    assert!(tree.get_height() <= height_bound(n as usize));
+
+    for k in (0..n).filter(|k| k % 3 != 0) {
+        assert_eq!(tree.delete(&k), Some(k));
+    }
+    tree.validate().unwrap();
+    assert!(tree.iter().copied().eq((0..n).step_by(3)));
+    assert!(tree.get_height() <= height_bound(tree.len()));
+}
+
+#[test]
+fn llrb_ascending_keys() {
+    ascending::<LeftLeaningRedBlackTree<i32>>();
+}
+
+#[test]
+fn aa_ascending_keys() {
+    ascending::<AaTree<i32>>();
+}
+
+fn split_and_merge<B: Tree<i32, Compare = NaturalOrder>>() {
+    let mut tree = B::new();
+    for k in (0..1000).map(|i| (i * 7919) % 1000) {
+        tree.insert(k);
+    }
+    let (less, found, greater) = tree.split(&400);
+    assert_eq!(found, Some((400, ())));
+    less.validate().unwrap();
+    greater.validate().unwrap();
+    assert!(less.iter().copied().eq(0..400));
+    assert!(greater.iter().copied().eq(401..1000));
+
+    let merged = B::merge(less, greater);
+    merged.validate().unwrap();
+    assert!(merged.iter().copied().eq((0..1000).filter(|&k| k != 400)));
+}
+
+#[test]
+fn llrb_split_and_merge() {
+    split_and_merge::<LeftLeaningRedBlackTree<i32>>();
+}
+
+#[test]
+fn aa_split_and_merge() {
+    split_and_merge::<AaTree<i32>>();
+}
+
+#[test]
+fn labels_show_colour_and_level() {
+    // the red node leans left in one tree, the horizontal link goes right in the other
+    let mut llrb = LeftLeaningRedBlackTree::new();
+    let mut aa = AaTree::new();
+    for k in [1, 2] {
+        llrb.insert(k);
+        aa.insert(k);
+    }
+    let expected = [
+        "      2 (B)",
+        "  ┌─────┘",
+        "1 (R)",
+    ];
+    assert_eq!(llrb.format_top_down(), expected.join("\n"));
+    let expected = [
+        "1 (L1)",
+        "   └──────┐",
+        "       2 (L1)",
+    ];
+    assert_eq!(aa.format_top_down(), expected.join("\n"));
+    assert!(llrb.to_dot(false).contains("fillcolor=red"));
+}
@@ -0,0 +1,114 @@
+// Multisets are checked against a `BTreeMap` from each key to its number of occurrences.
+use std::collections::BTreeMap;
+use quickcheck::{Gen, QuickCheck};
+use lib::avl_tree::AvlTree;
+use lib::multiset::TreeMultiSet;
+use lib::rb_tree::RedBlackTree;
+use lib::tree::Tree;
+
+#[derive(Clone, Debug)]
+enum Op {
+    Insert(i8),
+    RemoveOne(i8),
+    RemoveAll(i8),
+}
+
+impl quickcheck::Arbitrary for Op {
+    fn arbitrary(g: &mut Gen) -> Self {
+        // small keys so that they repeat often
+        let key = i8::arbitrary(g) % 16;
+        match u8::arbitrary(g) % 4 {
+            0 | 1 => Op::Insert(key),
+            2 => Op::RemoveOne(key),
+            _ => Op::RemoveAll(key),
+        }
+    }
+}
+
+fn run<B: Tree<i8, usize, Compare = lib::comparator::NaturalOrder>>(ops: Vec<Op>) -> Result<(), String> {
+    let mut set = TreeMultiSet::<i8, B>::new();
+    let mut model: BTreeMap<i8, usize> = BTreeMap::new();
+
+    for (step, op) in ops.into_iter().enumerate() {
+        match op {
+            Op::Insert(k) => {
+                let expected = *model.entry(k).and_modify(|c| *c += 1).or_insert(1);
+                let count = set.insert(k);
+                if count != expected {
+                    return Err(format!("step {step}: insert {k} gave count {count}, expected {expected}"));
+                }
+            }
+            Op::RemoveOne(k) => {
+                let expected = match model.get_mut(&k) {
+                    Some(1) => model.remove(&k).is_some(),
+                    Some(c) => {
+                        *c -= 1;
+                        true
+                    }
+                    None => false,
+                };
+                if set.remove_one(&k) != expected {
+                    return Err(format!("step {step}: remove_one {k} disagrees"));
+                }
+            }
+            Op::RemoveAll(k) => {
+                let expected = model.remove(&k).unwrap_or(0);
+                let removed = set.remove_all(&k);
+                if removed != expected {
+                    return Err(format!("step {step}: remove_all {k} removed {removed}, expected {expected}"));
+                }
+            }
+        }
+        if let Err(e) = set.tree().validate() {
+            return Err(format!("step {step}: invalid tree: {e}"));
+        }
+        if set.len() != model.values().sum::<usize>() || set.distinct_len() != model.len() {
+            return Err(format!("step {step}: len {} / {} disagrees", set.len(), set.distinct_len()));
+        }
+        let expected = model.iter().flat_map(|(k, &c)| std::iter::repeat_n(k, c));
+        if !set.iter().eq(expected) {
+            let keys: Vec<&i8> = set.iter().collect();
+            return Err(format!("step {step}: multiset has {keys:?}, expected {model:?}"));
+        }
+        if !set.iter().rev().eq(set.iter().collect::<Vec<_>>().into_iter().rev()) {
+            return Err(format!("step {step}: reverse iteration disagrees"));
+        }
+        if (-16..16).any(|k| set.count(&k) != model.get(&k).copied().unwrap_or(0)) {
+            return Err(format!("step {step}: counts disagree with {model:?}"));
+        }
+    }
+    Ok(())
+}
+
+fn check(prop: fn(Vec<Op>) -> Result<(), String>) {
+    QuickCheck::new()
+        .tests(200)
+        .rng(Gen::new(300))
+        .quickcheck(prop);
+}
+
+#[test]
+fn red_black_multiset() {
+    check(run::<RedBlackTree<i8, usize>>);
+}
+
+#[test]
+fn avl_multiset() {
+    check(run::<AvlTree<i8, usize>>);
+}
+
+#[test]
+fn duplicates_are_counted() {
+    let mut words: TreeMultiSet<String> = ["b", "a", "b", "c", "b"].iter().map(|s| s.to_string()).collect();
+    assert_eq!(words.len(), 5);
+    assert_eq!(words.distinct_len(), 3);
+    assert_eq!(words.count("b"), 3);
+    assert_eq!(words.iter().map(String::as_str).collect::<Vec<_>>(), ["a", "b", "b", "b", "c"]);
+
+    assert!(words.remove_one("b"));
+    assert_eq!(words.count("b"), 2);
+    assert_eq!(words.remove_all("b"), 2);
+    assert!(!words.contains("b"));
+    assert!(!words.remove_one("b"));
+    assert_eq!(words.counts().map(|(k, c)| (k.as_str(), c)).collect::<Vec<_>>(), [("a", 1), ("c", 1)]);
+}
@@ -0,0 +1,129 @@
+// Persistent trees must leave every old version readable and unchanged after an update,
+// while sharing all but a path's worth of nodes with it.
+use std::collections::{BTreeSet, HashSet};
+use std::rc::Rc;
+use quickcheck::{Gen, QuickCheck};
+use lib::persistent::{PersistentNode, PersistentTree};
+use lib::persistent_avl_tree::PersistentAvlTree;
+use lib::persistent_rb_tree::PersistentRedBlackTree;
+
+// whether to insert or delete, and the key
+type Update = (bool, i8);
+
+// every version is kept next to a set with the keys it should hold
+fn versions<P: PersistentTree<i8>>(ops: Vec<Update>) -> Result<(), String> {
+    let mut history = vec![(P::new(), BTreeSet::new())];
+    for (insert, key) in ops {
+        let (tree, set) = history.last().unwrap();
+        let mut set = set.clone();
+        let tree = if insert {
+            set.insert(key);
+            tree.insert(key)
+        } else {
+            set.remove(&key);
+            tree.delete(&key)
+        };
+        if let Err(e) = tree.validate() {
+            return Err(format!("invalid tree after {} {key}: {e}", if insert { "insert" } else { "delete" }));
+        }
+        history.push((tree, set));
+    }
+
+    for (i, (tree, set)) in history.iter().enumerate() {
+        if !tree.iter().eq(set.iter()) || tree.len() != set.len() {
+            let keys: Vec<&i8> = tree.iter().collect();
+            return Err(format!("version {i} has {keys:?}, expected {set:?}"));
+        }
+        if tree.first() != set.first() || tree.last() != set.last() {
+            return Err(format!("version {i} has the wrong first or last key"));
+        }
+    }
+    Ok(())
+}
+
+fn check(prop: fn(Vec<Update>) -> Result<(), String>) {
+    QuickCheck::new()
+        .tests(200)
+        .rng(Gen::new(300))
+        .quickcheck(prop);
+}
+
+// addresses of every node reachable from a root
+fn node_addresses<N: PersistentNode<i32, ()>>(node: Option<&Rc<N>>, out: &mut HashSet<*const N>) {
+    if let Some(n) = node {
+        out.insert(Rc::as_ptr(n));
+        node_addresses(n.left(), out);
+        node_addresses(n.right(), out);
+    }
+}
+
+// an update only allocates the nodes on its path, the rest is shared with the old version
+fn shares_structure<P: PersistentTree<i32>>() {
+    let mut tree = P::new();
+    for k in 0..1000 {
+        tree = tree.insert(k * 2);
+    }
+    let mut old = HashSet::new();
+    node_addresses(tree.root(), &mut old);
+
+    for updated in [tree.insert(501), tree.delete(&500), tree.insert(2000)] {
+        let mut new = HashSet::new();
+        node_addresses(updated.root(), &mut new);
+        let copied = new.difference(&old).count();
+        // a couple of times the height of the tree at most
+        assert!(copied <= 3 * 11, "{copied} nodes were copied for a single update");
+    }
+
+    let snapshot = tree.clone();
+    assert!(snapshot.ptr_eq(&tree));
+    assert!(tree.delete(&12345).ptr_eq(&tree));
+}
+
+#[test]
+fn rb_tree_keeps_old_versions() {
+    check(versions::<PersistentRedBlackTree<i8>>);
+}
+
+#[test]
+fn avl_tree_keeps_old_versions() {
+    check(versions::<PersistentAvlTree<i8>>);
+}
+
+#[test]
+fn rb_tree_shares_structure() {
+    shares_structure::<PersistentRedBlackTree<i32>>();
+}
+
+#[test]
+fn avl_tree_shares_structure() {
+    shares_structure::<PersistentAvlTree<i32>>();
+}
+
+#[test]
+fn values_are_kept_per_version() {
+    let empty: PersistentRedBlackTree<i32, String> = PersistentTree::new();
+    let first = empty.insert_entry(1, "one".to_string());
+    let second = first.insert_entry(1, "uno".to_string());
+    assert_eq!(first.get(&1).map(String::as_str), Some("one"));
+    assert_eq!(second.get(&1).map(String::as_str), Some("uno"));
+    assert_eq!(empty.get(&1), None);
+}
+
+#[test]
+fn owned_keys_are_looked_up_by_reference() {
+    let mut tree: PersistentAvlTree<String, usize> = PersistentTree::new();
+    for word in ["pear", "apple", "fig", "quince"] {
+        tree = tree.insert_entry(word.to_string(), word.len());
+    }
+    let without_fig = tree.delete("fig");
+    without_fig.validate().unwrap();
+    assert_eq!(tree.get("fig"), Some(&3));
+    assert!(!without_fig.contains("fig"));
+    assert_eq!(without_fig.first().map(String::as_str), Some("apple"));
+    assert_eq!(without_fig.last().map(String::as_str), Some("quince"));
+    assert!(without_fig.iter().eq(["apple", "pear", "quince"]));
+
+    let rb: PersistentRedBlackTree<String> = PersistentRedBlackTree::new().insert("b".to_string()).insert("a".to_string());
+    rb.validate().unwrap();
+    assert!(rb.delete("a").iter().eq(["b"]));
+}
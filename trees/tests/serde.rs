@@ -0,0 +1,173 @@
+// Round trips through JSON, only built with `--features serde`.
+#![cfg(feature = "serde")]
+
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use quickcheck::{Gen, QuickCheck};
+use serde_json::json;
+use lib::avl_tree::AvlTree;
+use lib::rb_tree::RedBlackTree;
+use lib::serialize::Shape;
+use lib::splay_tree::SplayTree;
+use lib::treap::Treap;
+use lib::llrb_tree::LeftLeaningRedBlackTree;
+use lib::aa_tree::AaTree;
+use lib::scapegoat_tree::ScapegoatTree;
+use lib::weight_balanced_tree::WeightBalancedTree;
+use lib::tree::Tree;
+use lib::tree_map::{AvlTreeMap, RedBlackTreeMap};
+
+fn round_trip<B>(keys: Vec<i16>) -> Result<(), String>
+where
+    B: Tree<i16> + FromIterator<i16> + serde::Serialize + serde::de::DeserializeOwned,
+{
+    let tree: B = keys.iter().copied().collect();
+    let json = serde_json::to_string(&tree).map_err(|e| e.to_string())?;
+    let set: BTreeSet<i16> = keys.into_iter().collect();
+    if json != serde_json::to_string(&set).unwrap() {
+        return Err(format!("{json} isn't the sorted keys {set:?}"));
+    }
+
+    let back: B = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+    if let Err(e) = back.validate() {
+        return Err(format!("invalid tree after reading {json}: {e}"));
+    }
+    if !back.iter().eq(set.iter()) {
+        return Err(format!("read back different keys from {json}"));
+    }
+    Ok(())
+}
+
+fn check(prop: fn(Vec<i16>) -> Result<(), String>) {
+    QuickCheck::new()
+        .tests(200)
+        .rng(Gen::new(300))
+        .quickcheck(prop);
+}
+
+#[test]
+fn red_black_round_trip() {
+    check(round_trip::<RedBlackTree<i16>>);
+}
+
+#[test]
+fn avl_round_trip() {
+    check(round_trip::<AvlTree<i16>>);
+}
+
+#[test]
+fn splay_round_trip() {
+    check(round_trip::<SplayTree<i16>>);
+}
+
+#[test]
+fn treap_round_trip() {
+    check(round_trip::<Treap<i16>>);
+}
+
+#[test]
+fn llrb_round_trip() {
+    check(round_trip::<LeftLeaningRedBlackTree<i16>>);
+}
+
+#[test]
+fn aa_round_trip() {
+    check(round_trip::<AaTree<i16>>);
+}
+
+#[test]
+fn scapegoat_round_trip() {
+    check(round_trip::<ScapegoatTree<i16>>);
+}
+
+#[test]
+fn weight_balanced_round_trip() {
+    check(round_trip::<WeightBalancedTree<i16>>);
+}
+
+#[test]
+fn unsorted_input_with_duplicates() {
+    let tree: AvlTree<i32> = serde_json::from_str("[5, 1, 4, 1, 3]").unwrap();
+    tree.validate().unwrap();
+    assert_eq!(tree.iter().copied().collect::<Vec<_>>(), [1, 3, 4, 5]);
+}
+
+#[test]
+fn input_sorted_by_the_comparator() {
+    type Largest = fn(&i32, &i32) -> Ordering;
+    #[derive(Default)]
+    struct Reverse;
+    impl lib::comparator::Comparator<i32> for Reverse {
+        fn compare(&self, a: &i32, b: &i32) -> Ordering {
+            b.cmp(a)
+        }
+    }
+
+    let tree: RedBlackTree<i32, (), Reverse> = serde_json::from_str("[9, 7, 3, 2]").unwrap();
+    tree.validate().unwrap();
+    assert_eq!(serde_json::to_string(&tree).unwrap(), "[9,7,3,2]");
+
+    let by_fn: RedBlackTree<i32, (), Largest> = RedBlackTree::with_comparator(|a: &i32, b: &i32| b.cmp(a));
+    assert_eq!(serde_json::to_string(&by_fn).unwrap(), "[]");
+}
+
+#[test]
+fn maps_are_json_objects() {
+    let mut map = RedBlackTreeMap::new();
+    map.insert("b".to_string(), 2);
+    map.insert("a".to_string(), 1);
+    let json = serde_json::to_string(&map).unwrap();
+    assert_eq!(json, r#"{"a":1,"b":2}"#);
+
+    let back: AvlTreeMap<String, i32> = serde_json::from_str(&json).unwrap();
+    back.tree().validate().unwrap();
+    assert_eq!(back.get("a"), Some(&1));
+    assert_eq!(back.get("b"), Some(&2));
+    assert_eq!(back.len(), 2);
+}
+
+#[test]
+fn exact_shape() {
+    let tree = RedBlackTree::from_sorted_iter([1, 2, 3]);
+    let shape = serde_json::to_value(Shape::new(&tree)).unwrap();
+    // a sorted build colours the bottom level red, the children are positions in the list
+    let leaf = |key| json!({"key": key, "value": null, "color": "red", "left": null, "right": null});
+    assert_eq!(shape, json!([{"key": 2, "value": null, "color": "black", "left": 1, "right": 2}, leaf(1), leaf(3)]));
+
+    let mut avl = AvlTree::new();
+    avl.insert(1);
+    avl.insert(2);
+    let shape = serde_json::to_value(Shape::new(&avl)).unwrap();
+    let right = json!({"key": 2, "value": null, "height": 1, "left": null, "right": null});
+    assert_eq!(shape, json!([{"key": 1, "value": null, "height": 2, "left": null, "right": 1}, right]));
+
+    // a splay tree node has nothing but its entry and children
+    let mut splay = SplayTree::new();
+    splay.insert(1);
+    splay.insert(2);
+    let shape = serde_json::to_value(Shape::new(&splay)).unwrap();
+    let left = json!({"key": 1, "value": null, "left": null, "right": null});
+    assert_eq!(shape, json!([{"key": 2, "value": null, "left": 1, "right": null}, left]));
+
+    let empty = RedBlackTree::<i32>::new();
+    assert_eq!(serde_json::to_string(&Shape::new(&empty)).unwrap(), "[]");
+}
+
+#[test]
+fn exact_shape_of_a_path() {
+    // sorted inserts leave a splay tree that is one long path down to the left
+    let n = 100_000;
+    let mut splay = SplayTree::new();
+    for k in 0..n {
+        splay.insert(k);
+    }
+    let json = serde_json::to_string(&Shape::new(&splay)).unwrap();
+    let nodes: Vec<serde_json::Value> = serde_json::from_str(&json).unwrap();
+    assert_eq!(nodes.len(), n as usize);
+    for (i, node) in nodes.iter().enumerate() {
+        assert_eq!(node["key"], json!(n - 1 - i as i32));
+        assert_eq!(node["right"], json!(null));
+    }
+    assert_eq!(nodes[0]["left"], json!(1));
+    assert_eq!(nodes[n as usize - 1]["left"], json!(null));
+}
@@ -0,0 +1,149 @@
+// Splaying moves whatever was last touched to the root without changing the keys in the tree.
+use std::collections::BTreeSet;
+use quickcheck::{Gen, QuickCheck};
+use lib::splay_tree::SplayTree;
+use lib::tree::Tree;
+
+fn root_key(tree: &SplayTree<i32>) -> Option<i32> {
+    tree.get_root().map(|r| *tree.get_key(r))
+}
+
+// whether to look up or delete, and the key
+type Access = (bool, i8);
+
+fn accesses(keys: Vec<i8>, ops: Vec<Access>) -> Result<(), String> {
+    let mut tree: SplayTree<i32> = keys.iter().map(|&k| k as i32).collect();
+    let mut set: BTreeSet<i32> = keys.iter().map(|&k| k as i32).collect();
+
+    for (step, (delete, key)) in ops.into_iter().enumerate() {
+        let key = key as i32;
+        if delete {
+            if tree.delete(&key) != set.take(&key) {
+                return Err(format!("step {step}: delete {key} disagrees"));
+            }
+        } else {
+            if tree.search(&key) != set.contains(&key) {
+                return Err(format!("step {step}: search {key} disagrees"));
+            }
+            // a key that was found ends up at the root, a missing one leaves a neighbour there
+            let root = root_key(&tree);
+            let neighbours = [set.range(..=key).next_back(), set.range(key..).next()];
+            if !set.is_empty() && !neighbours.contains(&root.as_ref()) {
+                return Err(format!("step {step}: searching {key} left {root:?} at the root"));
+            }
+        }
+        if let Err(e) = tree.validate() {
+            return Err(format!("step {step}: invalid tree: {e}"));
+        }
+        if !tree.iter().eq(set.iter()) {
+            return Err(format!("step {step}: keys disagree"));
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn search_splays_to_the_root() {
+    QuickCheck::new()
+        .tests(200)
+        .rng(Gen::new(300))
+        .quickcheck(accesses as fn(Vec<i8>, Vec<Access>) -> Result<(), String>);
+}
+
+#[test]
+fn inserts_and_lookups_move_keys_up() {
+    let mut tree = SplayTree::new();
+    for k in [5, 3, 8, 1, 4] {
+        tree.insert(k);
+        assert_eq!(root_key(&tree), Some(k));
+    }
+    assert!(tree.search(&8));
+    assert_eq!(root_key(&tree), Some(8));
+    // the lookup the CLI and `insert` go through splays as well
+    assert!(Tree::search(&mut tree, &3));
+    assert_eq!(root_key(&tree), Some(3));
+    assert!(!tree.insert(4));
+    assert_eq!(root_key(&tree), Some(4));
+
+    let mut map = SplayTree::<&str, i32>::default();
+    map.insert_entry("a", 1);
+    map.insert_entry("b", 2);
+    *map.get_mut("a").unwrap() += 10;
+    assert_eq!(map.get("a"), Some(&11));
+    assert_eq!(map.get("c"), None);
+}
+
+#[test]
+fn sorted_inserts_make_a_path_without_overflowing() {
+    // inserting in order leaves a single long path, which everything has to cope with
+    let n = 100_000;
+    let mut tree = SplayTree::new();
+    for k in 0..n {
+        tree.insert(k);
+    }
+    assert_eq!(tree.get_height(), n as usize);
+    assert_eq!(tree.count_leaves(), 1);
+    tree.validate().unwrap();
+
+    // deep nodes are drawn at a fixed indentation with their depth written out
+    let sideways = tree.format_sideways();
+    assert_eq!(sideways.lines().count(), n as usize);
+    assert!(sideways.lines().next().unwrap().ends_with(&format!("[depth {}] 0", n - 1)));
+    assert!(sideways.lines().all(|line| line.chars().count() < 300));
+
+    // moving half of the path into a new tree and back
+    let (less, found, greater) = tree.split(&(n / 2));
+    assert_eq!(found, Some((n / 2, ())));
+    tree = SplayTree::merge(less, greater);
+    tree.insert(n / 2);
+    tree.validate().unwrap();
+
+    // one search from the bottom roughly halves the depth of the path
+    assert!(tree.search(&0));
+    assert!(tree.get_height() <= n as usize / 2 + 2);
+    for k in (0..n).step_by(2) {
+        assert_eq!(tree.delete(&k), Some(k));
+    }
+    assert!(tree.iter().copied().eq((1..n).step_by(2)));
+    tree.validate().unwrap();
+
+    // the set operations on two paths, one of the odd keys and one of the multiples of three
+    let path = |keys: Vec<i32>| {
+        let mut tree = SplayTree::new();
+        for k in keys {
+            tree.insert(k);
+        }
+        tree
+    };
+    let odd = || path((1..n).step_by(2).collect());
+    let threes = || path((0..n).step_by(3).collect());
+    let union = odd().union(threes());
+    assert!(union.iter().copied().eq((0..n).filter(|k| k % 2 == 1 || k % 3 == 0)));
+    let intersection = odd().intersection(threes());
+    assert!(intersection.iter().copied().eq((0..n).filter(|k| k % 2 == 1 && k % 3 == 0)));
+    let difference = odd().difference(threes());
+    assert!(difference.iter().copied().eq((0..n).filter(|k| k % 2 == 1 && k % 3 != 0)));
+    let symmetric_difference = odd().symmetric_difference(threes());
+    assert!(symmetric_difference.iter().copied().eq((0..n).filter(|k| (k % 2 == 1) != (k % 3 == 0))));
+    for result in [union, intersection, difference, symmetric_difference] {
+        result.validate().unwrap();
+    }
+}
+
+#[test]
+fn set_operations_keep_the_entries_of_self() {
+    // the smaller tree is the one walked through, whichever side it is on
+    let map = |keys: std::ops::Range<i32>, value: &'static str| {
+        let mut tree = SplayTree::<i32, &str>::default();
+        for k in keys {
+            tree.insert_entry(k, value);
+        }
+        tree
+    };
+    for (a, b) in [(0..10, 5..100), (0..100, 5..10)] {
+        let union = map(a.clone(), "a").union(map(b.clone(), "b"));
+        assert!(union.entries().all(|(k, v)| *v == if a.contains(k) { "a" } else { "b" }));
+        let intersection = map(a.clone(), "a").intersection(map(b.clone(), "b"));
+        assert!(intersection.entries().map(|(k, v)| (*k, *v)).eq((5..10).map(|k| (k, "a"))));
+    }
+}
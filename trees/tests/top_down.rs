@@ -0,0 +1,67 @@
+// The top-down drawing puts every key on the line of its depth, in key order from left to right.
+use lib::avl_tree::AvlTree;
+use lib::rb_tree::RedBlackTree;
+use lib::tree::Tree;
+
+#[test]
+fn avl_drawing() {
+    let tree: AvlTree<i32> = (1..=7).collect();
+    let expected = [
+        "      4",
+        "  ┌───┴───┐",
+        "  2       6",
+        "┌─┴─┐   ┌─┴─┐",
+        "1   3   5   7",
+    ];
+    assert_eq!(tree.format_top_down(), expected.join("\n"));
+}
+
+#[test]
+fn red_black_labels_show_colours() {
+    let mut tree = RedBlackTree::new();
+    for k in [10, 5, 200] {
+        tree.insert(k);
+    }
+    let expected = [
+        "      10 (B)",
+        "  ┌──────┴──────┐",
+        "5 (R)        200 (R)",
+    ];
+    assert_eq!(tree.format_top_down(), expected.join("\n"));
+}
+
+#[test]
+fn wide_keys_and_lone_children() {
+    let mut tree = AvlTree::new();
+    for k in ["longer key", "a", "zz", "b"] {
+        tree.insert(k.to_string());
+    }
+    let expected = [
+        "    longer key",
+        "┌────────┴──────┐",
+        "a              zz",
+        "└─┐",
+        "  b",
+    ];
+    assert_eq!(tree.format_top_down(), expected.join("\n"));
+    assert_eq!(AvlTree::<i32>::new().format_top_down(), "");
+}
+
+#[test]
+fn keys_are_in_order_on_their_levels() {
+    let tree: RedBlackTree<i32> = (0..200).map(|i| (i * 73) % 211).collect();
+    let drawing = tree.format_top_down();
+    let mut keys: Vec<(usize, i32)> = Vec::new();
+    for line in drawing.lines().step_by(2) {
+        let mut column = 0;
+        for word in line.split(' ') {
+            if let Ok(k) = word.parse::<i32>() {
+                keys.push((column, k));
+            }
+            column += word.chars().count() + 1;
+        }
+    }
+    // sorting the labels by their column gives the keys in order
+    keys.sort();
+    assert!(keys.iter().map(|&(_, k)| k).eq(tree.iter().copied()));
+}
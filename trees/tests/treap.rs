@@ -0,0 +1,97 @@
+// Treap priorities come from a seeded generator, so a seed always gives the same tree.
+use lib::treap::Treap;
+use lib::tree::Tree;
+
+fn shape(tree: &Treap<i32>) -> String {
+    tree.format_top_down()
+}
+
+fn shuffled(n: i32) -> impl Iterator<Item = i32> {
+    (0..n).map(move |i| (i * 7919) % n)
+}
+
+#[test]
+fn same_seed_same_tree() {
+    let build = |seed| {
+        let mut tree = Treap::with_seed(seed);
+        tree.extend(shuffled(500));
+        for k in (0..500).step_by(3) {
+            tree.delete(&k);
+        }
+        tree
+    };
+    assert_eq!(shape(&build(1)), shape(&build(1)));
+    assert_ne!(shape(&build(1)), shape(&build(2)));
+
+    let mut reseeded = Treap::new();
+    reseeded.reseed(1);
+    reseeded.extend(shuffled(500));
+    for k in (0..500).step_by(3) {
+        reseeded.delete(&k);
+    }
+    assert_eq!(shape(&reseeded), shape(&build(1)));
+}
+
+#[test]
+fn split_halves_keep_the_seed() {
+    // the half a split hands out draws its priorities from the tree's generator, not the default seed
+    let build = |seed| {
+        let mut tree = Treap::with_seed(seed);
+        tree.extend(shuffled(500).map(|k| k + 1000));
+        let (mut less, _, mut greater) = tree.split(&0);
+        assert!(less.is_empty());
+        less.extend(shuffled(500));
+        greater.extend(shuffled(500).map(|k| k + 2000));
+        less.validate().unwrap();
+        greater.validate().unwrap();
+        (shape(&less), shape(&greater))
+    };
+    assert_eq!(build(1), build(1));
+    assert_ne!(build(1).0, build(2).0);
+    assert_ne!(build(1).1, build(2).1);
+}
+
+#[test]
+fn sorted_inserts_stay_shallow() {
+    // a plain BST would be a path here, random priorities keep the expected depth logarithmic
+    let n = 100_000;
+    let mut tree = Treap::new();
+    for k in 0..n {
+        tree.insert(k);
+    }
+    tree.validate().unwrap();
+    assert!(tree.get_height() < 60, "height {}", tree.get_height());
+
+    // the sorted build is a treap on random priorities too, not the perfectly balanced shape
+    let built = Treap::from_sorted_iter(0..n);
+    built.validate().unwrap();
+    assert!(built.get_height() > 17 && built.get_height() < 60, "height {}", built.get_height());
+    assert!(built.iter().copied().eq(0..n));
+}
+
+#[test]
+fn split_and_merge() {
+    let tree: Treap<i32> = shuffled(1000).collect();
+    let (less, found, greater) = tree.split(&400);
+    assert_eq!(found, Some((400, ())));
+    less.validate().unwrap();
+    greater.validate().unwrap();
+    assert!(less.iter().copied().eq(0..400));
+    assert!(greater.iter().copied().eq(401..1000));
+
+    let merged = Treap::merge(less, greater);
+    merged.validate().unwrap();
+    assert_eq!(merged.len(), 999);
+    assert!(merged.iter().copied().eq((0..1000).filter(|&k| k != 400)));
+
+    let merged = Treap::merge(Treap::new(), merged);
+    assert_eq!(merged.len(), 999);
+}
+
+#[test]
+#[should_panic(expected = "keys of the left tree must be less")]
+fn merge_checks_the_order() {
+    let low: Treap<i32> = (0..10).collect();
+    let high: Treap<i32> = (5..20).collect();
+    Treap::merge(low, high);
+}
@@ -0,0 +1,162 @@
+// The map API, entries included, driven through random operation sequences next to a `BTreeMap`.
+// Keys come from a narrow type so that most operations find the key already in the map.
+use std::collections::btree_map::{self, BTreeMap};
+use quickcheck::{Arbitrary, Gen, QuickCheck};
+use lib::avl_tree::AvlTree;
+use lib::comparator::NaturalOrder;
+use lib::rb_tree::RedBlackTree;
+use lib::tree::Tree;
+use lib::tree_map::{AvlTreeMap, Entry, RedBlackTreeMap, TreeMap};
+
+#[derive(Clone, Debug)]
+enum Op {
+    Insert(i8, i32),
+    // add to the value through `get_mut`
+    GetMut(i8, i32),
+    OrInsert(i8, i32),
+    // double the value if it is there, otherwise insert it
+    AndModify(i8, i32),
+    OrDefault(i8),
+    // replace the value through an occupied entry
+    OccupiedInsert(i8, i32),
+    OccupiedRemove(i8),
+    // insert through a vacant entry and add one through the reference it hands back
+    VacantInsert(i8, i32),
+}
+
+impl Arbitrary for Op {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let (k, v) = (i8::arbitrary(g) % 16, i32::arbitrary(g));
+        match u8::arbitrary(g) % 8 {
+            0 => Op::Insert(k, v),
+            1 => Op::GetMut(k, v),
+            2 => Op::OrInsert(k, v),
+            3 => Op::AndModify(k, v),
+            4 => Op::OrDefault(k),
+            5 => Op::OccupiedInsert(k, v),
+            6 => Op::OccupiedRemove(k),
+            _ => Op::VacantInsert(k, v),
+        }
+    }
+}
+
+// run the operations on a map and a `BTreeMap`, returning a description of the first disagreement
+fn run<B: Tree<i8, i32, Compare = NaturalOrder>>(ops: Vec<Op>) -> Result<(), String> {
+    let mut map: TreeMap<i8, i32, B> = TreeMap::new();
+    let mut model = BTreeMap::new();
+
+    for (step, op) in ops.iter().enumerate() {
+        let (got, expected) = match *op {
+            Op::Insert(k, v) => (map.insert(k, v), model.insert(k, v)),
+            Op::GetMut(k, v) => {
+                let add = |x: &mut i32| {
+                    *x = x.wrapping_add(v);
+                    *x
+                };
+                (map.get_mut(&k).map(add), model.get_mut(&k).map(add))
+            }
+            Op::OrInsert(k, v) => (Some(*map.entry(k).or_insert(v)), Some(*model.entry(k).or_insert(v))),
+            Op::AndModify(k, v) => {
+                let double = |x: &mut i32| *x = x.wrapping_mul(2);
+                (Some(*map.entry(k).and_modify(double).or_insert(v)), Some(*model.entry(k).and_modify(double).or_insert(v)))
+            }
+            Op::OrDefault(k) => (Some(*map.entry(k).or_default()), Some(*model.entry(k).or_default())),
+            Op::OccupiedInsert(k, v) => {
+                let got = match map.entry(k) {
+                    Entry::Occupied(mut e) => Some(e.insert(v)),
+                    Entry::Vacant(_) => None,
+                };
+                let expected = match model.entry(k) {
+                    btree_map::Entry::Occupied(mut e) => Some(e.insert(v)),
+                    btree_map::Entry::Vacant(_) => None,
+                };
+                (got, expected)
+            }
+            Op::OccupiedRemove(k) => {
+                let got = match map.entry(k) {
+                    Entry::Occupied(e) => {
+                        if *e.key() != k {
+                            return Err(format!("step {step} {op:?}: the entry has key {}", e.key()));
+                        }
+                        Some(e.remove())
+                    }
+                    Entry::Vacant(_) => None,
+                };
+                (got, model.remove(&k))
+            }
+            Op::VacantInsert(k, v) => {
+                let got = match map.entry(k) {
+                    Entry::Vacant(e) => {
+                        let value = e.insert(v);
+                        *value = value.wrapping_add(1);
+                        Some(*value)
+                    }
+                    Entry::Occupied(_) => None,
+                };
+                let expected = match model.entry(k) {
+                    btree_map::Entry::Vacant(e) => {
+                        let value = e.insert(v);
+                        *value = value.wrapping_add(1);
+                        Some(*value)
+                    }
+                    btree_map::Entry::Occupied(_) => None,
+                };
+                (got, expected)
+            }
+        };
+        if got != expected {
+            return Err(format!("step {step} {op:?}: got {got:?}, expected {expected:?}"));
+        }
+
+        if let Err(e) = map.tree().validate() {
+            return Err(format!("step {step} {op:?}: {e:?}"));
+        }
+        if !map.tree().entries().eq(model.iter()) {
+            return Err(format!("step {step} {op:?}: the entries differ from the model"));
+        }
+        if map.len() != model.len() {
+            return Err(format!("step {step} {op:?}: len {} instead of {}", map.len(), model.len()));
+        }
+    }
+    Ok(())
+}
+
+fn check(prop: fn(Vec<Op>) -> Result<(), String>) {
+    QuickCheck::new()
+        .tests(200)
+        .rng(Gen::new(300))
+        .quickcheck(prop);
+}
+
+#[test]
+fn red_black_tree_map() {
+    check(run::<RedBlackTree<i8, i32>>);
+}
+
+#[test]
+fn avl_tree_map() {
+    check(run::<AvlTree<i8, i32>>);
+}
+
+#[test]
+fn lookups_and_entries() {
+    let mut map = RedBlackTreeMap::new();
+    assert_eq!(map.insert("a", 1), None);
+    assert_eq!(map.insert("a", 2), Some(1));
+    assert_eq!(map.get("a"), Some(&2));
+    *map.get_mut("a").unwrap() += 1;
+    assert_eq!(map.get("a"), Some(&3));
+    assert_eq!(map.get_mut("b"), None);
+
+    *map.entry("b").or_insert_with(|| 10) += 1;
+    assert_eq!(*map.entry("b").and_modify(|v| *v *= 2).or_insert(0), 22);
+    assert_eq!(map.entry("c").key(), &"c");
+
+    let mut avl = AvlTreeMap::new();
+    avl.insert(String::from("x"), 5);
+    match avl.entry(String::from("x")) {
+        Entry::Occupied(e) => assert_eq!(e.remove_entry(), (String::from("x"), 5)),
+        Entry::Vacant(_) => panic!("x is in the map"),
+    }
+    assert!(avl.is_empty());
+}